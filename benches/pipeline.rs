@@ -1,9 +1,12 @@
 use std::num::NonZero;
+use std::sync::OnceLock;
 use std::time::Duration;
 
 use divan::Bencher;
+use rodio::buffer::SamplesBuffer;
+use rodio::mixer::MixerSource;
 use rodio::ChannelCount;
-use rodio::{source::UniformSourceIterator, Source};
+use rodio::{source::UniformSourceIterator, Sample, Source};
 
 mod shared;
 use shared::music_wav;
@@ -46,3 +49,85 @@ fn short(bencher: Bencher) {
             .for_each(divan::black_box_drop)
     })
 }
+
+/// Drains `source` through [`Source::fill_buffer`] in blocks of 512 samples.
+fn drain_blocks(mut source: impl Source) {
+    let mut buffer = [0.0; 512];
+    loop {
+        let written = source.fill_buffer(&mut buffer);
+        if written == 0 {
+            break;
+        }
+        divan::black_box(&buffer[..written]);
+    }
+}
+
+#[divan::bench]
+fn short_block(bencher: Bencher) {
+    bencher
+        .with_inputs(music_wav)
+        .bench_values(|source| drain_blocks(source.amplify(1.2).low_pass(200)))
+}
+
+#[divan::bench]
+fn mixer(bencher: Bencher) {
+    bencher.with_inputs(mixed_sources).bench_values(|output| {
+        output.for_each(divan::black_box_drop);
+    })
+}
+
+#[divan::bench]
+fn mixer_block(bencher: Bencher) {
+    bencher
+        .with_inputs(mixed_sources)
+        .bench_values(drain_blocks)
+}
+
+/// A mixer playing the music three times, once from a mono copy that has to be
+/// converted.
+fn mixed_sources() -> MixerSource {
+    let music = music_wav();
+    let (channels, sample_rate) = (music.channels(), music.sample_rate());
+    let mono: Vec<Sample> =
+        UniformSourceIterator::new(music_wav(), ChannelCount::new(1).unwrap(), sample_rate)
+            .collect();
+
+    let (mixer, output) = rodio::mixer::mixer(channels, sample_rate);
+    mixer.add(music.amplify(0.5));
+    mixer.add(music_wav().pausable(false));
+    mixer.add(SamplesBuffer::new(
+        ChannelCount::new(1).unwrap(),
+        sample_rate,
+        mono,
+    ));
+    output
+}
+
+#[divan::bench]
+fn many_voices(bencher: Bencher) {
+    bencher.with_inputs(voices).bench_values(|output| {
+        output.for_each(divan::black_box_drop);
+    })
+}
+
+#[divan::bench]
+fn many_voices_block(bencher: Bencher) {
+    bencher.with_inputs(voices).bench_values(drain_blocks)
+}
+
+/// A mixer playing 128 voices at once, each one second of the music.
+fn voices() -> MixerSource {
+    static CLIP: OnceLock<SamplesBuffer> = OnceLock::new();
+    let clip = CLIP.get_or_init(|| {
+        let music = music_wav();
+        let (channels, sample_rate) = (music.channels(), music.sample_rate());
+        let clip = music.take_duration(Duration::from_secs(1));
+        SamplesBuffer::new(channels, sample_rate, clip.collect::<Vec<_>>())
+    });
+
+    let (mixer, output) = rodio::mixer::mixer(clip.channels(), clip.sample_rate());
+    for _ in 0..128 {
+        mixer.add(clip.clone().amplify(0.01));
+    }
+    output
+}
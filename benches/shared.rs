@@ -47,6 +47,17 @@ impl Source for TestSource {
     fn total_duration(&self) -> Option<Duration> {
         Some(self.total_duration)
     }
+
+    #[inline]
+    fn fill_buffer(&mut self, buffer: &mut [Sample]) -> usize {
+        let remaining = self.samples.as_slice();
+        let len = buffer.len().min(remaining.len());
+        buffer[..len].copy_from_slice(&remaining[..len]);
+        if len > 0 {
+            self.samples.nth(len - 1);
+        }
+        len
+    }
 }
 
 pub fn music_wav() -> TestSource {
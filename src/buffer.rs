@@ -59,6 +59,23 @@ impl SamplesBuffer {
         }
     }
 
+    /// Reverses the order of the frames in the buffer and rewinds it, so it plays
+    /// backwards from the start.
+    ///
+    /// The samples are reversed in place unless the buffer is shared with a clone.
+    pub fn reversed(mut self) -> Self {
+        let channels = self.channels.get() as usize;
+        let data = Arc::make_mut(&mut self.data);
+        let frames_len = data.len() / channels * channels;
+        let frames = &mut data[..frames_len];
+        frames.reverse();
+        for frame in frames.chunks_exact_mut(channels) {
+            frame.reverse();
+        }
+        self.pos = 0;
+        self
+    }
+
     pub(crate) fn record_source(source: impl Source) -> Self {
         let channel_count = source.channels();
         let sample_rate = source.sample_rate();
@@ -96,6 +113,15 @@ impl Source for SamplesBuffer {
         Some(self.duration)
     }
 
+    #[inline]
+    fn fill_buffer(&mut self, buffer: &mut [Sample]) -> usize {
+        let remaining = self.data.get(self.pos..).unwrap_or_default();
+        let len = buffer.len().min(remaining.len());
+        buffer[..len].copy_from_slice(&remaining[..len]);
+        self.pos += len;
+        len
+    }
+
     /// This jumps in memory till the sample for `pos`.
     #[inline]
     fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
@@ -139,6 +165,17 @@ impl Iterator for SamplesBuffer {
 
 impl ExactSizeIterator for SamplesBuffer {}
 
+#[cfg(test)]
+impl SamplesBuffer {
+    /// A buffer of `frames` frames whose samples count up from zero, so tests
+    /// can tell where each sample ended up.
+    pub(crate) fn ramp(channels: ChannelCount, sample_rate: SampleRate, frames: usize) -> Self {
+        let len = frames * channels.get() as usize;
+        let samples: Vec<Sample> = (0..len).map(|i| i as Sample).collect();
+        SamplesBuffer::new(channels, sample_rate, samples)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::buffer::SamplesBuffer;
@@ -170,6 +207,17 @@ mod tests {
         assert_eq!(buf.next(), None);
     }
 
+    #[test]
+    fn reversed() {
+        let buf = SamplesBuffer::new(nz!(2), nz!(44100), vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        let clone = buf.clone();
+        assert_eq!(
+            buf.reversed().collect::<Vec<_>>(),
+            [5.0, 6.0, 3.0, 4.0, 1.0, 2.0]
+        );
+        assert_eq!(clone.collect::<Vec<_>>(), [1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+    }
+
     #[cfg(test)]
     mod try_seek {
         use super::*;
@@ -46,6 +46,52 @@ where
     pub fn inner_mut(&mut self) -> &mut I {
         &mut self.input
     }
+
+    /// Converts whole frames at once. `read` fills a slice with input samples and
+    /// returns how many it wrote.
+    ///
+    /// Returns `None` if the conversion can not be done in a block because a
+    /// frame is partially converted or `buffer` can not hold a whole frame.
+    pub(crate) fn fill_buffer(
+        &mut self,
+        buffer: &mut [Sample],
+        scratch: &mut Vec<Sample>,
+        read: impl FnOnce(&mut I, &mut [Sample]) -> usize,
+    ) -> Option<usize> {
+        if self.next_output_sample_pos != 0 {
+            return None;
+        }
+        if self.from == self.to {
+            return Some(read(&mut self.input, buffer));
+        }
+
+        let from = self.from.get() as usize;
+        let to = self.to.get() as usize;
+        let frames = buffer.len() / to;
+        if frames == 0 {
+            return None;
+        }
+        scratch.resize(frames * from, 0.0);
+        let read = read(&mut self.input, scratch);
+
+        let whole_frames = read / from;
+        for (input, output) in scratch[..read]
+            .chunks(from)
+            .zip(buffer.chunks_exact_mut(to))
+        {
+            for (channel, sample) in output.iter_mut().enumerate() {
+                *sample = match channel {
+                    c if c < from => input.get(c).copied().unwrap_or(0.0),
+                    // mono -> stereo conversion
+                    1 => input[0],
+                    _ => 0.0,
+                };
+            }
+        }
+        // Like `next`, a trailing partial frame is cut short.
+        let tail = (read % from).min(to);
+        Some(whole_frames * to + tail)
+    }
 }
 
 impl<I> Iterator for ChannelCountConverter<I>
@@ -142,6 +188,36 @@ mod test {
         assert_eq!(output, [1.0, 2.0, 0.0, 0.0, 3.0, 4.0, 0.0, 0.0]);
     }
 
+    #[test]
+    fn fill_buffer_matches_next() {
+        let input: Vec<Sample> = (1..=13).map(|x| x as Sample).collect();
+        for (from, to) in [(1, 2), (2, 1), (2, 3), (3, 2), (2, 2)] {
+            let from = ChannelCount::new(from).unwrap();
+            let to = ChannelCount::new(to).unwrap();
+            let expected: Vec<_> =
+                ChannelCountConverter::new(input.iter().copied(), from, to).collect();
+
+            let mut converter = ChannelCountConverter::new(input.iter().copied(), from, to);
+            let mut scratch = Vec::new();
+            let mut buffer = [0.0; 7];
+            let written = converter
+                .fill_buffer(&mut buffer, &mut scratch, |input, buffer| {
+                    buffer
+                        .iter_mut()
+                        .zip(input)
+                        .map(|(slot, sample)| *slot = sample)
+                        .count()
+                })
+                .unwrap();
+            assert_eq!(buffer[..written], expected[..written]);
+            assert_eq!(
+                converter.collect::<Vec<_>>(),
+                expected[written..],
+                "from {from} to {to}"
+            );
+        }
+    }
+
     #[test]
     fn size_hint() {
         fn test(input: &[Sample], from: ChannelCount, to: ChannelCount) {
@@ -107,6 +107,12 @@ where
         &self.input
     }
 
+    /// Whether the samples are passed on unchanged.
+    #[inline]
+    pub(crate) fn is_passthrough(&self) -> bool {
+        self.from == self.to
+    }
+
     fn next_input_span(&mut self) {
         self.current_span_pos_in_chunk += 1;
 
@@ -36,7 +36,11 @@
 //! - `seekable` - Whether seeking operations are enabled
 //! - `gapless` - Enable gapless playback
 //! - `coarse_seek` - Use faster but less precise seeking
+//! - `track` - Which track of a multi-track container to decode
+//! - `stream_buffer` - How much audio a [`StreamDecoder`] decodes ahead
 
+#[cfg(feature = "symphonia")]
+use std::fmt;
 use std::io::{Read, Seek};
 
 #[cfg(feature = "symphonia")]
@@ -44,6 +48,7 @@ use self::read_seek_source::ReadSeekSource;
 #[cfg(feature = "symphonia")]
 use ::symphonia::core::io::{MediaSource, MediaSourceStream};
 
+use super::stream::LookAhead;
 use super::*;
 
 /// Audio decoder configuration settings.
@@ -77,6 +82,41 @@ pub struct Settings {
 
     /// Whether the decoder should report as seekable.
     pub(crate) is_seekable: bool,
+
+    /// Which track of the container to decode.
+    #[cfg(feature = "symphonia")]
+    pub(crate) track: TrackSelection,
+
+    /// Whether the input is a live stream, on which the decoder skips damaged
+    /// data instead of ending.
+    pub(crate) is_stream: bool,
+
+    /// How much decoded audio a [`StreamDecoder`] buffers ahead of playback.
+    pub(crate) stream_buffer: Duration,
+}
+
+/// How the Symphonia decoder picks the track to decode.
+#[cfg(feature = "symphonia")]
+#[derive(Clone, Default)]
+pub(crate) enum TrackSelection {
+    /// The first track with a codec that can be decoded.
+    #[default]
+    FirstSupported,
+    /// The track with this id.
+    Id(u32),
+    /// The first track the function accepts.
+    Selector(Arc<dyn Fn(&TrackInfo) -> bool + Send + Sync>),
+}
+
+#[cfg(feature = "symphonia")]
+impl fmt::Debug for TrackSelection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrackSelection::FirstSupported => f.write_str("FirstSupported"),
+            TrackSelection::Id(id) => f.debug_tuple("Id").field(id).finish(),
+            TrackSelection::Selector(_) => f.write_str("Selector"),
+        }
+    }
 }
 
 impl Default for Settings {
@@ -88,6 +128,10 @@ impl Default for Settings {
             hint: None,
             mime_type: None,
             is_seekable: false,
+            #[cfg(feature = "symphonia")]
+            track: TrackSelection::default(),
+            is_stream: false,
+            stream_buffer: Duration::from_secs(1),
         }
     }
 }
@@ -132,7 +176,7 @@ impl<R> Default for DecoderBuilder<R> {
     }
 }
 
-impl<R: Read + Seek + Send + Sync + 'static> DecoderBuilder<R> {
+impl<R> DecoderBuilder<R> {
     /// Creates a new decoder builder with default settings.
     ///
     /// # Examples
@@ -263,6 +307,60 @@ impl<R: Read + Seek + Send + Sync + 'static> DecoderBuilder<R> {
         self
     }
 
+    /// Decodes the track with this id instead of the first supported one.
+    /// Building fails with [`DecoderError::NoSuchTrack`] if the container has no
+    /// such track.
+    ///
+    /// The ids of the available tracks can be listed with [`Decoder::tracks`].
+    #[cfg(feature = "symphonia")]
+    pub fn with_track(mut self, id: u32) -> Self {
+        self.settings.track = TrackSelection::Id(id);
+        self
+    }
+
+    /// Decodes the first track `selector` accepts, for example to pick an
+    /// audio track by language. Building fails with
+    /// [`DecoderError::NoSuchTrack`] if it accepts none of them.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use std::fs::File;
+    /// use rodio::Decoder;
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let file = File::open("movie.mkv")?;
+    ///     let decoder = Decoder::builder()
+    ///         .with_data(file)
+    ///         .with_track_selector(|track| {
+    ///             track.is_supported() && track.language() == Some("eng")
+    ///         })
+    ///         .build()?;
+    ///
+    ///     // Use the decoder...
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(feature = "symphonia")]
+    pub fn with_track_selector(
+        mut self,
+        selector: impl Fn(&TrackInfo) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.settings.track = TrackSelection::Selector(Arc::new(selector));
+        self
+    }
+
+    /// Sets how much decoded audio a [`StreamDecoder`] keeps ahead of playback.
+    /// This is one second by default.
+    ///
+    /// A larger buffer rides out longer network stalls, at the cost of memory and
+    /// a longer wait before playback resumes after one.
+    pub fn with_stream_buffer(mut self, duration: Duration) -> Self {
+        self.settings.stream_buffer = duration;
+        self
+    }
+}
+
+impl<R: Read + Seek + Send + Sync + 'static> DecoderBuilder<R> {
     /// Creates the decoder implementation with configured settings.
     fn build_impl(self) -> Result<(DecoderImpl<R>, Settings), DecoderError> {
         let data = self.data.ok_or(DecoderError::UnrecognizedFormat)?;
@@ -330,3 +428,39 @@ impl<R: Read + Seek + Send + Sync + 'static> DecoderBuilder<R> {
         })
     }
 }
+
+impl<R: Read + Send + 'static> DecoderBuilder<R> {
+    /// Creates a [`StreamDecoder`] for data that cannot seek, such as a network
+    /// stream, with previously configured settings.
+    ///
+    /// The data does not need to implement [`Seek`]. Seeking and the total
+    /// duration are therefore unavailable, and `byte_len` and `seekable` are
+    /// ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DecoderError::UnrecognizedFormat` if the audio format could not be determined
+    /// or is not supported, and `DecoderError::IoError` if the decoding thread could not be
+    /// started.
+    pub fn build_stream(self) -> Result<StreamDecoder, DecoderError> {
+        let data = self.data.ok_or(DecoderError::UnrecognizedFormat)?;
+        let settings = Settings {
+            byte_len: None,
+            is_seekable: false,
+            is_stream: true,
+            ..self.settings
+        };
+        let buffer = settings.stream_buffer;
+
+        let reader = LookAhead::new(data);
+        let status = reader.status();
+        let decoder = DecoderBuilder {
+            data: Some(reader),
+            settings,
+        }
+        .build()?;
+
+        StreamDecoder::spawn(decoder, status, buffer)
+            .map_err(|e| DecoderError::IoError(e.to_string()))
+    }
+}
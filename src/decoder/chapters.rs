@@ -0,0 +1,547 @@
+//! Chapters of audiobooks and tracks of cue sheets.
+
+use std::io::{self, Read, Seek, SeekFrom};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+use super::Decoder;
+use crate::common::{ChannelCount, SampleRate};
+use crate::math::NANOS_PER_SEC;
+use crate::source::{SeekError, Source};
+use crate::Sample;
+
+/// A chapter of an audiobook, or a track of a cue sheet.
+///
+/// # Example
+/// ```
+/// use rodio::decoder::Chapter;
+/// use std::time::Duration;
+///
+/// let cue_sheet = r#"
+/// FILE "album.flac" WAVE
+///   TRACK 01 AUDIO
+///     TITLE "Intro"
+///     INDEX 01 00:00:00
+///   TRACK 02 AUDIO
+///     TITLE "Theme"
+///     INDEX 01 01:30:00
+/// "#;
+/// let chapters = Chapter::parse_cue_sheet(cue_sheet);
+/// assert_eq!(chapters[1].title(), Some("Theme"));
+/// assert_eq!(chapters[1].start(), Duration::from_secs(90));
+/// assert_eq!(chapters[0].end(), Some(Duration::from_secs(90)));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chapter {
+    title: Option<String>,
+    start: Duration,
+    end: Option<Duration>,
+}
+
+impl Chapter {
+    /// A chapter without title starting at `start`. It lasts until the end of
+    /// the file unless it is followed by another chapter.
+    pub fn new(start: Duration) -> Self {
+        Chapter {
+            title: None,
+            start,
+            end: None,
+        }
+    }
+
+    /// Sets the title.
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// The title, if the file stores one.
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    /// Position of the start of the chapter in the file.
+    pub fn start(&self) -> Duration {
+        self.start
+    }
+
+    /// Position of the end of the chapter in the file. `None` for the last
+    /// chapter when the length of the file is unknown.
+    pub fn end(&self) -> Option<Duration> {
+        self.end
+    }
+
+    /// The length of the chapter, if its end is known.
+    pub fn duration(&self) -> Option<Duration> {
+        self.end.map(|end| end.saturating_sub(self.start))
+    }
+
+    /// Reads the tracks of a cue sheet as chapters. Each track starts at its
+    /// `INDEX 01` and takes its `TITLE`. Sheets referring to several files
+    /// are read as if all tracks were in one file.
+    ///
+    /// Lines that are not understood are skipped.
+    pub fn parse_cue_sheet(text: &str) -> Vec<Chapter> {
+        let mut chapters = Vec::new();
+        // Title and start of the track being read.
+        let mut track: Option<(Option<String>, Option<Duration>)> = None;
+        let mut finish = |track: Option<(Option<String>, Option<Duration>)>| {
+            if let Some((title, Some(start))) = track {
+                chapters.push(Chapter {
+                    title,
+                    start,
+                    end: None,
+                });
+            }
+        };
+
+        for line in text.lines() {
+            let line = line.trim();
+            let (command, arguments) = line
+                .split_once(char::is_whitespace)
+                .map_or((line, ""), |(command, arguments)| {
+                    (command, arguments.trim())
+                });
+            match (command.to_ascii_uppercase().as_str(), &mut track) {
+                ("TRACK", _) => {
+                    finish(track.take());
+                    track = Some((None, None));
+                }
+                ("TITLE", Some((title, _))) => {
+                    *title = Some(unquote(arguments).to_owned());
+                }
+                ("INDEX", Some((_, start))) => {
+                    if let Some(("01" | "1", time)) = arguments.split_once(char::is_whitespace) {
+                        *start = parse_cue_time(time.trim()).or(*start);
+                    }
+                }
+                _ => {}
+            }
+        }
+        finish(track);
+
+        link(&mut chapters, None);
+        chapters
+    }
+}
+
+fn unquote(text: &str) -> &str {
+    text.strip_prefix('"')
+        .and_then(|text| text.strip_suffix('"'))
+        .unwrap_or(text)
+}
+
+/// Parses `mm:ss:ff`, where a frame is 1/75th of a second.
+fn parse_cue_time(time: &str) -> Option<Duration> {
+    let mut parts = time.split(':').map(|part| part.parse::<u64>().ok());
+    let (minutes, seconds, frames) = (parts.next()??, parts.next()??, parts.next()??);
+    if parts.next().is_some() || seconds >= 60 || frames >= 75 {
+        return None;
+    }
+    Some(
+        Duration::from_secs(minutes * 60 + seconds)
+            + Duration::from_nanos(frames * NANOS_PER_SEC / 75),
+    )
+}
+
+/// Sorts the chapters and ends each one where the next one starts. The last
+/// one ends at `total_duration`.
+pub(crate) fn link(chapters: &mut [Chapter], total_duration: Option<Duration>) {
+    chapters.sort_by_key(|chapter| chapter.start);
+    let mut end = total_duration;
+    for chapter in chapters.iter_mut().rev() {
+        chapter.end = end.or(chapter.end);
+        end = Some(chapter.start);
+    }
+}
+
+/// Reads the Nero chapter list (`moov/udta/chpl`) found in most M4B
+/// audiobooks. Returns no chapters for other files.
+///
+/// The stream is left where it started.
+pub(crate) fn read_mp4_chapters(reader: &mut (impl Read + Seek)) -> io::Result<Vec<Chapter>> {
+    let start = reader.stream_position()?;
+    let chapters = find_chpl(reader, start);
+    reader.seek(SeekFrom::Start(start))?;
+    Ok(chapters?
+        .map(|payload| parse_chpl(&payload))
+        .unwrap_or_default())
+}
+
+fn find_chpl(reader: &mut (impl Read + Seek), start: u64) -> io::Result<Option<Vec<u8>>> {
+    let end = reader.seek(SeekFrom::End(0))?;
+    reader.seek(SeekFrom::Start(start))?;
+    match next_box(reader, end)? {
+        Some((kind, _)) if &kind == b"ftyp" => {}
+        _ => return Ok(None),
+    }
+    reader.seek(SeekFrom::Start(start))?;
+
+    let mut end = end;
+    for wanted in [b"moov", b"udta", b"chpl"] {
+        end = loop {
+            let Some((kind, box_end)) = next_box(reader, end)? else {
+                return Ok(None);
+            };
+            if &kind == wanted {
+                break box_end;
+            }
+            reader.seek(SeekFrom::Start(box_end))?;
+        };
+    }
+
+    // The chapter list is small, anything larger is not a real one.
+    const MAX_LEN: u64 = 1 << 20;
+    let len = end.saturating_sub(reader.stream_position()?);
+    if len > MAX_LEN {
+        return Ok(None);
+    }
+    let mut payload = vec![0; len as usize];
+    reader.read_exact(&mut payload)?;
+    Ok(Some(payload))
+}
+
+/// Reads the header of the box at the current position. Returns its type and
+/// where it ends, leaving the reader at the start of its payload.
+fn next_box(
+    reader: &mut (impl Read + Seek),
+    parent_end: u64,
+) -> io::Result<Option<([u8; 4], u64)>> {
+    let position = reader.stream_position()?;
+    if position + 8 > parent_end {
+        return Ok(None);
+    }
+    let mut header = [0; 8];
+    reader.read_exact(&mut header)?;
+    let kind = [header[4], header[5], header[6], header[7]];
+    let end = match u32::from_be_bytes([header[0], header[1], header[2], header[3]]) {
+        0 => parent_end,
+        1 => {
+            let mut size = [0; 8];
+            reader.read_exact(&mut size)?;
+            position.saturating_add(u64::from_be_bytes(size))
+        }
+        size => position + u64::from(size),
+    };
+    if end > parent_end || end < reader.stream_position()? {
+        return Ok(None);
+    }
+    Ok(Some((kind, end)))
+}
+
+fn parse_chpl(payload: &[u8]) -> Vec<Chapter> {
+    let mut chapters = Vec::new();
+    let Some((&version, rest)) = payload.split_first() else {
+        return chapters;
+    };
+    // Flags, and for version 1 four bytes nobody documented.
+    let skip = if version == 0 { 3 } else { 7 };
+    let Some((&count, mut rest)) = rest.get(skip..).and_then(<[u8]>::split_first) else {
+        return chapters;
+    };
+
+    for _ in 0..count {
+        let Some((start, &[len, ..])) = rest.split_first_chunk::<8>() else {
+            break;
+        };
+        let Some(title) = rest.get(9..9 + usize::from(len)) else {
+            break;
+        };
+        // In units of 100 ns.
+        let start = Duration::from_nanos(u64::from_be_bytes(*start).saturating_mul(100));
+        let title = String::from_utf8_lossy(title).into_owned();
+        chapters.push(Chapter {
+            title: (!title.is_empty()).then_some(title),
+            start,
+            end: None,
+        });
+        rest = &rest[9 + usize::from(len)..];
+    }
+    chapters
+}
+
+/// Where the decoder shared by the [`ChapterSource`]s of one file is.
+struct Playhead<R: Read + Seek> {
+    decoder: Decoder<R>,
+    /// Samples since the start of the file.
+    position: u64,
+}
+
+/// One chapter of a file, created by [`Decoder::split_chapters`].
+///
+/// The chapters of a file share one decoder and are meant to be played one
+/// after the other, for example by appending them all to a
+/// [`Player`](crate::Player). A chapter that does not start where the previous
+/// one stopped seeks to its start, so skipping chapters only works when the
+/// decoder can seek.
+pub struct ChapterSource<R: Read + Seek> {
+    chapter: Chapter,
+    shared: Arc<Mutex<Option<Playhead<R>>>>,
+    /// Holds the decoder while this chapter plays.
+    active: Option<Playhead<R>>,
+    /// Samples left in the chapter, `None` if it lasts until the end of the file.
+    remaining: Option<u64>,
+    /// The last chapter plays until the decoder ends, even if the reported
+    /// duration of the file is slightly off.
+    is_last: bool,
+    finished: bool,
+    channels: ChannelCount,
+    sample_rate: SampleRate,
+}
+
+impl<R: Read + Seek> Decoder<R> {
+    /// Splits the file into one source per chapter, for example to queue them
+    /// as separate entries that can be skipped. Use [`Decoder::chapters`] or
+    /// [`Chapter::parse_cue_sheet`] to get the chapters.
+    ///
+    /// Without chapters the whole file becomes a single source.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use rodio::Decoder;
+    /// use std::fs::File;
+    ///
+    /// let decoder = Decoder::try_from(File::open("audiobook.m4b")?)?;
+    /// let chapters = decoder.chapters().to_vec();
+    /// for chapter in decoder.split_chapters(chapters) {
+    ///     // Append `chapter` to a `Player`.
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn split_chapters(self, mut chapters: Vec<Chapter>) -> Vec<ChapterSource<R>> {
+        if chapters.is_empty() {
+            chapters.push(Chapter::new(Duration::ZERO));
+        }
+        link(&mut chapters, self.total_duration());
+
+        let channels = self.channels();
+        let sample_rate = self.sample_rate();
+        let shared = Arc::new(Mutex::new(Some(Playhead {
+            decoder: self,
+            position: 0,
+        })));
+        let last = chapters.len() - 1;
+        chapters
+            .into_iter()
+            .enumerate()
+            .map(|(index, chapter)| ChapterSource {
+                chapter,
+                shared: Arc::clone(&shared),
+                active: None,
+                remaining: None,
+                is_last: index == last,
+                finished: false,
+                channels,
+                sample_rate,
+            })
+            .collect()
+    }
+}
+
+impl<R: Read + Seek> ChapterSource<R> {
+    /// The chapter this source plays.
+    pub fn chapter(&self) -> &Chapter {
+        &self.chapter
+    }
+
+    /// The first sample at or after `position` counted from the start of the
+    /// file, aligned to whole frames.
+    fn sample_index(&self, position: Duration) -> u64 {
+        let frames = (position.as_nanos() * u128::from(self.sample_rate.get()))
+            .div_ceil(u128::from(NANOS_PER_SEC));
+        frames as u64 * u64::from(self.channels.get())
+    }
+
+    /// Where the chapter ends counted in samples from the start of the file.
+    fn end_index(&self) -> Option<u64> {
+        if self.is_last {
+            None
+        } else {
+            self.chapter.end.map(|end| self.sample_index(end))
+        }
+    }
+
+    /// Takes the decoder and moves it to `offset` into the chapter.
+    fn activate(&mut self, offset: Duration) -> Result<&mut Playhead<R>, SeekError> {
+        if self.active.is_none() {
+            let playhead = self
+                .shared
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .take();
+            // Only missing when another chapter of the file is playing.
+            self.active = Some(playhead.ok_or(SeekError::NotSupported {
+                underlying_source: std::any::type_name::<Self>(),
+            })?);
+        }
+
+        let start = self.chapter.start + offset;
+        let target = self.sample_index(start);
+        let end = self.end_index();
+        let playhead = self.active.as_mut().expect("just activated");
+        if playhead.position != target {
+            playhead.decoder.try_seek(start)?;
+            playhead.position = target;
+        }
+        self.remaining = end.map(|end| end.saturating_sub(target));
+        Ok(playhead)
+    }
+
+    /// Hands the decoder to the next chapter.
+    fn release(&mut self) {
+        if let Some(playhead) = self.active.take() {
+            *self.shared.lock().unwrap_or_else(PoisonError::into_inner) = Some(playhead);
+        }
+    }
+}
+
+impl<R: Read + Seek> Iterator for ChapterSource<R> {
+    type Item = Sample;
+
+    fn next(&mut self) -> Option<Sample> {
+        if self.finished {
+            return None;
+        }
+        if self.active.is_none() && self.activate(Duration::ZERO).is_err() {
+            self.release();
+            self.finished = true;
+            return None;
+        }
+
+        let sample = match self.remaining {
+            Some(0) => None,
+            _ => self
+                .active
+                .as_mut()
+                .and_then(|playhead| playhead.decoder.next()),
+        };
+        match sample {
+            Some(sample) => {
+                if let Some(playhead) = self.active.as_mut() {
+                    playhead.position += 1;
+                }
+                if let Some(remaining) = self.remaining.as_mut() {
+                    *remaining -= 1;
+                }
+                Some(sample)
+            }
+            None => {
+                self.release();
+                self.finished = true;
+                None
+            }
+        }
+    }
+}
+
+impl<R: Read + Seek> Source for ChapterSource<R> {
+    fn current_span_len(&self) -> Option<usize> {
+        if self.finished {
+            return Some(0);
+        }
+        // The whole chapter is a single span.
+        self.end_index()
+            .map(|end| end.saturating_sub(self.sample_index(self.chapter.start)) as usize)
+    }
+
+    fn channels(&self) -> ChannelCount {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> SampleRate {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.chapter.duration()
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        let pos = match self.chapter.duration() {
+            Some(duration) => pos.min(duration),
+            None => pos,
+        };
+        self.finished = false;
+        self.activate(pos).map(|_| ())
+    }
+}
+
+impl<R: Read + Seek> Drop for ChapterSource<R> {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use std::time::Duration;
+
+    use super::{link, read_mp4_chapters, Chapter};
+
+    #[test]
+    fn parses_cue_sheet() {
+        let sheet = "REM GENRE Audiobook\n\
+            TITLE \"Album\"\n\
+            FILE \"book.flac\" WAVE\n\
+            \x20 TRACK 01 AUDIO\n\
+            \x20   TITLE \"Prologue\"\n\
+            \x20   INDEX 01 00:00:00\n\
+            \x20 TRACK 02 AUDIO\n\
+            \x20   INDEX 00 02:59:00\n\
+            \x20   INDEX 01 03:00:15\n\
+            \x20   TITLE \"One\"\n";
+        let chapters = Chapter::parse_cue_sheet(sheet);
+        assert_eq!(chapters.len(), 2);
+        assert_eq!(chapters[0].title(), Some("Prologue"));
+        assert_eq!(chapters[0].start(), Duration::ZERO);
+        assert_eq!(chapters[1].title(), Some("One"));
+        assert_eq!(chapters[1].start(), Duration::from_millis(180_200));
+        assert_eq!(chapters[0].end(), Some(chapters[1].start()));
+        assert_eq!(chapters[1].end(), None);
+    }
+
+    #[test]
+    fn link_ends_at_total_duration() {
+        let mut chapters = vec![
+            Chapter::new(Duration::from_secs(10)),
+            Chapter::new(Duration::ZERO),
+        ];
+        link(&mut chapters, Some(Duration::from_secs(25)));
+        assert_eq!(chapters[0].duration(), Some(Duration::from_secs(10)));
+        assert_eq!(chapters[1].duration(), Some(Duration::from_secs(15)));
+    }
+
+    fn mp4_box(kind: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let mut data = ((payload.len() + 8) as u32).to_be_bytes().to_vec();
+        data.extend_from_slice(kind);
+        data.extend_from_slice(payload);
+        data
+    }
+
+    #[test]
+    fn reads_nero_chapters() {
+        let mut chpl = vec![1, 0, 0, 0, 0, 0, 0, 0, 2];
+        for (start, title) in [(0u64, "Start"), (12_345_678_900, "Later")] {
+            chpl.extend_from_slice(&start.to_be_bytes());
+            chpl.push(title.len() as u8);
+            chpl.extend_from_slice(title.as_bytes());
+        }
+        let udta = mp4_box(b"udta", &mp4_box(b"chpl", &chpl));
+        let mut moov = mp4_box(b"mvhd", &[0; 100]);
+        moov.extend(udta);
+        let mut file = mp4_box(b"ftyp", b"M4B \0\0\0\0");
+        file.extend(mp4_box(b"mdat", &[0; 64]));
+        file.extend(mp4_box(b"moov", &moov));
+
+        let mut reader = Cursor::new(file);
+        let chapters = read_mp4_chapters(&mut reader).unwrap();
+        assert_eq!(reader.position(), 0);
+        assert_eq!(chapters.len(), 2);
+        assert_eq!(chapters[1].title(), Some("Later"));
+        assert_eq!(chapters[1].start(), Duration::from_nanos(1_234_567_890_000));
+
+        let mut not_mp4 = Cursor::new(b"RIFF\0\0\0\0WAVE".to_vec());
+        assert!(read_mp4_chapters(&mut not_mp4).unwrap().is_empty());
+    }
+}
@@ -40,6 +40,7 @@
 //! - `flac` - FLAC format support
 //! - `vorbis` - Vorbis format support
 //! - `mp3` - MP3 format support via minimp3
+//! - `opus` - Opus in Ogg or WebM/Matroska containers via libopus
 //! - `symphonia` - Enhanced format support via the Symphonia backend
 //!
 //! When using `symphonia`, additional formats like AAC and MP4 containers become available
@@ -64,16 +65,24 @@ use crate::{
 
 pub mod builder;
 pub use builder::{DecoderBuilder, Settings};
+mod chapters;
+pub use chapters::{Chapter, ChapterSource};
 
 #[cfg(all(feature = "claxon", not(feature = "symphonia-flac")))]
 mod flac;
 #[cfg(all(feature = "minimp3", not(feature = "symphonia-mp3")))]
 mod mp3;
+#[cfg(feature = "opus")]
+mod opus;
 #[cfg(feature = "symphonia")]
 mod read_seek_source;
+mod stream;
+pub use stream::{StreamDecoder, StreamStatus};
 #[cfg(feature = "symphonia")]
 /// Symphonia decoders types
 pub mod symphonia;
+#[cfg(feature = "symphonia")]
+pub use self::symphonia::TrackInfo;
 #[cfg(all(feature = "lewton", not(feature = "symphonia-vorbis")))]
 mod vorbis;
 #[cfg(all(feature = "hound", not(feature = "symphonia-wav")))]
@@ -375,6 +384,43 @@ impl<R: Read + Seek + Send + Sync + 'static> Decoder<R> {
         DecoderBuilder::new()
     }
 
+    /// The chapters of the file, read from an embedded cue sheet, the cue
+    /// points of a FLAC file or the chapter list of an MP4 audiobook (M4B).
+    /// Empty when the file has none or is not decoded by Symphonia.
+    ///
+    /// Use [`Player::seek_to_chapter`](crate::Player::seek_to_chapter) to jump
+    /// to a chapter or [`Decoder::split_chapters`] to queue them separately.
+    pub fn chapters(&self) -> &[Chapter] {
+        match &self.0 {
+            #[cfg(feature = "symphonia")]
+            DecoderImpl::Symphonia(source, PhantomData) => source.chapters(),
+            _ => &[],
+        }
+    }
+
+    /// Lists all tracks of the container, including the ones that are not
+    /// decoded. Empty for formats that are not decoded by Symphonia.
+    ///
+    /// To decode another track, build a new decoder with
+    /// [`DecoderBuilder::with_track`] or [`DecoderBuilder::with_track_selector`].
+    #[cfg(feature = "symphonia")]
+    pub fn tracks(&self) -> Vec<TrackInfo> {
+        match &self.0 {
+            DecoderImpl::Symphonia(source, PhantomData) => source.tracks(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// The id of the track being decoded, `None` for formats that are not
+    /// decoded by Symphonia.
+    #[cfg(feature = "symphonia")]
+    pub fn track_id(&self) -> Option<u32> {
+        match &self.0 {
+            DecoderImpl::Symphonia(source, PhantomData) => Some(source.track_id()),
+            _ => None,
+        }
+    }
+
     /// Builds a new decoder with default settings.
     ///
     /// Attempts to automatically detect the format of the source of data.
@@ -478,6 +524,32 @@ impl<R: Read + Seek + Send + Sync + 'static> Decoder<R> {
             .build()
     }
 
+    /// Builds a new decoder with Ogg Opus format hint.
+    ///
+    /// This method provides a hint that the data is Opus in an Ogg container, which may help
+    /// the decoder identify the format more quickly. However, if Opus decoding fails, other
+    /// formats will still be attempted. Opus always decodes at 48 kHz.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DecoderError::UnrecognizedFormat` if no suitable decoder was found.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use rodio::Decoder;
+    /// use std::fs::File;
+    ///
+    /// let file = File::open("audio.opus").unwrap();
+    /// let decoder = Decoder::new_opus(file).unwrap();
+    /// ```
+    #[cfg(feature = "opus")]
+    pub fn new_opus(data: R) -> Result<Self, DecoderError> {
+        DecoderBuilder::new()
+            .with_data(data)
+            .with_hint("opus")
+            .build()
+    }
+
     /// Builds a new decoder with MP3 format hint.
     ///
     /// This method provides a hint that the data is MP3 format, which may help the decoder
@@ -794,5 +866,10 @@ pub enum DecoderError {
     #[error("No streams were found by the decoder.")]
     #[cfg(feature = "symphonia")]
     NoStreams,
+
+    /// No track matches the id or selector passed to the [`DecoderBuilder`].
+    #[error("No track matches the requested track id or selector.")]
+    #[cfg(feature = "symphonia")]
+    NoSuchTrack,
 }
 assert_error_traits!(DecoderError);
@@ -0,0 +1,218 @@
+//! Opus decoding using libopus, plugged into Symphonia so its Ogg and
+//! Matroska/WebM demuxers take care of reading packets and seeking.
+
+use std::sync::{Mutex, OnceLock, PoisonError};
+
+use audiopus::coder::{Decoder as LibOpusDecoder, GenericCtl};
+use audiopus::{Channels as OpusChannels, SampleRate as OpusSampleRate};
+use symphonia::core::{
+    audio::{AsAudioBufferRef, AudioBuffer, AudioBufferRef, Channels, Signal, SignalSpec},
+    codecs::{
+        CodecDescriptor, CodecParameters, CodecRegistry, Decoder, DecoderOptions, FinalizeResult,
+        CODEC_TYPE_OPUS,
+    },
+    errors::{unsupported_error, Error, Result},
+    formats::Packet,
+    units::TimeBase,
+};
+
+/// Opus always decodes at 48 kHz.
+const SAMPLE_RATE: u32 = 48_000;
+/// The longest packet Opus allows is 120 ms.
+const MAX_FRAMES_PER_PACKET: usize = 5760;
+
+/// The codecs enabled in Symphonia together with the Opus decoder.
+pub(super) fn codecs() -> &'static CodecRegistry {
+    static CODECS: OnceLock<CodecRegistry> = OnceLock::new();
+    CODECS.get_or_init(|| {
+        let mut registry = CodecRegistry::new();
+        symphonia::default::register_enabled_codecs(&mut registry);
+        registry.register_all::<OpusDecoder>();
+        registry
+    })
+}
+
+/// The fields of the `OpusHead` identification header (RFC 7845) the decoder
+/// needs. Both the Ogg and the Matroska demuxer pass it as extra data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct OpusHead {
+    channels: u8,
+    pre_skip: u16,
+    /// Q7.8 fixed point decibels.
+    output_gain: i16,
+}
+
+impl OpusHead {
+    fn parse(data: &[u8]) -> Option<Self> {
+        if data.len() < 19 || &data[..8] != b"OpusHead" {
+            return None;
+        }
+        // Only the major version in the upper nibble breaks compatibility.
+        if data[8] >> 4 != 0 {
+            return None;
+        }
+        Some(OpusHead {
+            channels: data[9],
+            pre_skip: u16::from_le_bytes([data[10], data[11]]),
+            output_gain: i16::from_le_bytes([data[16], data[17]]),
+        })
+    }
+
+    fn gain(&self) -> f32 {
+        10f32.powf(f32::from(self.output_gain) / (20.0 * 256.0))
+    }
+}
+
+/// Decodes mono and stereo Opus streams to 48 kHz.
+pub(super) struct OpusDecoder {
+    // A libopus decoder can be sent but not shared. The mutex makes this Sync,
+    // it is never contended since decoding needs `&mut self`.
+    decoder: Mutex<LibOpusDecoder>,
+    params: CodecParameters,
+    buffer: AudioBuffer<f32>,
+    interleaved: Vec<f32>,
+    /// Frames to drop from the start of the stream. Zero once the demuxer is
+    /// seen trimming the packets itself.
+    pre_skip: u64,
+    gain: f32,
+}
+
+impl OpusDecoder {
+    /// The timestamp of the packet counted in frames at 48 kHz.
+    fn frame_position(&self, packet: &Packet) -> u64 {
+        match self.params.time_base {
+            Some(base) if base != TimeBase::new(1, SAMPLE_RATE) => {
+                let time = base.calc_time(packet.ts());
+                time.seconds * u64::from(SAMPLE_RATE)
+                    + (time.frac * f64::from(SAMPLE_RATE)).round() as u64
+            }
+            _ => packet.ts(),
+        }
+    }
+}
+
+impl Decoder for OpusDecoder {
+    fn try_new(params: &CodecParameters, _options: &DecoderOptions) -> Result<Self> {
+        if params.codec != CODEC_TYPE_OPUS {
+            return unsupported_error("opus: invalid codec type");
+        }
+        let head = params.extra_data.as_deref().and_then(OpusHead::parse);
+        let channel_count = head
+            .map(|head| usize::from(head.channels))
+            .or_else(|| params.channels.map(|channels| channels.count()));
+        let (opus_channels, channels) = match channel_count {
+            Some(1) => (OpusChannels::Mono, Channels::FRONT_LEFT),
+            Some(2) => (
+                OpusChannels::Stereo,
+                Channels::FRONT_LEFT | Channels::FRONT_RIGHT,
+            ),
+            _ => return unsupported_error("opus: only mono and stereo streams are supported"),
+        };
+        let decoder = LibOpusDecoder::new(OpusSampleRate::Hz48000, opus_channels)
+            .map_err(|_| Error::Unsupported("opus: libopus failed to initialize"))?;
+
+        // Demuxers report the pre-skip as delay at most, whether they trim it
+        // is only known from the packets.
+        let pre_skip = head
+            .map(|head| u64::from(head.pre_skip))
+            .or(params.delay.map(u64::from))
+            .unwrap_or(0);
+
+        let mut params = params.clone();
+        params.with_sample_rate(SAMPLE_RATE).with_channels(channels);
+        let spec = SignalSpec::new(SAMPLE_RATE, channels);
+        Ok(OpusDecoder {
+            decoder: Mutex::new(decoder),
+            params,
+            buffer: AudioBuffer::new(MAX_FRAMES_PER_PACKET as u64, spec),
+            interleaved: vec![0.0; MAX_FRAMES_PER_PACKET * channels.count()],
+            pre_skip,
+            gain: head.map_or(1.0, |head| head.gain()),
+        })
+    }
+
+    fn supported_codecs() -> &'static [CodecDescriptor] {
+        &[CodecDescriptor {
+            codec: CODEC_TYPE_OPUS,
+            short_name: "opus",
+            long_name: "Opus (libopus)",
+            inst_func: |params, options| Ok(Box::new(OpusDecoder::try_new(params, options)?)),
+        }]
+    }
+
+    fn reset(&mut self) {
+        let decoder = self
+            .decoder
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner);
+        // Resetting only fails on an invalid decoder, which can not exist.
+        let _ = decoder.reset_state();
+    }
+
+    fn codec_params(&self) -> &CodecParameters {
+        &self.params
+    }
+
+    fn decode(&mut self, packet: &Packet) -> Result<AudioBufferRef<'_>> {
+        let position = self.frame_position(packet);
+        let decoder = self
+            .decoder
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner);
+        let frames = decoder
+            .decode_float(Some(packet.buf()), &mut self.interleaved[..], false)
+            .map_err(|_| Error::DecodeError("opus: invalid packet"))?;
+
+        let channels = self.buffer.spec().channels.count();
+        self.buffer.clear();
+        self.buffer.render_reserved(Some(frames));
+        for channel in 0..channels {
+            let samples = self.interleaved[channel..].iter().step_by(channels);
+            for (out, sample) in self.buffer.chan_mut(channel).iter_mut().zip(samples) {
+                *out = sample * self.gain;
+            }
+        }
+
+        let mut trim_start = packet.trim_start() as usize;
+        if trim_start > 0 {
+            // The demuxer trims the start, its timestamps exclude the pre-skip.
+            self.pre_skip = 0;
+        } else if position < self.pre_skip {
+            trim_start = (self.pre_skip - position) as usize;
+        }
+        self.buffer.trim(trim_start, packet.trim_end() as usize);
+        Ok(self.buffer.as_audio_buffer_ref())
+    }
+
+    fn finalize(&mut self) -> FinalizeResult {
+        FinalizeResult::default()
+    }
+
+    fn last_decoded(&self) -> AudioBufferRef<'_> {
+        self.buffer.as_audio_buffer_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OpusHead;
+
+    #[test]
+    fn parses_opus_head() {
+        let mut head = b"OpusHead".to_vec();
+        head.extend_from_slice(&[1, 2]);
+        head.extend_from_slice(&312u16.to_le_bytes());
+        head.extend_from_slice(&44_100u32.to_le_bytes());
+        head.extend_from_slice(&(-256i16).to_le_bytes());
+        head.push(0);
+
+        let parsed = OpusHead::parse(&head).unwrap();
+        assert_eq!(parsed.channels, 2);
+        assert_eq!(parsed.pre_skip, 312);
+        assert!((parsed.gain() - 0.891).abs() < 1e-3);
+
+        assert_eq!(OpusHead::parse(&head[..18]), None);
+        head[8] = 0x10;
+        assert_eq!(OpusHead::parse(&head), None);
+    }
+}
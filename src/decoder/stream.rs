@@ -0,0 +1,485 @@
+//! Decoding from readers that cannot seek, such as network streams.
+//!
+//! A [`StreamDecoder`] decodes on a background thread, a little ahead of playback.
+//! When the input stalls it plays silence instead of ending, and resumes once
+//! enough audio has been buffered again.
+
+use std::io::{self, Read, Seek, SeekFrom};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use super::{Decoder, DecoderBuilder, DecoderError};
+use crate::common::{ChannelCount, SampleRate};
+use crate::source::read_ahead::{self, SetOnDrop, BLOCK_FRAMES};
+use crate::source::SeekError;
+use crate::{Sample, Source};
+
+/// The most bytes kept for rewinding while the format is being detected.
+const MAX_LOOKAHEAD: usize = 4 * 1024 * 1024;
+
+/// How long to wait before retrying a read that would block.
+const RETRY_INTERVAL: Duration = Duration::from_millis(5);
+
+/// State shared between a [`StreamDecoder`], its decoding thread and its reader.
+#[derive(Debug, Default)]
+struct Shared {
+    /// Whether the reader keeps the bytes it reads so format detection can rewind.
+    recording: AtomicBool,
+    /// Set when the [`StreamDecoder`] is dropped.
+    closed: AtomicBool,
+    /// Set once the decoding thread has sent its last block.
+    ended: AtomicBool,
+    /// Whether playback waits for the buffer to fill up.
+    buffering: AtomicBool,
+    /// Blocks sent by the decoding thread that playback has not taken yet.
+    queued: AtomicUsize,
+    /// How often playback ran out of decoded audio.
+    underruns: AtomicUsize,
+}
+
+/// Decodes audio from a reader that does not need to implement [`Seek`].
+///
+/// Decoding happens on a background thread that stays ahead of playback by the
+/// duration set with [`DecoderBuilder::with_stream_buffer`]. When the reader
+/// stalls, for example because the network is slow, the decoder plays silence
+/// until that buffer has partially refilled instead of ending. Reads that fail
+/// with [`io::ErrorKind::WouldBlock`], [`io::ErrorKind::TimedOut`] or
+/// [`io::ErrorKind::Interrupted`] are retried. Damaged packets are skipped.
+///
+/// The stream ends when the reader reaches its end or fails with any other error.
+///
+/// # Examples
+/// ```no_run
+/// use std::net::TcpStream;
+/// use rodio::decoder::StreamDecoder;
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let connection = TcpStream::connect("radio.example.com:8000")?;
+///     let decoder = StreamDecoder::new(connection)?;
+///     let status = decoder.status();
+///
+///     // Play the decoder...
+///     println!("{} underruns so far", status.underruns());
+///     Ok(())
+/// }
+/// ```
+pub struct StreamDecoder {
+    receiver: Receiver<Block>,
+    shared: Arc<Shared>,
+    channels: ChannelCount,
+    sample_rate: SampleRate,
+    samples: Vec<Sample>,
+    /// Length of the current span, which is silence if `silent` is set.
+    len: usize,
+    position: usize,
+    silent: bool,
+    /// Queued blocks needed to end buffering.
+    rebuffer: usize,
+    started: bool,
+}
+
+/// Decoded samples that share one format.
+struct Block {
+    channels: ChannelCount,
+    sample_rate: SampleRate,
+    samples: Vec<Sample>,
+}
+
+/// The state of a [`StreamDecoder`], which can be checked from another thread.
+#[derive(Debug, Clone)]
+pub struct StreamStatus(Arc<Shared>);
+
+impl StreamStatus {
+    /// Whether the decoder plays silence while waiting for its buffer to fill,
+    /// at the start or after an underrun.
+    pub fn is_buffering(&self) -> bool {
+        self.0.buffering.load(Ordering::Relaxed)
+    }
+
+    /// How often playback ran out of decoded audio after it had started.
+    pub fn underruns(&self) -> usize {
+        self.0.underruns.load(Ordering::Relaxed)
+    }
+
+    /// Whether all of the input has been decoded. Buffered audio may still
+    /// be playing.
+    pub fn has_ended(&self) -> bool {
+        self.0.ended.load(Ordering::Relaxed)
+    }
+}
+
+impl StreamDecoder {
+    /// Decodes `reader` with default settings.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DecoderError::UnrecognizedFormat` if the audio format could not be
+    /// determined or is not supported.
+    pub fn new<R: Read + Send + 'static>(reader: R) -> Result<Self, DecoderError> {
+        DecoderBuilder::new().with_data(reader).build_stream()
+    }
+
+    /// Returns a handle to follow buffering and underruns from another thread.
+    pub fn status(&self) -> StreamStatus {
+        StreamStatus(self.shared.clone())
+    }
+
+    /// Starts decoding `decoder` on a new thread. `status` must come from the
+    /// reader the decoder was built on.
+    pub(super) fn spawn<R: Read + Send + 'static>(
+        decoder: Decoder<LookAhead<R>>,
+        status: StreamStatus,
+        buffer: Duration,
+    ) -> io::Result<Self> {
+        let shared = status.0;
+        // The format is known, so the reader no longer needs to rewind.
+        shared.recording.store(false, Ordering::Relaxed);
+
+        let channels = decoder.channels();
+        let sample_rate = decoder.sample_rate();
+        let frames = buffer.as_secs_f64() * f64::from(sample_rate.get());
+        let capacity = ((frames / BLOCK_FRAMES as f64).ceil() as usize).max(2);
+        let (sender, receiver) = mpsc::sync_channel(capacity);
+
+        let worker = shared.clone();
+        thread::Builder::new()
+            .name("rodio-stream-decoder".to_owned())
+            .spawn(move || decode(decoder, &sender, &worker))?;
+
+        shared.buffering.store(true, Ordering::Relaxed);
+        Ok(StreamDecoder {
+            receiver,
+            shared,
+            channels,
+            sample_rate,
+            samples: Vec::new(),
+            len: 0,
+            position: 0,
+            silent: true,
+            rebuffer: capacity / 2,
+            started: false,
+        }
+        .with_next_span())
+    }
+
+    fn with_next_span(mut self) -> Self {
+        self.next_span();
+        self
+    }
+
+    /// Moves to the next decoded block, or to silence if there is none yet.
+    fn next_span(&mut self) {
+        self.position = 0;
+        if self.shared.buffering.load(Ordering::Relaxed) {
+            let ended = self.shared.ended.load(Ordering::Acquire);
+            if !ended && self.shared.queued.load(Ordering::Acquire) < self.rebuffer {
+                self.play_silence();
+                return;
+            }
+            self.shared.buffering.store(false, Ordering::Relaxed);
+        }
+
+        match self.receiver.try_recv() {
+            Ok(block) => {
+                self.shared.queued.fetch_sub(1, Ordering::AcqRel);
+                self.channels = block.channels;
+                self.sample_rate = block.sample_rate;
+                self.samples = block.samples;
+                self.len = self.samples.len();
+                self.silent = false;
+                self.started = true;
+            }
+            Err(TryRecvError::Empty) => {
+                // The decoding thread may be about to hang up.
+                if self.started && !self.shared.ended.load(Ordering::Acquire) {
+                    self.shared.underruns.fetch_add(1, Ordering::Relaxed);
+                }
+                self.shared.buffering.store(true, Ordering::Relaxed);
+                self.play_silence();
+            }
+            Err(TryRecvError::Disconnected) => {
+                self.len = 0;
+                self.silent = true;
+            }
+        }
+    }
+
+    fn play_silence(&mut self) {
+        self.len = read_ahead::silence_len(self.channels, self.sample_rate);
+        self.silent = true;
+    }
+}
+
+impl Drop for StreamDecoder {
+    fn drop(&mut self) {
+        self.shared.closed.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Iterator for StreamDecoder {
+    type Item = Sample;
+
+    #[inline]
+    fn next(&mut self) -> Option<Sample> {
+        if self.position >= self.len {
+            return None;
+        }
+        let sample = if self.silent {
+            0.0
+        } else {
+            self.samples[self.position]
+        };
+        self.position += 1;
+        if self.position == self.len {
+            self.next_span();
+        }
+        Some(sample)
+    }
+}
+
+impl Source for StreamDecoder {
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        Some(self.len)
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.channels
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.sample_rate
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+
+    fn try_seek(&mut self, _: Duration) -> Result<(), SeekError> {
+        Err(SeekError::NotSupported {
+            underlying_source: std::any::type_name::<Self>(),
+        })
+    }
+}
+
+/// Runs on the decoding thread until the input ends or the [`StreamDecoder`] is dropped.
+fn decode<R: Read + Send + 'static>(
+    mut decoder: Decoder<LookAhead<R>>,
+    sender: &SyncSender<Block>,
+    shared: &Shared,
+) {
+    // Also set if the decoder panics, so playback stops buffering.
+    let _ended = SetOnDrop(&shared.ended);
+    let mut done = false;
+    while !done {
+        let mut samples = Vec::with_capacity(BLOCK_FRAMES * decoder.channels().get() as usize);
+        let format = read_ahead::decode_block(&mut decoder, &mut samples);
+        done = format.input_ended;
+
+        if samples.is_empty() {
+            continue;
+        }
+        shared.queued.fetch_add(1, Ordering::AcqRel);
+        let block = Block {
+            channels: format.channels,
+            sample_rate: format.sample_rate,
+            samples,
+        };
+        if sender.send(block).is_err() {
+            return;
+        }
+    }
+}
+
+/// Makes a plain reader seekable within the bytes read while the format is
+/// being detected, which is all the decoders need from a stream.
+pub(crate) struct LookAhead<R> {
+    /// Behind a mutex only so the reader does not have to be `Sync`.
+    inner: Mutex<R>,
+    shared: Arc<Shared>,
+    /// The first bytes of the stream, kept while `shared.recording` is set.
+    buffer: Vec<u8>,
+    position: u64,
+    /// Bytes read from `inner` so far.
+    read: u64,
+}
+
+impl<R: Read> LookAhead<R> {
+    pub(super) fn new(inner: R) -> Self {
+        let shared = Shared {
+            recording: AtomicBool::new(true),
+            ..Shared::default()
+        };
+        LookAhead {
+            inner: Mutex::new(inner),
+            shared: Arc::new(shared),
+            buffer: Vec::new(),
+            position: 0,
+            read: 0,
+        }
+    }
+
+    /// The status of the [`StreamDecoder`] that will play this reader.
+    pub(super) fn status(&self) -> StreamStatus {
+        StreamStatus(self.shared.clone())
+    }
+
+    /// Reads from the inner reader, waiting out stalls.
+    fn read_inner(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let inner = self.inner.get_mut().unwrap_or_else(|e| e.into_inner());
+        loop {
+            if self.shared.closed.load(Ordering::Relaxed) {
+                return Err(io::Error::other("stream decoder was dropped"));
+            }
+            match inner.read(buf) {
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                    ) =>
+                {
+                    thread::sleep(RETRY_INTERVAL)
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Whether `buffer` holds everything read so far.
+    fn is_complete(&self) -> bool {
+        self.buffer.len() as u64 == self.read
+    }
+}
+
+impl<R: Read> Read for LookAhead<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.position < self.read {
+            let start = self.position as usize;
+            let n = buf.len().min(self.buffer.len() - start);
+            buf[..n].copy_from_slice(&self.buffer[start..start + n]);
+            self.position += n as u64;
+            if self.position == self.read && !self.shared.recording.load(Ordering::Relaxed) {
+                self.buffer = Vec::new();
+            }
+            return Ok(n);
+        }
+
+        let n = self.read_inner(buf)?;
+        if self.shared.recording.load(Ordering::Relaxed) && self.is_complete() {
+            if self.buffer.len() + n <= MAX_LOOKAHEAD {
+                self.buffer.extend_from_slice(&buf[..n]);
+            } else {
+                self.buffer = Vec::new();
+            }
+        } else if !self.buffer.is_empty() {
+            self.buffer = Vec::new();
+        }
+        self.read += n as u64;
+        self.position = self.read;
+        Ok(n)
+    }
+}
+
+impl<R: Read> Seek for LookAhead<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+            SeekFrom::End(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "cannot seek from the end of a stream",
+                ))
+            }
+        }
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "seek before start"))?;
+
+        if target <= self.read {
+            if target != self.position && !self.is_complete() {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "cannot seek back in a stream",
+                ));
+            }
+            self.position = target;
+        } else {
+            self.position = self.read;
+            let mut scratch = [0; 4096];
+            while self.position < target {
+                let len = scratch.len().min((target - self.position) as usize);
+                if self.read(&mut scratch[..len])? == 0 {
+                    break;
+                }
+            }
+        }
+        Ok(self.position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn look_ahead(data: &[u8]) -> LookAhead<&[u8]> {
+        LookAhead::new(data)
+    }
+
+    #[test]
+    fn rewinds_while_recording() {
+        let data: Vec<u8> = (0..100).collect();
+        let mut reader = look_ahead(&data);
+
+        let mut buf = [0; 10];
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(reader.seek(SeekFrom::Current(20)).unwrap(), 30);
+        assert_eq!(reader.seek(SeekFrom::Start(5)).unwrap(), 5);
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(buf[0], 5);
+        assert!(reader.seek(SeekFrom::End(0)).is_err());
+    }
+
+    #[test]
+    fn stops_rewinding_after_recording() {
+        let data: Vec<u8> = (0..100).collect();
+        let mut reader = look_ahead(&data);
+
+        let mut buf = [0; 10];
+        reader.read_exact(&mut buf).unwrap();
+        reader.seek(SeekFrom::Start(0)).unwrap();
+        reader.shared.recording.store(false, Ordering::Relaxed);
+
+        // The recorded bytes are replayed once more.
+        let mut all = Vec::new();
+        reader.read_to_end(&mut all).unwrap();
+        assert_eq!(all, data);
+        assert!(reader.buffer.is_empty());
+        assert!(reader.seek(SeekFrom::Start(0)).is_err());
+        assert_eq!(reader.stream_position().unwrap(), 100);
+    }
+
+    #[test]
+    fn retries_reads_that_would_block() {
+        struct Flaky(bool, &'static [u8]);
+        impl Read for Flaky {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                self.0 = !self.0;
+                if self.0 {
+                    return Err(io::ErrorKind::WouldBlock.into());
+                }
+                self.1.read(buf)
+            }
+        }
+
+        let mut reader = LookAhead::new(Flaky(false, b"abc"));
+        let mut all = Vec::new();
+        reader.read_to_end(&mut all).unwrap();
+        assert_eq!(all, b"abc");
+    }
+}
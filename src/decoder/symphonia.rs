@@ -3,10 +3,10 @@ use std::sync::Arc;
 use symphonia::{
     core::{
         audio::{AudioBufferRef, SampleBuffer, SignalSpec},
-        codecs::{Decoder, DecoderOptions, CODEC_TYPE_NULL},
+        codecs::{Decoder, DecoderOptions},
         errors::Error,
-        formats::{FormatOptions, FormatReader, SeekMode, SeekTo, SeekedTo},
-        io::MediaSourceStream,
+        formats::{FormatOptions, FormatReader, SeekMode, SeekTo, SeekedTo, Track},
+        io::{MediaSource, MediaSourceStream},
         meta::MetadataOptions,
         probe::Hint,
         units,
@@ -14,37 +14,196 @@ use symphonia::{
     default::get_probe,
 };
 
-use super::{DecoderError, Settings};
+#[cfg(feature = "opus")]
+use super::opus::codecs;
+#[cfg(not(feature = "opus"))]
+use symphonia::default::get_codecs as codecs;
+
+use super::chapters::{self, read_mp4_chapters, Chapter};
+use super::{builder::TrackSelection, DecoderError, Settings};
 use crate::{
     common::{assert_error_traits, ChannelCount, Sample, SampleRate},
     source, Source,
 };
 
+/// How many damaged packets in a row a stream may have before decoding gives up.
+const MAX_STREAM_ERRORS: usize = 100;
+
+/// Description of one track in a container, as listed by
+/// [`Decoder::tracks`](crate::Decoder::tracks).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrackInfo {
+    id: u32,
+    codec: Option<&'static str>,
+    language: Option<String>,
+    channels: Option<ChannelCount>,
+    sample_rate: Option<SampleRate>,
+    total_duration: Option<Duration>,
+}
+
+impl TrackInfo {
+    fn new(track: &Track) -> Self {
+        let params = &track.codec_params;
+        TrackInfo {
+            id: track.id,
+            codec: codecs()
+                .get_codec(params.codec)
+                .map(|descriptor| descriptor.short_name),
+            language: track.language.clone(),
+            channels: params
+                .channels
+                .and_then(|channels| u16::try_from(channels.count()).ok())
+                .and_then(ChannelCount::new),
+            sample_rate: params.sample_rate.and_then(SampleRate::new),
+            total_duration: track_duration(track),
+        }
+    }
+
+    /// The id to pass to [`DecoderBuilder::with_track`](super::DecoderBuilder::with_track).
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Short name of the codec, like `"aac"` or `"opus"`. `None` if none of the
+    /// enabled decoders supports it.
+    pub fn codec(&self) -> Option<&'static str> {
+        self.codec
+    }
+
+    /// Whether one of the enabled decoders can decode the track.
+    pub fn is_supported(&self) -> bool {
+        self.codec.is_some()
+    }
+
+    /// The language of the track as stored in the container, usually an
+    /// ISO 639-2 code such as `"eng"`.
+    pub fn language(&self) -> Option<&str> {
+        self.language.as_deref()
+    }
+
+    /// The channel count, if the container stores it.
+    pub fn channels(&self) -> Option<ChannelCount> {
+        self.channels
+    }
+
+    /// The sample rate, if the container stores it.
+    pub fn sample_rate(&self) -> Option<SampleRate> {
+        self.sample_rate
+    }
+
+    /// The duration of the track, if the container stores it.
+    pub fn total_duration(&self) -> Option<Duration> {
+        self.total_duration
+    }
+}
+
+fn track_duration(track: &Track) -> Option<Duration> {
+    track
+        .codec_params
+        .time_base
+        .zip(track.codec_params.n_frames)
+        .map(|(base, spans)| base.calc_time(spans).into())
+        .filter(|d: &Duration| !d.is_zero())
+}
+
+/// Chapters from an embedded cue sheet, the cue points of the container or
+/// an MP4 chapter list, in that order of preference.
+fn read_chapters(format: &mut dyn FormatReader, mp4_chapters: Vec<Chapter>) -> Vec<Chapter> {
+    let cue_sheet = format.metadata().current().and_then(|revision| {
+        revision
+            .tags()
+            .iter()
+            .find(|tag| tag.key.eq_ignore_ascii_case("CUESHEET"))
+            .map(|tag| tag.value.to_string())
+    });
+    if let Some(chapters) = cue_sheet
+        .map(|text| Chapter::parse_cue_sheet(&text))
+        .filter(|chapters| !chapters.is_empty())
+    {
+        return chapters;
+    }
+
+    let time_base = format
+        .default_track()
+        .and_then(|track| track.codec_params.time_base);
+    if let Some(time_base) = time_base {
+        let chapters: Vec<_> = format
+            .cues()
+            .iter()
+            // The lead-out track only marks where the last track ends.
+            .filter(|cue| !cue.points.is_empty() && cue.index != 170 && cue.index != 255)
+            .map(|cue| {
+                // Tracks with a pregap start with INDEX 00, the track itself
+                // starts at the following INDEX 01.
+                let offset = cue
+                    .points
+                    .get(1)
+                    .or(cue.points.first())
+                    .map_or(0, |point| point.start_offset_ts);
+                Chapter::new(time_base.calc_time(cue.start_ts + offset).into())
+            })
+            .collect();
+        if !chapters.is_empty() {
+            return chapters;
+        }
+    }
+    mp4_chapters
+}
+
+/// The track `selection` picks.
+fn select_track<'a>(
+    tracks: &'a [Track],
+    selection: &TrackSelection,
+) -> Result<&'a Track, DecoderError> {
+    match selection {
+        TrackSelection::FirstSupported => tracks
+            .iter()
+            .find(|track| codecs().get_codec(track.codec_params.codec).is_some())
+            .ok_or(DecoderError::UnrecognizedFormat),
+        TrackSelection::Id(id) => tracks
+            .iter()
+            .find(|track| track.id == *id)
+            .ok_or(DecoderError::NoSuchTrack),
+        TrackSelection::Selector(selector) => tracks
+            .iter()
+            .find(|track| selector(&TrackInfo::new(track)))
+            .ok_or(DecoderError::NoSuchTrack),
+    }
+}
+
 pub(crate) struct SymphoniaDecoder {
     decoder: Box<dyn Decoder>,
+    track_id: u32,
+    // Picks the track again when a chained stream starts a new one.
+    track_selection: TrackSelection,
+    chapters: Vec<Chapter>,
     current_span_offset: usize,
     format: Box<dyn FormatReader>,
     total_duration: Option<Duration>,
     buffer: SampleBuffer<Sample>,
     spec: SignalSpec,
     seek_mode: SeekMode,
+    is_stream: bool,
 }
 
 impl SymphoniaDecoder {
     pub(crate) fn new(mss: MediaSourceStream, settings: &Settings) -> Result<Self, DecoderError> {
-        match SymphoniaDecoder::init(mss, settings) {
-            Err(e) => match e {
-                Error::IoError(e) => Err(DecoderError::IoError(e.to_string())),
-                Error::DecodeError(e) => Err(DecoderError::DecodeError(e)),
-                Error::SeekError(_) => {
-                    unreachable!("Seek errors should not occur during initialization")
-                }
-                Error::Unsupported(_) => Err(DecoderError::UnrecognizedFormat),
-                Error::LimitError(e) => Err(DecoderError::LimitError(e)),
-                Error::ResetRequired => Err(DecoderError::ResetRequired),
-            },
-            Ok(Some(decoder)) => Ok(decoder),
-            Ok(None) => Err(DecoderError::NoStreams),
+        match SymphoniaDecoder::init(mss, settings)? {
+            Some(decoder) => Ok(decoder),
+            None => Err(DecoderError::NoStreams),
+        }
+    }
+
+    fn init_error(e: Error) -> DecoderError {
+        match e {
+            Error::IoError(e) => DecoderError::IoError(e.to_string()),
+            Error::DecodeError(e) => DecoderError::DecodeError(e),
+            Error::SeekError(_) => {
+                unreachable!("Seek errors should not occur during initialization")
+            }
+            Error::Unsupported(_) => DecoderError::UnrecognizedFormat,
+            Error::LimitError(e) => DecoderError::LimitError(e),
+            Error::ResetRequired => DecoderError::ResetRequired,
         }
     }
 
@@ -54,9 +213,9 @@ impl SymphoniaDecoder {
     }
 
     fn init(
-        mss: MediaSourceStream,
+        mut mss: MediaSourceStream,
         settings: &Settings,
-    ) -> symphonia::core::errors::Result<Option<SymphoniaDecoder>> {
+    ) -> Result<Option<SymphoniaDecoder>, DecoderError> {
         let mut hint = Hint::new();
         if let Some(ext) = settings.hint.as_ref() {
             hint.with_extension(ext);
@@ -74,48 +233,35 @@ impl SymphoniaDecoder {
         } else {
             SeekMode::Accurate
         };
-        let mut probed = get_probe().format(&hint, mss, &format_opts, &metadata_opts)?;
-
-        let stream = match probed.format.default_track() {
-            Some(stream) => stream,
-            None => return Ok(None),
+        // Symphonia does not read the chapters of MP4 files.
+        let mp4_chapters = if mss.is_seekable() {
+            read_mp4_chapters(&mut mss).unwrap_or_default()
+        } else {
+            Vec::new()
         };
+        let mut probed = get_probe()
+            .format(&hint, mss, &format_opts, &metadata_opts)
+            .map_err(Self::init_error)?;
+        let mut chapters = read_chapters(&mut *probed.format, mp4_chapters);
 
-        // Select the first supported track
-        let track_id = probed
-            .format
-            .tracks()
-            .iter()
-            .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
-            .ok_or(symphonia::core::errors::Error::Unsupported(
-                "No track with supported codec",
-            ))?
-            .id;
-
-        let track = match probed
-            .format
-            .tracks()
-            .iter()
-            .find(|track| track.id == track_id)
-        {
-            Some(track) => track,
-            None => return Ok(None),
-        };
+        if probed.format.tracks().is_empty() {
+            return Ok(None);
+        }
 
-        let mut decoder = symphonia::default::get_codecs()
-            .make(&track.codec_params, &DecoderOptions::default())?;
-        let total_duration: Option<Duration> = track
-            .codec_params
-            .time_base
-            .zip(stream.codec_params.n_frames)
-            .map(|(base, spans)| base.calc_time(spans).into())
-            .filter(|d: &Duration| !d.is_zero());
+        let track = select_track(probed.format.tracks(), &settings.track)?;
+        let track_id = track.id;
+
+        let mut decoder = codecs()
+            .make(&track.codec_params, &DecoderOptions::default())
+            .map_err(Self::init_error)?;
+        let total_duration = track_duration(track);
+        chapters::link(&mut chapters, total_duration);
 
         let decoded = loop {
             let current_span = match probed.format.next_packet() {
                 Ok(packet) => packet,
                 Err(Error::IoError(_)) => break decoder.last_decoded(),
-                Err(e) => return Err(e),
+                Err(e) => return Err(Self::init_error(e)),
             };
 
             // If the packet does not belong to the selected track, skip over it
@@ -132,7 +278,7 @@ impl SymphoniaDecoder {
                         // and continues processing the rest of the stream.
                         continue;
                     }
-                    _ => return Err(e),
+                    _ => return Err(Self::init_error(e)),
                 },
             }
         };
@@ -140,15 +286,35 @@ impl SymphoniaDecoder {
         let buffer = SymphoniaDecoder::get_buffer(decoded, &spec);
         Ok(Some(SymphoniaDecoder {
             decoder,
+            track_id,
+            track_selection: settings.track.clone(),
+            chapters,
             current_span_offset: 0,
             format: probed.format,
             total_duration,
             buffer,
             spec,
             seek_mode,
+            is_stream: settings.is_stream,
         }))
     }
 
+    #[inline]
+    pub(crate) fn chapters(&self) -> &[Chapter] {
+        &self.chapters
+    }
+
+    /// All tracks in the container.
+    pub(crate) fn tracks(&self) -> Vec<TrackInfo> {
+        self.format.tracks().iter().map(TrackInfo::new).collect()
+    }
+
+    /// The id of the track being decoded.
+    #[inline]
+    pub(crate) fn track_id(&self) -> u32 {
+        self.track_id
+    }
+
     #[inline]
     fn get_buffer(decoded: AudioBufferRef, spec: &SignalSpec) -> SampleBuffer<Sample> {
         let duration = units::Duration::from(decoded.capacity() as u64);
@@ -211,7 +377,7 @@ impl Source for SymphoniaDecoder {
             self.seek_mode,
             SeekTo::Time {
                 time: target.into(),
-                track_id: None,
+                track_id: Some(self.track_id),
             },
         ) {
             Err(Error::SeekError(symphonia::core::errors::SeekErrorKind::ForwardOnly)) => {
@@ -267,6 +433,17 @@ pub enum SeekError {
 assert_error_traits!(SeekError);
 
 impl SymphoniaDecoder {
+    /// Recreates the codec decoder for the selected track, after the stream
+    /// changed its parameters.
+    fn reset_decoder(&mut self) -> Option<()> {
+        let track = select_track(self.format.tracks(), &self.track_selection).ok()?;
+        self.decoder = codecs()
+            .make(&track.codec_params, &DecoderOptions::default())
+            .ok()?;
+        self.track_id = track.id;
+        Some(())
+    }
+
     /// Note span offset must be set after
     fn refine_position(&mut self, seek_res: SeekedTo) -> Result<(), source::SeekError> {
         // Calculate the number of samples to skip.
@@ -299,8 +476,29 @@ impl Iterator for SymphoniaDecoder {
 
     fn next(&mut self) -> Option<Self::Item> {
         if self.current_span_offset >= self.buffer.len() {
+            let mut stream_errors = 0;
             let decoded = loop {
-                let packet = self.format.next_packet().ok()?;
+                let packet = match self.format.next_packet() {
+                    Ok(packet) => packet,
+                    // A live stream may carry damaged data, which the demuxer
+                    // resynchronizes past on the next call.
+                    Err(Error::DecodeError(_))
+                        if self.is_stream && stream_errors < MAX_STREAM_ERRORS =>
+                    {
+                        stream_errors += 1;
+                        continue;
+                    }
+                    // Chained streams, such as Ogg radio, start a new logical stream
+                    // with different codec parameters.
+                    Err(Error::ResetRequired) if self.is_stream => {
+                        self.reset_decoder()?;
+                        continue;
+                    }
+                    Err(_) => return None,
+                };
+                if packet.track_id() != self.track_id {
+                    continue;
+                }
                 let decoded = match self.decoder.decode(&packet) {
                     Ok(decoded) => decoded,
                     Err(Error::DecodeError(_)) => {
@@ -309,6 +507,10 @@ impl Iterator for SymphoniaDecoder {
                         // non-critical decode errors.
                         continue;
                     }
+                    Err(Error::ResetRequired) if self.is_stream => {
+                        self.reset_decoder()?;
+                        continue;
+                    }
                     Err(_) => return None,
                 };
 
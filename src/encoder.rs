@@ -0,0 +1,345 @@
+//! Encode a [`Source`] to a compressed or uncompressed audio file.
+
+use std::io;
+use std::path;
+use std::sync::Arc;
+
+use crate::common::assert_error_traits;
+#[cfg(feature = "dither")]
+use crate::source::DitherAlgorithm;
+use crate::source::UniformSourceIterator;
+use crate::{ChannelCount, Source};
+
+#[cfg(feature = "flac_output")]
+mod flac;
+#[cfg(feature = "opus_output")]
+mod opus;
+
+/// The file formats [`encode_to_writer`] can produce.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum EncodingFormat {
+    /// Uncompressed wav with integer samples.
+    #[cfg(feature = "wav_output")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "wav_output")))]
+    Wav,
+    /// Lossless FLAC with up to 8 channels.
+    #[cfg(feature = "flac_output")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "flac_output")))]
+    Flac,
+    /// Lossy Opus in an Ogg container with one or two channels. The audio is
+    /// resampled to 48 kHz, the only rate Opus decodes at.
+    #[cfg(feature = "opus_output")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "opus_output")))]
+    OggOpus,
+}
+
+impl EncodingFormat {
+    /// The usual file extension, without the dot.
+    pub fn extension(self) -> &'static str {
+        match self {
+            #[cfg(feature = "wav_output")]
+            EncodingFormat::Wav => "wav",
+            #[cfg(feature = "flac_output")]
+            EncodingFormat::Flac => "flac",
+            #[cfg(feature = "opus_output")]
+            EncodingFormat::OggOpus => "opus",
+        }
+    }
+}
+
+/// Settings for [`encode_to_writer`]. Each setting only affects the formats
+/// it applies to.
+///
+/// # Example
+/// ```
+/// use rodio::EncodeOptions;
+///
+/// let options = EncodeOptions::default()
+///     .with_bits_per_sample(24)
+///     .with_bitrate(64_000);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodeOptions {
+    bits_per_sample: u16,
+    bitrate: Option<u32>,
+    #[cfg(feature = "dither")]
+    dither: Option<DitherAlgorithm>,
+}
+
+impl Default for EncodeOptions {
+    fn default() -> Self {
+        Self {
+            bits_per_sample: 16,
+            bitrate: None,
+            #[cfg(feature = "dither")]
+            dither: Some(DitherAlgorithm::default()),
+        }
+    }
+}
+
+impl EncodeOptions {
+    /// Bits per sample of lossless formats. Wav supports 16, 24 and 32 bits,
+    /// FLAC 16 and 24 bits.
+    ///
+    /// Default is 16.
+    pub fn with_bits_per_sample(mut self, bits: u16) -> Self {
+        self.bits_per_sample = bits;
+        self
+    }
+
+    /// Target bitrate in bits per second of lossy formats. By default the
+    /// encoder picks a bitrate based on the channel count.
+    pub fn with_bitrate(mut self, bits_per_second: u32) -> Self {
+        self.bitrate = Some(bits_per_second);
+        self
+    }
+
+    /// The dither applied when lossless formats quantize samples to integers,
+    /// or `None` to only round.
+    ///
+    /// Default is [`DitherAlgorithm::TPDF`].
+    #[cfg(feature = "dither")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "dither")))]
+    pub fn with_dither(mut self, algorithm: Option<DitherAlgorithm>) -> Self {
+        self.dither = algorithm;
+        self
+    }
+}
+
+/// Errors that can occur when encoding.
+#[derive(Debug, thiserror::Error, Clone)]
+pub enum EncodeError {
+    /// The output file could not be created.
+    #[error("Opening file for writing")]
+    OpenFile(#[source] Arc<io::Error>),
+    /// The encoded audio could not be written.
+    #[error("Failed to write encoded audio")]
+    Writing(#[source] Arc<io::Error>),
+    /// The format can not store this many channels.
+    #[error("{format:?} can not encode {channels} channels")]
+    UnsupportedChannelCount {
+        /// The requested format.
+        format: EncodingFormat,
+        /// The channel count of the source.
+        channels: ChannelCount,
+    },
+    /// The format can not store samples with this many bits.
+    #[error("{format:?} can not encode {bits} bits per sample")]
+    UnsupportedBitsPerSample {
+        /// The requested format.
+        format: EncodingFormat,
+        /// The requested bits per sample.
+        bits: u16,
+    },
+    /// The source's sample rate is too high for the format.
+    #[error("{format:?} can not encode a sample rate of {sample_rate} Hz")]
+    UnsupportedSampleRate {
+        /// The requested format.
+        format: EncodingFormat,
+        /// The sample rate of the source.
+        sample_rate: u32,
+    },
+    /// Writing the wav file failed.
+    #[cfg(feature = "wav_output")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "wav_output")))]
+    #[error("Failed to write wav")]
+    Wav(#[source] crate::ToWavError),
+    /// The Opus encoder reported an error.
+    #[cfg(feature = "opus_output")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "opus_output")))]
+    #[error("Opus encoder failed")]
+    Opus(#[source] audiopus::Error),
+}
+assert_error_traits!(EncodeError);
+
+/// Encodes the source into a file.
+///
+/// If the file already exists it will be overwritten.
+///
+/// # Note
+/// This is a convenience wrapper around [`encode_to_writer`].
+pub fn encode_to_file(
+    source: impl Source,
+    path: impl AsRef<path::Path>,
+    format: EncodingFormat,
+    options: &EncodeOptions,
+) -> Result<(), EncodeError> {
+    let mut file = std::fs::File::create(path)
+        .map_err(Arc::new)
+        .map_err(EncodeError::OpenFile)?;
+    encode_to_writer(source, &mut file, format, options)
+}
+
+/// Encodes the source into `writer` until the source ends.
+///
+/// The output keeps the channel count and sample rate the source starts with,
+/// except where the format requires otherwise. Should the source change those
+/// later on, it is converted back.
+///
+/// # Example
+#[cfg_attr(not(feature = "flac_output"), doc = "```ignore")]
+#[cfg_attr(feature = "flac_output", doc = "```no_run")]
+/// use rodio::{encode_to_writer, EncodeOptions, EncodingFormat};
+/// use rodio::source::{SineWave, Source};
+/// use std::time::Duration;
+///
+/// let source = SineWave::new(440.0).take_duration(Duration::from_secs(1));
+/// let mut file = std::fs::File::create("tone.flac")?;
+/// encode_to_writer(source, &mut file, EncodingFormat::Flac, &EncodeOptions::default())?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn encode_to_writer(
+    source: impl Source,
+    writer: &mut (impl io::Write + io::Seek),
+    format: EncodingFormat,
+    options: &EncodeOptions,
+) -> Result<(), EncodeError> {
+    let channels = source.channels();
+    let sample_rate = source.sample_rate();
+    let unsupported_bits = || EncodeError::UnsupportedBitsPerSample {
+        format,
+        bits: options.bits_per_sample,
+    };
+
+    match format {
+        #[cfg(feature = "wav_output")]
+        EncodingFormat::Wav => {
+            use crate::wav_output::{WavOutputSettings, WavSampleFormat, WavWriterSink};
+
+            let sample_format = match options.bits_per_sample {
+                16 => WavSampleFormat::Int16,
+                24 => WavSampleFormat::Int24,
+                32 => WavSampleFormat::Int32,
+                _ => return Err(unsupported_bits()),
+            };
+            let settings = WavOutputSettings::default().with_sample_format(sample_format);
+            #[cfg(feature = "dither")]
+            let settings = settings.with_dither(options.dither);
+
+            let mut sink = WavWriterSink::new(writer, channels, sample_rate, settings)
+                .map_err(EncodeError::Wav)?;
+            sink.append(source).map_err(EncodeError::Wav)?;
+            sink.finalize().map_err(EncodeError::Wav)?;
+            Ok(())
+        }
+        #[cfg(feature = "flac_output")]
+        EncodingFormat::Flac => {
+            if !matches!(options.bits_per_sample, 16 | 24) {
+                return Err(unsupported_bits());
+            }
+            if channels.get() > 8 {
+                return Err(EncodeError::UnsupportedChannelCount { format, channels });
+            }
+            // The largest rate STREAMINFO can hold.
+            if sample_rate.get() > 0xf_ffff {
+                return Err(EncodeError::UnsupportedSampleRate {
+                    format,
+                    sample_rate: sample_rate.get(),
+                });
+            }
+
+            let source = UniformSourceIterator::new(source, channels, sample_rate);
+            #[cfg(feature = "dither")]
+            let source: Box<dyn Iterator<Item = crate::Sample>> = match options.dither {
+                Some(algorithm) => {
+                    let bits = crate::BitDepth::new(u32::from(options.bits_per_sample))
+                        .expect("bits per sample is 16 or 24");
+                    Box::new(source.dither(bits, algorithm))
+                }
+                None => Box::new(source),
+            };
+            flac::encode(
+                source,
+                channels.get(),
+                sample_rate.get(),
+                options.bits_per_sample as u8,
+                writer,
+            )
+            .map_err(Arc::new)
+            .map_err(EncodeError::Writing)
+        }
+        #[cfg(feature = "opus_output")]
+        EncodingFormat::OggOpus => {
+            if channels.get() > 2 {
+                return Err(EncodeError::UnsupportedChannelCount { format, channels });
+            }
+            let opus_rate = std::num::NonZero::new(opus::SAMPLE_RATE).expect("not zero");
+            let source = UniformSourceIterator::new(source, channels, opus_rate);
+            opus::encode(
+                source,
+                channels.get(),
+                sample_rate.get(),
+                options.bitrate,
+                writer,
+            )
+        }
+    }
+}
+
+#[cfg(all(test, feature = "flac_output", feature = "flac"))]
+mod tests {
+    use std::io::Cursor;
+    use std::time::Duration;
+
+    use super::{encode_to_writer, EncodeOptions, EncodingFormat};
+    use crate::buffer::SamplesBuffer;
+    use crate::math::nz;
+    use crate::source::{SineWave, Source};
+    use crate::{Decoder, Sample};
+
+    fn encode_flac(source: impl Source, options: &EncodeOptions) -> Vec<u8> {
+        let mut writer = Cursor::new(Vec::new());
+        encode_to_writer(source, &mut writer, EncodingFormat::Flac, options).unwrap();
+        writer.into_inner()
+    }
+
+    #[test]
+    fn flac_round_trip_is_lossless() {
+        // Values that are exact in 16 bits survive unchanged.
+        let samples: Vec<_> = (0..10_000)
+            .map(|i| ((i * 37 % 2000) as Sample - 1000.0) / 32768.0)
+            .collect();
+        let source = SamplesBuffer::new(nz!(2), nz!(44_100), samples.clone());
+        let options = EncodeOptions::default();
+        #[cfg(feature = "dither")]
+        let options = options.with_dither(None);
+        let bytes = encode_flac(source, &options);
+        assert!(bytes.len() < samples.len() * 2);
+
+        let decoder = Decoder::new(Cursor::new(bytes)).unwrap();
+        assert_eq!(decoder.channels(), nz!(2));
+        assert_eq!(decoder.sample_rate(), nz!(44_100));
+        let decoded: Vec<_> = decoder.collect();
+        assert_eq!(decoded, samples);
+    }
+
+    #[test]
+    fn flac_24_bit_sine() {
+        let source = SineWave::new(440.0)
+            .amplify(0.5)
+            .take_duration(Duration::from_millis(200));
+        let expected: Vec<_> = source.clone().collect();
+        let options = EncodeOptions::default().with_bits_per_sample(24);
+        let bytes = encode_flac(source, &options);
+
+        let decoded: Vec<_> = Decoder::new(Cursor::new(bytes)).unwrap().collect();
+        assert_eq!(decoded.len(), expected.len());
+        for (decoded, expected) in decoded.iter().zip(expected) {
+            assert!((decoded - expected).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn flac_rejects_unsupported_bits() {
+        let source = SamplesBuffer::new(nz!(1), nz!(8000), vec![0.0; 10]);
+        let options = EncodeOptions::default().with_bits_per_sample(20);
+        let result = encode_to_writer(
+            source,
+            &mut Cursor::new(Vec::new()),
+            EncodingFormat::Flac,
+            &options,
+        );
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,439 @@
+//! A small FLAC encoder.
+//!
+//! Every channel is coded on its own using the fixed polynomial predictors
+//! (or a constant or verbatim subframe when that is smaller) with Rice coded
+//! residuals. That gets most of the compression a full LPC encoder achieves
+//! at a fraction of the complexity.
+
+use std::io::{self, Seek, SeekFrom, Write};
+
+use dasp_sample::Sample as DaspSample;
+
+use crate::Sample;
+
+/// Frames per block, the most common FLAC block size.
+const BLOCK_SIZE: usize = 4096;
+const MAX_PARTITION_ORDER: u32 = 8;
+const MAX_FIXED_ORDER: usize = 4;
+/// Rice parameters are stored with 5 bits (RICE2), 31 marks an escape.
+const MAX_RICE_PARAMETER: u32 = 30;
+const STREAMINFO_LEN: usize = 34;
+
+/// Writes interleaved `samples` as a FLAC stream. The samples are rounded to
+/// `bits_per_sample` bits.
+pub(super) fn encode(
+    mut samples: impl Iterator<Item = Sample>,
+    channels: u16,
+    sample_rate: u32,
+    bits_per_sample: u8,
+    writer: &mut (impl Write + Seek),
+) -> io::Result<()> {
+    let start = writer.stream_position()?;
+    let mut stream = StreamInfo {
+        channels,
+        sample_rate,
+        bits_per_sample,
+        total_frames: 0,
+        min_frame_len: u32::MAX,
+        max_frame_len: 0,
+    };
+    writer.write_all(b"fLaC")?;
+    // A STREAMINFO block that is also the last metadata block.
+    writer.write_all(&[0x80, 0, 0, STREAMINFO_LEN as u8])?;
+    writer.write_all(&stream.to_bytes())?;
+
+    let scale = (1_u64 << (bits_per_sample - 1)) as f64;
+    let quantize = |sample: Sample| {
+        (sample.to_sample::<f64>() * scale)
+            .round()
+            .clamp(-scale, scale - 1.0) as i64
+    };
+
+    let mut block: Vec<Vec<i64>> = vec![Vec::with_capacity(BLOCK_SIZE); channels as usize];
+    let mut frame = BitWriter::default();
+    for frame_number in 0_u64.. {
+        block.iter_mut().for_each(Vec::clear);
+        'fill: for _ in 0..BLOCK_SIZE {
+            for channel in &mut block {
+                match samples.next() {
+                    Some(sample) => channel.push(quantize(sample)),
+                    None => break 'fill,
+                }
+            }
+        }
+        // An incomplete frame at the end is dropped.
+        let block_len = block.iter().map(Vec::len).min().unwrap_or(0);
+        if block_len == 0 {
+            break;
+        }
+        block
+            .iter_mut()
+            .for_each(|channel| channel.truncate(block_len));
+
+        write_frame(&mut frame, &block, frame_number, &stream);
+        writer.write_all(&frame.bytes)?;
+
+        let frame_len = frame.bytes.len() as u32;
+        stream.min_frame_len = stream.min_frame_len.min(frame_len);
+        stream.max_frame_len = stream.max_frame_len.max(frame_len);
+        stream.total_frames += block_len as u64;
+    }
+
+    let end = writer.stream_position()?;
+    writer.seek(SeekFrom::Start(start + 8))?;
+    writer.write_all(&stream.to_bytes())?;
+    writer.seek(SeekFrom::Start(end))?;
+    Ok(())
+}
+
+struct StreamInfo {
+    channels: u16,
+    sample_rate: u32,
+    bits_per_sample: u8,
+    total_frames: u64,
+    min_frame_len: u32,
+    max_frame_len: u32,
+}
+
+impl StreamInfo {
+    fn to_bytes(&self) -> [u8; STREAMINFO_LEN] {
+        let mut bytes = [0; STREAMINFO_LEN];
+        bytes[0..2].copy_from_slice(&(BLOCK_SIZE as u16).to_be_bytes());
+        bytes[2..4].copy_from_slice(&(BLOCK_SIZE as u16).to_be_bytes());
+        // Zero means unknown, which is what they are until the end.
+        if self.min_frame_len <= self.max_frame_len {
+            bytes[4..7].copy_from_slice(&self.min_frame_len.to_be_bytes()[1..]);
+            bytes[7..10].copy_from_slice(&self.max_frame_len.to_be_bytes()[1..]);
+        }
+        let packed = (u64::from(self.sample_rate) << 44)
+            | (u64::from(self.channels - 1) << 41)
+            | (u64::from(self.bits_per_sample - 1) << 36)
+            | (self.total_frames & 0xf_ffff_ffff);
+        bytes[10..18].copy_from_slice(&packed.to_be_bytes());
+        // The remaining 16 bytes are the MD5 of the audio, all zero means it
+        // was not computed.
+        bytes
+    }
+}
+
+fn write_frame(frame: &mut BitWriter, block: &[Vec<i64>], frame_number: u64, stream: &StreamInfo) {
+    let block_len = block[0].len();
+    frame.clear();
+
+    // Sync code, reserved bit and fixed block size strategy.
+    frame.write(0b1111_1111_1111_1000, 16);
+    // Block size stored as a 16 bit value after the frame number.
+    frame.write(0b0111, 4);
+    let (rate_code, rate_extra) = sample_rate_code(stream.sample_rate);
+    frame.write(rate_code, 4);
+    // Independent channels.
+    frame.write(u64::from(stream.channels - 1), 4);
+    let size_code = match stream.bits_per_sample {
+        8 => 0b001,
+        12 => 0b010,
+        16 => 0b100,
+        20 => 0b101,
+        24 => 0b110,
+        32 => 0b111,
+        _ => 0b000,
+    };
+    frame.write(size_code, 3);
+    frame.write(0, 1);
+    write_utf8_number(frame, frame_number);
+    frame.write(block_len as u64 - 1, 16);
+    if let Some((value, bits)) = rate_extra {
+        frame.write(value, bits);
+    }
+    let header_crc = crc8(&frame.bytes);
+    frame.write(u64::from(header_crc), 8);
+
+    for channel in block {
+        write_subframe(frame, channel, u32::from(stream.bits_per_sample));
+    }
+
+    frame.align();
+    let crc = crc16(&frame.bytes);
+    frame.write(u64::from(crc), 16);
+}
+
+/// Returns the 4 bit code and any value stored at the end of the header.
+fn sample_rate_code(rate: u32) -> (u64, Option<(u64, u32)>) {
+    match rate {
+        88_200 => (0b0001, None),
+        176_400 => (0b0010, None),
+        192_000 => (0b0011, None),
+        8_000 => (0b0100, None),
+        16_000 => (0b0101, None),
+        22_050 => (0b0110, None),
+        24_000 => (0b0111, None),
+        32_000 => (0b1000, None),
+        44_100 => (0b1001, None),
+        48_000 => (0b1010, None),
+        96_000 => (0b1011, None),
+        rate if rate % 1000 == 0 && rate / 1000 <= 0xff => {
+            (0b1100, Some((u64::from(rate / 1000), 8)))
+        }
+        rate if rate <= 0xffff => (0b1101, Some((u64::from(rate), 16))),
+        rate if rate % 10 == 0 && rate / 10 <= 0xffff => (0b1110, Some((u64::from(rate / 10), 16))),
+        // Taken from the STREAMINFO block.
+        _ => (0b0000, None),
+    }
+}
+
+/// Frame numbers are stored like UTF-8 code points, extended to 36 bits.
+fn write_utf8_number(frame: &mut BitWriter, value: u64) {
+    if value < 0x80 {
+        frame.write(value, 8);
+        return;
+    }
+    let len = match value {
+        0x80..0x800 => 2,
+        0x800..0x1_0000 => 3,
+        0x1_0000..0x20_0000 => 4,
+        0x20_0000..0x400_0000 => 5,
+        0x400_0000..0x8000_0000 => 6,
+        _ => 7,
+    };
+    let marker = (0xff00_u64 >> len) & 0xff;
+    frame.write(marker | (value >> (6 * (len - 1))), 8);
+    for i in (0..len - 1).rev() {
+        frame.write(0x80 | ((value >> (6 * i)) & 0x3f), 8);
+    }
+}
+
+fn write_subframe(frame: &mut BitWriter, samples: &[i64], bits_per_sample: u32) {
+    if samples.iter().all(|sample| *sample == samples[0]) {
+        frame.write(0b0000_0000, 8);
+        frame.write_signed(samples[0], bits_per_sample);
+        return;
+    }
+
+    let verbatim_bits = samples.len() as u64 * u64::from(bits_per_sample);
+    let best = (0..=MAX_FIXED_ORDER.min(samples.len() - 1))
+        .map(|order| {
+            let residual = fixed_residual(samples, order);
+            let partitions = choose_partitions(&residual, samples.len(), order);
+            let bits = order as u64 * u64::from(bits_per_sample) + partitions.bits;
+            (bits, order, residual, partitions)
+        })
+        .min_by_key(|(bits, ..)| *bits);
+
+    match best {
+        Some((bits, order, residual, partitions)) if bits < verbatim_bits => {
+            frame.write(0b0001_0000 | (order as u64) << 1, 8);
+            for sample in &samples[..order] {
+                frame.write_signed(*sample, bits_per_sample);
+            }
+            write_residual(frame, &residual, order, &partitions);
+        }
+        _ => {
+            frame.write(0b0000_0010, 8);
+            for sample in samples {
+                frame.write_signed(*sample, bits_per_sample);
+            }
+        }
+    }
+}
+
+/// The prediction error of the fixed polynomial predictor of `order`, for
+/// every sample after the first `order` warm-up samples.
+fn fixed_residual(samples: &[i64], order: usize) -> Vec<i64> {
+    let s = samples;
+    (order..samples.len())
+        .map(|i| match order {
+            0 => s[i],
+            1 => s[i] - s[i - 1],
+            2 => s[i] - 2 * s[i - 1] + s[i - 2],
+            3 => s[i] - 3 * s[i - 1] + 3 * s[i - 2] - s[i - 3],
+            _ => s[i] - 4 * s[i - 1] + 6 * s[i - 2] - 4 * s[i - 3] + s[i - 4],
+        })
+        .collect()
+}
+
+struct Partitions {
+    order: u32,
+    parameters: Vec<u32>,
+    /// Estimated size of the coded residual.
+    bits: u64,
+}
+
+#[inline]
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+/// Picks the partition order and per partition Rice parameter that give the
+/// smallest residual.
+fn choose_partitions(residual: &[i64], block_len: usize, predictor_order: usize) -> Partitions {
+    let mut best: Option<Partitions> = None;
+    for order in 0..=MAX_PARTITION_ORDER {
+        let partition_len = block_len >> order;
+        if !block_len.is_multiple_of(1 << order) || partition_len <= predictor_order {
+            break;
+        }
+
+        let mut parameters = Vec::with_capacity(1 << order);
+        // Residual coding method and partition order.
+        let mut bits = 2 + 4;
+        let mut start = 0;
+        for partition in 0..1_usize << order {
+            let len = if partition == 0 {
+                partition_len - predictor_order
+            } else {
+                partition_len
+            };
+            let sum: u64 = residual[start..start + len]
+                .iter()
+                .map(|r| zigzag(*r))
+                .sum();
+            start += len;
+
+            let (parameter, partition_bits) = (0..=MAX_RICE_PARAMETER)
+                .map(|k| (k, len as u64 * u64::from(k + 1) + (sum >> k)))
+                .min_by_key(|(_, bits)| *bits)
+                .expect("there is at least one parameter");
+            parameters.push(parameter);
+            bits += 5 + partition_bits;
+        }
+
+        if best.as_ref().is_none_or(|best| bits < best.bits) {
+            best = Some(Partitions {
+                order,
+                parameters,
+                bits,
+            });
+        }
+    }
+    best.expect("partition order 0 always fits")
+}
+
+fn write_residual(
+    frame: &mut BitWriter,
+    residual: &[i64],
+    predictor_order: usize,
+    partitions: &Partitions,
+) {
+    // Coding method RICE2, 5 bit parameters.
+    frame.write(0b01, 2);
+    frame.write(u64::from(partitions.order), 4);
+
+    let partition_len = (residual.len() + predictor_order) >> partitions.order;
+    let mut start = 0;
+    for (partition, parameter) in partitions.parameters.iter().enumerate() {
+        // The warm-up samples count towards the first partition.
+        let len = if partition == 0 {
+            partition_len - predictor_order
+        } else {
+            partition_len
+        };
+        frame.write(u64::from(*parameter), 5);
+        for value in &residual[start..start + len] {
+            let value = zigzag(*value);
+            frame.write_unary(value >> parameter);
+            frame.write(value, *parameter);
+        }
+        start += len;
+    }
+}
+
+/// Collects bits, most significant bit first.
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    pending: u64,
+    pending_bits: u32,
+}
+
+impl BitWriter {
+    fn clear(&mut self) {
+        self.bytes.clear();
+        self.pending = 0;
+        self.pending_bits = 0;
+    }
+
+    /// Writes the lowest `bits` bits of `value`, at most 32.
+    #[inline]
+    fn write(&mut self, value: u64, bits: u32) {
+        debug_assert!(bits <= 32);
+        if bits == 0 {
+            return;
+        }
+        self.pending = (self.pending << bits) | (value & ((1 << bits) - 1));
+        self.pending_bits += bits;
+        while self.pending_bits >= 8 {
+            self.pending_bits -= 8;
+            self.bytes.push((self.pending >> self.pending_bits) as u8);
+        }
+    }
+
+    #[inline]
+    fn write_signed(&mut self, value: i64, bits: u32) {
+        self.write(value as u64, bits);
+    }
+
+    /// Writes `zeros` zero bits followed by a one.
+    #[inline]
+    fn write_unary(&mut self, mut zeros: u64) {
+        while zeros >= 32 {
+            self.write(0, 32);
+            zeros -= 32;
+        }
+        self.write(1, zeros as u32 + 1);
+    }
+
+    /// Pads with zero bits up to the next byte boundary.
+    fn align(&mut self) {
+        if self.pending_bits > 0 {
+            self.write(0, 8 - self.pending_bits);
+        }
+    }
+}
+
+fn crc8(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0, |mut crc, byte| {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x07
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
+fn crc16(bytes: &[u8]) -> u16 {
+    bytes.iter().fold(0, |mut crc, byte| {
+        crc ^= u16::from(*byte) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x8005
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{crc16, crc8, BitWriter};
+
+    #[test]
+    fn crc_check_values() {
+        assert_eq!(crc8(b"123456789"), 0xf4);
+        assert_eq!(crc16(b"123456789"), 0xfee8);
+    }
+
+    #[test]
+    fn bit_writer_packs_msb_first() {
+        let mut writer = BitWriter::default();
+        writer.write(0b101, 3);
+        writer.write_unary(2);
+        writer.write_signed(-1, 4);
+        writer.align();
+        assert_eq!(writer.bytes, [0b1010_0111, 0b1100_0000]);
+    }
+}
@@ -0,0 +1,234 @@
+//! Ogg Opus output (RFC 7845) using libopus.
+
+use std::io::Write;
+use std::sync::Arc;
+
+use audiopus::coder::Encoder;
+use audiopus::{Application, Bitrate, Channels, SampleRate};
+use dasp_sample::Sample as DaspSample;
+use ogg::writing::{PacketWriteEndInfo, PacketWriter};
+
+use super::EncodeError;
+use crate::Sample;
+
+/// Opus always decodes at 48 kHz.
+pub(super) const SAMPLE_RATE: u32 = 48_000;
+/// 20 ms, the frame size recommended for general purpose audio.
+const FRAME_LEN: usize = 960;
+/// Recommended maximum packet size.
+const MAX_PACKET_LEN: usize = 4000;
+/// One second of packets. Demuxers need more than one page to find where the
+/// padding at the end starts, and seek with page granularity.
+const PACKETS_PER_PAGE: u64 = 50;
+
+/// Encodes interleaved `samples` at 48 kHz with one or two channels.
+pub(super) fn encode(
+    samples: impl Iterator<Item = Sample>,
+    channels: u16,
+    input_sample_rate: u32,
+    bitrate: Option<u32>,
+    writer: &mut impl Write,
+) -> Result<(), EncodeError> {
+    let opus_channels = match channels {
+        1 => Channels::Mono,
+        _ => Channels::Stereo,
+    };
+    let mut encoder = Encoder::new(SampleRate::Hz48000, opus_channels, Application::Audio)
+        .map_err(EncodeError::Opus)?;
+    if let Some(bitrate) = bitrate {
+        let bitrate = i32::try_from(bitrate).unwrap_or(i32::MAX);
+        encoder
+            .set_bitrate(Bitrate::BitsPerSecond(bitrate))
+            .map_err(EncodeError::Opus)?;
+    }
+    // The decoder has to discard this many samples at the start.
+    let pre_skip = encoder.lookahead().map_err(EncodeError::Opus)? as usize;
+
+    let serial = stream_serial();
+    let mut ogg = PacketWriter::new(writer);
+    let head = opus_head(channels as u8, pre_skip as u16, input_sample_rate);
+    write_packet(&mut ogg, head, serial, PacketWriteEndInfo::EndPage, 0)?;
+    let tags = opus_tags();
+    write_packet(&mut ogg, tags, serial, PacketWriteEndInfo::EndPage, 0)?;
+
+    let channels = channels as usize;
+    // Pad the end so everything still in the encoder's look-ahead comes out.
+    let mut samples = samples
+        .map(|sample| sample.to_sample::<f32>())
+        .chain(std::iter::repeat_n(0.0, pre_skip * channels))
+        .peekable();
+    let mut frame = Vec::with_capacity(FRAME_LEN * channels);
+    let mut packet = vec![0; MAX_PACKET_LEN];
+    let mut input_len = 0;
+    let mut granule = 0;
+    loop {
+        frame.clear();
+        frame.extend(samples.by_ref().take(FRAME_LEN * channels));
+        if frame.is_empty() {
+            break;
+        }
+        input_len += frame.len();
+        frame.resize(FRAME_LEN * channels, 0.0);
+        granule += FRAME_LEN as u64;
+
+        let len = encoder
+            .encode_float(&frame, &mut packet)
+            .map_err(EncodeError::Opus)?;
+        let end = if samples.peek().is_none() {
+            // The granule position of the last page tells the decoder where
+            // the padding starts.
+            granule = (input_len / channels) as u64;
+            PacketWriteEndInfo::EndStream
+        } else if granule % (PACKETS_PER_PAGE * FRAME_LEN as u64) == 0 {
+            PacketWriteEndInfo::EndPage
+        } else {
+            PacketWriteEndInfo::NormalPacket
+        };
+        write_packet(&mut ogg, packet[..len].to_vec(), serial, end, granule)?;
+    }
+    Ok(())
+}
+
+fn write_packet(
+    ogg: &mut PacketWriter<&mut impl Write>,
+    packet: Vec<u8>,
+    serial: u32,
+    end: PacketWriteEndInfo,
+    granule: u64,
+) -> Result<(), EncodeError> {
+    ogg.write_packet(packet.into_boxed_slice(), serial, end, granule)
+        .map_err(Arc::new)
+        .map_err(EncodeError::Writing)
+}
+
+/// Ogg streams should use a random serial number so streams can be chained.
+fn stream_serial() -> u32 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0x726f_6469, |time| time.subsec_nanos())
+}
+
+fn opus_head(channels: u8, pre_skip: u16, input_sample_rate: u32) -> Vec<u8> {
+    let mut head = Vec::with_capacity(19);
+    head.extend_from_slice(b"OpusHead");
+    // Version.
+    head.push(1);
+    head.push(channels);
+    head.extend_from_slice(&pre_skip.to_le_bytes());
+    head.extend_from_slice(&input_sample_rate.to_le_bytes());
+    // Output gain.
+    head.extend_from_slice(&0_i16.to_le_bytes());
+    // Channel mapping family 0: mono or stereo.
+    head.push(0);
+    head
+}
+
+fn opus_tags() -> Vec<u8> {
+    let vendor = concat!("rodio ", env!("CARGO_PKG_VERSION"));
+    let mut tags = Vec::new();
+    tags.extend_from_slice(b"OpusTags");
+    tags.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+    tags.extend_from_slice(vendor.as_bytes());
+    // No user comments.
+    tags.extend_from_slice(&0_u32.to_le_bytes());
+    tags
+}
+
+#[cfg(all(test, feature = "opus"))]
+mod tests {
+    use std::io::Cursor;
+
+    use ogg::reading::PacketReader;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::math::nz;
+    use crate::source::{SineWave, Source};
+    use crate::{encode_to_writer, Decoder, EncodeOptions, EncodingFormat, Sample};
+
+    // 1.5 s at 48 kHz, not a whole number of 20 ms frames.
+    const FRAMES: usize = 72_000 + 100;
+
+    fn sine() -> SamplesBuffer {
+        let samples: Vec<_> = SineWave::new(440.0).amplify(0.5).take(FRAMES).collect();
+        SamplesBuffer::new(nz!(1), nz!(48_000), samples)
+    }
+
+    fn encode(source: impl Source) -> Vec<u8> {
+        let mut writer = Cursor::new(Vec::new());
+        let options = EncodeOptions::default().with_bitrate(96_000);
+        encode_to_writer(source, &mut writer, EncodingFormat::OggOpus, &options).unwrap();
+        writer.into_inner()
+    }
+
+    #[test]
+    fn writes_headers_and_granule_positions() {
+        let bytes = encode(sine());
+        let mut reader = PacketReader::new(Cursor::new(bytes));
+
+        let head = reader.read_packet().unwrap().unwrap();
+        assert!(head.first_in_stream() && head.last_in_page());
+        assert_eq!(head.absgp_page(), 0);
+        assert_eq!(&head.data[..8], b"OpusHead");
+        assert_eq!(head.data.len(), 19);
+        // Version 1, mono.
+        assert_eq!(head.data[8..10], [1, 1]);
+        let pre_skip = u16::from_le_bytes([head.data[10], head.data[11]]) as u64;
+        assert!(pre_skip > 0);
+        assert_eq!(head.data[12..16], 48_000u32.to_le_bytes());
+        // No output gain, mapping family 0.
+        assert_eq!(head.data[16..], [0, 0, 0]);
+
+        let tags = reader.read_packet().unwrap().unwrap();
+        assert!(tags.last_in_page());
+        assert_eq!(tags.absgp_page(), 0);
+        assert_eq!(&tags.data[..8], b"OpusTags");
+        let vendor_len = u32::from_le_bytes(tags.data[8..12].try_into().unwrap()) as usize;
+        assert!(tags.data[12..12 + vendor_len].starts_with(b"rodio "));
+        assert_eq!(tags.data[12 + vendor_len..], 0u32.to_le_bytes());
+
+        let mut packets = 0;
+        let mut granule = 0;
+        while let Some(packet) = reader.read_packet().unwrap() {
+            packets += 1;
+            if packet.last_in_stream() {
+                granule = packet.absgp_page();
+                break;
+            }
+            if packet.last_in_page() {
+                // Pages end after whole seconds of audio.
+                assert_eq!(packet.absgp_page(), granule + 48_000);
+                granule = packet.absgp_page();
+            }
+        }
+        // The padding flushing the look-ahead is not counted at the end.
+        assert_eq!(granule, pre_skip + FRAMES as u64);
+        assert_eq!(packets, (FRAMES + pre_skip as usize).div_ceil(960));
+    }
+
+    #[test]
+    fn round_trip_trims_pre_skip() {
+        let expected: Vec<_> = sine().collect();
+        let bytes = encode(sine());
+        // The Ogg demuxer only finds the end of a seekable stream of known length.
+        let decoder = Decoder::builder()
+            .with_byte_len(bytes.len() as u64)
+            .with_data(Cursor::new(bytes))
+            .with_hint("opus")
+            .with_seekable(true)
+            .build()
+            .unwrap();
+        assert_eq!(decoder.channels(), nz!(1));
+        assert_eq!(decoder.sample_rate(), nz!(48_000));
+        let decoded: Vec<_> = decoder.collect();
+        assert_eq!(decoded.len(), FRAMES);
+
+        // Lossy, but in phase with the input once the pre-skip is gone.
+        let error = decoded
+            .iter()
+            .zip(&expected)
+            .map(|(decoded, expected)| (decoded - expected).powi(2))
+            .sum::<Sample>()
+            / FRAMES as Sample;
+        assert!(error.sqrt() < 0.02, "rms error {}", error.sqrt());
+    }
+}
@@ -1,8 +1,46 @@
 //! Sources of sound and various filters which never change sample rate or
 //! channel count.
+//!
+//! Because the format of a [`FixedSource`] never changes, the filters in this
+//! module never have to check for span boundaries. Turn any [`Source`] into a
+//! `FixedSource` with [`Source::into_fixed`], which resamples and remixes it only
+//! where needed.
+//!
+//! # Example
+//!
+//! ```
+//! use std::time::Duration;
+//! use rodio::fixed_source::{self, FixedConfig};
+//! use rodio::math::nz;
+//! use rodio::source::{SineWave, Source};
+//! use rodio::FixedSource;
+//!
+//! let config = FixedConfig::new(nz!(2), nz!(48_000));
+//! let (mixer, output) = fixed_source::mixer(config);
+//! let beep = SineWave::new(440.0)
+//!     .into_fixed(config)
+//!     .take_duration(Duration::from_secs(1))
+//!     .amplify(0.2);
+//! mixer.add(beep).unwrap();
+//! ```
 use std::time::Duration;
 
-use crate::{ChannelCount, Sample, SampleRate};
+use crate::common::assert_error_traits;
+#[cfg(any(doc, test))]
+use crate::Source;
+use crate::{ChannelCount, Float, Sample, SampleRate};
+
+pub(crate) mod convert;
+mod effects;
+pub mod mixer;
+pub mod queue;
+
+pub use self::convert::IntoFixed;
+pub use self::effects::{
+    Amplify, FadeIn, Pausable, PeriodicAccess, SkipDuration, Stoppable, TakeDuration,
+};
+pub use self::mixer::{mixer, FixedMixer, FixedMixerSource};
+pub use self::queue::{queue, FixedQueue, FixedQueueOutput};
 
 /// Similar to `Source`, something that can produce interleaved samples for a
 /// fixed amount of channels at a fixed sample rate. Those parameters never
@@ -16,4 +54,153 @@ pub trait FixedSource: Iterator<Item = Sample> {
     ///
     /// `None` indicates at the same time "infinite" or "unknown".
     fn total_duration(&self) -> Option<Duration>;
+
+    /// Returns the channel count and sample rate of this source.
+    #[inline]
+    fn config(&self) -> FixedConfig {
+        FixedConfig::new(self.channels(), self.sample_rate())
+    }
+
+    /// Amplifies the sound by the given value.
+    #[inline]
+    fn amplify(self, value: Float) -> Amplify<Self>
+    where
+        Self: Sized,
+    {
+        effects::amplify(self, value)
+    }
+
+    /// Takes a certain duration of this source and then stops. The duration is
+    /// rounded down to whole frames.
+    #[inline]
+    fn take_duration(self, duration: Duration) -> TakeDuration<Self>
+    where
+        Self: Sized,
+    {
+        effects::take_duration(self, duration)
+    }
+
+    /// Skips a certain duration of this source before the first sample is played.
+    #[inline]
+    fn skip_duration(self, duration: Duration) -> SkipDuration<Self>
+    where
+        Self: Sized,
+    {
+        effects::skip_duration(self, duration)
+    }
+
+    /// Fades in the sound linearly over the given duration.
+    #[inline]
+    fn fade_in(self, duration: Duration) -> FadeIn<Self>
+    where
+        Self: Sized,
+    {
+        effects::fade_in(self, duration)
+    }
+
+    /// Makes the sound pausable. While paused it plays silence.
+    #[inline]
+    fn pausable(self, initially_paused: bool) -> Pausable<Self>
+    where
+        Self: Sized,
+    {
+        effects::pausable(self, initially_paused)
+    }
+
+    /// Makes the sound stoppable.
+    #[inline]
+    fn stoppable(self) -> Stoppable<Self>
+    where
+        Self: Sized,
+    {
+        effects::stoppable(self)
+    }
+
+    /// Calls the `access` closure on `Self` the first time the source is
+    /// iterated and every time `period` elapses, always on a frame boundary.
+    #[inline]
+    fn periodic_access<F>(self, period: Duration, access: F) -> PeriodicAccess<Self, F>
+    where
+        Self: Sized,
+        F: FnMut(&mut Self),
+    {
+        effects::periodic_access(self, period, access)
+    }
+}
+
+/// The channel count and sample rate of a [`FixedSource`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedConfig {
+    /// The number of channels.
+    pub channel_count: ChannelCount,
+    /// The sample rate.
+    pub sample_rate: SampleRate,
+}
+
+impl FixedConfig {
+    /// Creates a config from a channel count and a sample rate.
+    #[inline]
+    pub fn new(channel_count: ChannelCount, sample_rate: SampleRate) -> Self {
+        Self {
+            channel_count,
+            sample_rate,
+        }
+    }
+
+    /// Returns an error if `source` does not have this config.
+    pub(crate) fn check(&self, source: &impl FixedSource) -> Result<(), FormatMismatch> {
+        let found = source.config();
+        if found == *self {
+            Ok(())
+        } else {
+            Err(FormatMismatch {
+                expected: *self,
+                found,
+            })
+        }
+    }
+
+    /// Converts a number of samples, summed over all channels, to a duration.
+    pub(crate) fn samples_to_duration(&self, samples: u64) -> Duration {
+        let frames = samples / self.channel_count.get() as u64;
+        crate::math::frames_to_duration(frames, self.sample_rate)
+    }
+
+    /// Converts a duration to whole frames, rounding down, in samples summed over
+    /// all channels.
+    pub(crate) fn duration_to_samples(&self, duration: Duration) -> u64 {
+        crate::math::duration_to_frames(duration, self.sample_rate)
+            * self.channel_count.get() as u64
+    }
+}
+
+/// Stereo at 10 Hz, few enough samples per second for tests to write out.
+#[cfg(test)]
+pub(crate) const TEST_CONFIG: FixedConfig = FixedConfig {
+    channel_count: crate::math::nz!(2),
+    sample_rate: crate::math::nz!(10),
+};
+
+/// A source in [`TEST_CONFIG`] playing `samples`.
+#[cfg(test)]
+pub(crate) fn stereo(samples: Vec<Sample>) -> impl FixedSource + Send {
+    crate::buffer::SamplesBuffer::new(TEST_CONFIG.channel_count, TEST_CONFIG.sample_rate, samples)
+        .into_fixed(TEST_CONFIG)
+}
+
+/// A [`FixedSource`] does not have the channel count or sample rate it is used with.
+#[derive(Debug, thiserror::Error, Clone, Copy, PartialEq, Eq)]
+#[error(
+    "The source has {} channels at {} Hz, but {} channels at {} Hz are needed",
+    found.channel_count,
+    found.sample_rate,
+    expected.channel_count,
+    expected.sample_rate
+)]
+pub struct FormatMismatch {
+    /// The format that is needed.
+    pub expected: FixedConfig,
+    /// The format of the source.
+    pub found: FixedConfig,
 }
+assert_error_traits! {FormatMismatch}
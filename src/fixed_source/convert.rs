@@ -0,0 +1,229 @@
+use std::time::Duration;
+
+use super::{FixedConfig, FixedSource};
+use crate::source::UniformSourceIterator;
+use crate::{ChannelCount, Sample, SampleRate, Source};
+
+/// Internal function that builds an `IntoFixed` object.
+pub fn into_fixed<I>(input: I, config: FixedConfig) -> IntoFixed<I>
+where
+    I: Source,
+{
+    let total_duration = input.total_duration();
+    let inner =
+        if input.channels() == config.channel_count && input.sample_rate() == config.sample_rate {
+            let span_left = input.current_span_len();
+            Inner::Direct { input, span_left }
+        } else {
+            Inner::Converted(UniformSourceIterator::new(
+                input,
+                config.channel_count,
+                config.sample_rate,
+            ))
+        };
+    IntoFixed {
+        inner: Some(inner),
+        config,
+        total_duration,
+    }
+}
+
+/// A [`FixedSource`] made from a [`Source`] by resampling and remixing it.
+///
+/// Created with [`Source::into_fixed`]. As long as the spans of the input already
+/// have the target format its samples are passed on unchanged; only span
+/// boundaries are checked. From the first span with another format on, the input
+/// is converted.
+pub struct IntoFixed<I>
+where
+    I: Source,
+{
+    inner: Option<Inner<I>>,
+    config: FixedConfig,
+    total_duration: Option<Duration>,
+}
+
+enum Inner<I>
+where
+    I: Source,
+{
+    /// The input has the target format. `span_left` counts the samples left in
+    /// its current span.
+    Direct {
+        input: I,
+        span_left: Option<usize>,
+    },
+    Converted(UniformSourceIterator<I>),
+}
+
+impl<I> IntoFixed<I>
+where
+    I: Source,
+{
+    /// Returns the inner source. Samples buffered for conversion are lost.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        match self.inner.expect("only None while switching to conversion") {
+            Inner::Direct { input, .. } => input,
+            Inner::Converted(input) => input.into_inner(),
+        }
+    }
+
+    /// Starts converting the input, which has just begun a span in another format.
+    fn convert(&mut self) {
+        let Some(Inner::Direct { input, .. }) = self.inner.take() else {
+            unreachable!("only called while passing samples on directly");
+        };
+        self.inner = Some(Inner::Converted(UniformSourceIterator::new(
+            input,
+            self.config.channel_count,
+            self.config.sample_rate,
+        )));
+    }
+}
+
+impl<I> Iterator for IntoFixed<I>
+where
+    I: Source,
+{
+    type Item = Sample;
+
+    #[inline]
+    fn next(&mut self) -> Option<Sample> {
+        let (sample, span_ended) = match self.inner.as_mut().expect("only None while switching") {
+            Inner::Direct { input, span_left } => {
+                let sample = input.next()?;
+                match span_left {
+                    Some(left) => {
+                        *left = left.saturating_sub(1);
+                        (sample, *left == 0)
+                    }
+                    None => (sample, false),
+                }
+            }
+            Inner::Converted(input) => return input.next(),
+        };
+
+        // Sources describe their next span as soon as the current one is played.
+        if span_ended {
+            let Some(Inner::Direct { input, span_left }) = &mut self.inner else {
+                unreachable!();
+            };
+            if input.channels() == self.config.channel_count
+                && input.sample_rate() == self.config.sample_rate
+            {
+                *span_left = input.current_span_len();
+            } else {
+                self.convert();
+            }
+        }
+        Some(sample)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.inner.as_ref().expect("only None while switching") {
+            Inner::Direct { input, .. } => (input.size_hint().0, None),
+            Inner::Converted(input) => input.size_hint(),
+        }
+    }
+}
+
+impl<I> FixedSource for IntoFixed<I>
+where
+    I: Source,
+{
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.config.channel_count
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.config.sample_rate
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.total_duration
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::SamplesBuffer;
+    use crate::math::nz;
+    use crate::source::SeekError;
+
+    /// A stereo span of two samples followed by a mono span of one sample.
+    struct TwoSpans {
+        pos: usize,
+    }
+
+    impl Iterator for TwoSpans {
+        type Item = Sample;
+
+        fn next(&mut self) -> Option<Sample> {
+            self.pos += 1;
+            (self.pos <= 3).then_some(self.pos as Sample)
+        }
+    }
+
+    impl Source for TwoSpans {
+        fn current_span_len(&self) -> Option<usize> {
+            Some(match self.pos {
+                0..2 => 2,
+                2 => 1,
+                _ => 0,
+            })
+        }
+
+        fn channels(&self) -> ChannelCount {
+            if self.pos < 2 {
+                nz!(2)
+            } else {
+                nz!(1)
+            }
+        }
+
+        fn sample_rate(&self) -> SampleRate {
+            nz!(1000)
+        }
+
+        fn total_duration(&self) -> Option<Duration> {
+            None
+        }
+
+        fn try_seek(&mut self, _: Duration) -> Result<(), SeekError> {
+            Err(SeekError::NotSupported {
+                underlying_source: std::any::type_name::<Self>(),
+            })
+        }
+    }
+
+    #[test]
+    fn passes_matching_source_through() {
+        let source = SamplesBuffer::new(nz!(2), nz!(1000), vec![1.0, 2.0, 3.0, 4.0]);
+        let fixed = source.into_fixed(FixedConfig::new(nz!(2), nz!(1000)));
+        assert!(matches!(fixed.inner, Some(Inner::Direct { .. })));
+        assert_eq!(fixed.collect::<Vec<_>>(), [1.0, 2.0, 3.0, 4.0]);
+    }
+
+    #[test]
+    fn converts_source_with_other_format() {
+        let source = SamplesBuffer::new(nz!(1), nz!(1000), vec![1.0, 2.0]);
+        let fixed = source.into_fixed(FixedConfig::new(nz!(2), nz!(1000)));
+        assert_eq!(fixed.channels(), nz!(2));
+        assert_eq!(fixed.collect::<Vec<_>>(), [1.0, 1.0, 2.0, 2.0]);
+    }
+
+    #[test]
+    fn converts_from_span_with_other_format() {
+        let mut fixed = TwoSpans { pos: 0 }.into_fixed(FixedConfig::new(nz!(2), nz!(1000)));
+        assert_eq!(fixed.next(), Some(1.0));
+        assert_eq!(fixed.next(), Some(2.0));
+        assert!(matches!(fixed.inner, Some(Inner::Converted(_))));
+        assert_eq!(fixed.collect::<Vec<_>>(), [3.0, 3.0]);
+    }
+}
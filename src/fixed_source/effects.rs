@@ -0,0 +1,486 @@
+//! Filters for [`FixedSource`]s.
+//!
+//! The format of a `FixedSource` never changes, so unlike their counterparts in
+//! [`crate::source`] these never look at span lengths.
+
+use std::time::Duration;
+
+use super::FixedSource;
+use crate::{ChannelCount, Float, Sample, SampleRate};
+
+/// Internal function that builds an `Amplify` object.
+pub fn amplify<I>(input: I, factor: Float) -> Amplify<I>
+where
+    I: FixedSource,
+{
+    Amplify { input, factor }
+}
+
+/// Internal function that builds a `TakeDuration` object.
+pub fn take_duration<I>(input: I, duration: Duration) -> TakeDuration<I>
+where
+    I: FixedSource,
+{
+    let config = input.config();
+    let remaining = config.duration_to_samples(duration);
+    TakeDuration {
+        duration: config.samples_to_duration(remaining),
+        input,
+        remaining,
+    }
+}
+
+/// Internal function that builds a `SkipDuration` object.
+pub fn skip_duration<I>(input: I, duration: Duration) -> SkipDuration<I>
+where
+    I: FixedSource,
+{
+    let to_skip = input.config().duration_to_samples(duration);
+    SkipDuration { input, to_skip }
+}
+
+/// Internal function that builds a `FadeIn` object.
+pub fn fade_in<I>(input: I, duration: Duration) -> FadeIn<I>
+where
+    I: FixedSource,
+{
+    let config = input.config();
+    let total = config.duration_to_samples(duration) / config.channel_count.get() as u64;
+    FadeIn {
+        input,
+        total_frames: total,
+        elapsed_samples: 0,
+    }
+}
+
+/// Internal function that builds a `Pausable` object.
+pub fn pausable<I>(input: I, paused: bool) -> Pausable<I>
+where
+    I: FixedSource,
+{
+    Pausable {
+        input,
+        paused,
+        channel: 0,
+        paused_in_frame: paused,
+    }
+}
+
+/// Internal function that builds a `Stoppable` object.
+pub fn stoppable<I>(input: I) -> Stoppable<I>
+where
+    I: FixedSource,
+{
+    Stoppable {
+        input,
+        stopped: false,
+    }
+}
+
+/// Internal function that builds a `PeriodicAccess` object.
+pub fn periodic_access<I, F>(input: I, period: Duration, access: F) -> PeriodicAccess<I, F>
+where
+    I: FixedSource,
+    F: FnMut(&mut I),
+{
+    let config = input.config();
+    let channels = config.channel_count.get() as u64;
+    let update_period = config.duration_to_samples(period).max(channels);
+    PeriodicAccess {
+        input,
+        access,
+        update_period,
+        samples_until_update: 0,
+    }
+}
+
+/// Implements `FixedSource` for a filter that keeps the format of its `input`.
+macro_rules! impl_fixed_source {
+    ($name:ident $(<$($extra:ident),+>)?, |$this:ident| $total_duration:expr) => {
+        impl<I $($(, $extra)+)?> FixedSource for $name<I $($(, $extra)+)?>
+        where
+            Self: Iterator<Item = Sample>,
+            I: FixedSource,
+        {
+            #[inline]
+            fn channels(&self) -> ChannelCount {
+                self.input.channels()
+            }
+
+            #[inline]
+            fn sample_rate(&self) -> SampleRate {
+                self.input.sample_rate()
+            }
+
+            #[inline]
+            fn total_duration(&self) -> Option<Duration> {
+                let $this = self;
+                $total_duration
+            }
+        }
+
+        impl<I $($(, $extra)+)?> $name<I $($(, $extra)+)?>
+        where
+            I: FixedSource,
+        {
+            /// Returns a reference to the inner source.
+            #[inline]
+            pub fn inner(&self) -> &I {
+                &self.input
+            }
+
+            /// Returns a mutable reference to the inner source.
+            #[inline]
+            pub fn inner_mut(&mut self) -> &mut I {
+                &mut self.input
+            }
+
+            /// Returns the inner source.
+            #[inline]
+            pub fn into_inner(self) -> I {
+                self.input
+            }
+        }
+    };
+}
+
+/// Filter that multiplies each sample by a given value.
+#[derive(Clone, Debug)]
+pub struct Amplify<I> {
+    input: I,
+    factor: Float,
+}
+
+impl<I> Amplify<I> {
+    /// Modifies the amplification factor.
+    #[inline]
+    pub fn set_factor(&mut self, factor: Float) {
+        self.factor = factor;
+    }
+}
+
+impl<I> Iterator for Amplify<I>
+where
+    I: FixedSource,
+{
+    type Item = Sample;
+
+    #[inline]
+    fn next(&mut self) -> Option<Sample> {
+        self.input.next().map(|value| value * self.factor)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl_fixed_source!(Amplify, |this| this.input.total_duration());
+
+/// Plays a certain duration of a source and then stops.
+#[derive(Clone, Debug)]
+pub struct TakeDuration<I> {
+    input: I,
+    /// The duration to play, rounded down to whole frames.
+    duration: Duration,
+    /// Samples left to play.
+    remaining: u64,
+}
+
+impl<I> Iterator for TakeDuration<I>
+where
+    I: FixedSource,
+{
+    type Item = Sample;
+
+    #[inline]
+    fn next(&mut self) -> Option<Sample> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        self.input.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = usize::try_from(self.remaining).unwrap_or(usize::MAX);
+        let (lower, upper) = self.input.size_hint();
+        (
+            lower.min(remaining),
+            Some(upper.map_or(remaining, |upper| upper.min(remaining))),
+        )
+    }
+}
+
+impl_fixed_source!(TakeDuration, |this| {
+    Some(
+        this.input
+            .total_duration()
+            .map_or(this.duration, |total| total.min(this.duration)),
+    )
+});
+
+/// Skips a certain duration of a source before playing it.
+#[derive(Clone, Debug)]
+pub struct SkipDuration<I> {
+    input: I,
+    /// Samples still to skip before the first one is played.
+    to_skip: u64,
+}
+
+impl<I> Iterator for SkipDuration<I>
+where
+    I: FixedSource,
+{
+    type Item = Sample;
+
+    #[inline]
+    fn next(&mut self) -> Option<Sample> {
+        while self.to_skip > 0 {
+            self.to_skip -= 1;
+            self.input.next()?;
+        }
+        self.input.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let to_skip = usize::try_from(self.to_skip).unwrap_or(usize::MAX);
+        let (lower, upper) = self.input.size_hint();
+        (
+            lower.saturating_sub(to_skip),
+            upper.map(|upper| upper.saturating_sub(to_skip)),
+        )
+    }
+}
+
+impl_fixed_source!(SkipDuration, |this| {
+    let skipped = this.input.config().samples_to_duration(this.to_skip);
+    this.input
+        .total_duration()
+        .map(|total| total.saturating_sub(skipped))
+});
+
+/// Fades in a source linearly.
+#[derive(Clone, Debug)]
+pub struct FadeIn<I> {
+    input: I,
+    /// Length of the fade, in frames.
+    total_frames: u64,
+    /// Samples played so far, until the fade is done.
+    elapsed_samples: u64,
+}
+
+impl<I> Iterator for FadeIn<I>
+where
+    I: FixedSource,
+{
+    type Item = Sample;
+
+    #[inline]
+    fn next(&mut self) -> Option<Sample> {
+        let sample = self.input.next()?;
+        let frame = self.elapsed_samples / self.input.channels().get() as u64;
+        if frame >= self.total_frames {
+            return Some(sample);
+        }
+        self.elapsed_samples += 1;
+        Some(sample * frame as Float / self.total_frames as Float)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl_fixed_source!(FadeIn, |this| this.input.total_duration());
+
+/// Plays silence instead of the source while paused.
+///
+/// Pausing and resuming take effect at the next frame boundary, so the
+/// channels stay in order.
+#[derive(Clone, Debug)]
+pub struct Pausable<I> {
+    input: I,
+    paused: bool,
+    /// Channel of the next sample.
+    channel: u16,
+    /// Whether the current frame is silence.
+    paused_in_frame: bool,
+}
+
+impl<I> Pausable<I> {
+    /// Sets whether the source is paused.
+    #[inline]
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    /// Indicates if the source is paused.
+    #[inline]
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+}
+
+impl<I> Iterator for Pausable<I>
+where
+    I: FixedSource,
+{
+    type Item = Sample;
+
+    #[inline]
+    fn next(&mut self) -> Option<Sample> {
+        if self.channel == 0 {
+            self.paused_in_frame = self.paused;
+        }
+        let sample = if self.paused_in_frame {
+            0.0
+        } else {
+            self.input.next()?
+        };
+        self.channel = (self.channel + 1) % self.input.channels().get();
+        Some(sample)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl_fixed_source!(Pausable, |this| this.input.total_duration());
+
+/// Ends the source early once stopped.
+#[derive(Clone, Debug)]
+pub struct Stoppable<I> {
+    input: I,
+    stopped: bool,
+}
+
+impl<I> Stoppable<I> {
+    /// Stops the source.
+    #[inline]
+    pub fn stop(&mut self) {
+        self.stopped = true;
+    }
+}
+
+impl<I> Iterator for Stoppable<I>
+where
+    I: FixedSource,
+{
+    type Item = Sample;
+
+    #[inline]
+    fn next(&mut self) -> Option<Sample> {
+        if self.stopped {
+            None
+        } else {
+            self.input.next()
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl_fixed_source!(Stoppable, |this| this.input.total_duration());
+
+/// Calls a function on a source every time a period elapsed.
+#[derive(Clone, Debug)]
+pub struct PeriodicAccess<I, F> {
+    input: I,
+    access: F,
+    /// Samples between two calls, a whole number of frames.
+    update_period: u64,
+    /// Samples left until the next call.
+    samples_until_update: u64,
+}
+
+impl<I, F> Iterator for PeriodicAccess<I, F>
+where
+    I: FixedSource,
+    F: FnMut(&mut I),
+{
+    type Item = Sample;
+
+    #[inline]
+    fn next(&mut self) -> Option<Sample> {
+        if self.samples_until_update == 0 {
+            (self.access)(&mut self.input);
+            self.samples_until_update = self.update_period;
+        }
+        self.samples_until_update -= 1;
+        self.input.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl_fixed_source!(PeriodicAccess<F>, |this| this.input.total_duration());
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::fixed_source::{FixedConfig, FixedSource};
+    use crate::math::nz;
+    use crate::{Sample, Source};
+
+    /// One second of stereo at 10 Hz counting up from zero.
+    fn source() -> impl FixedSource {
+        SamplesBuffer::ramp(nz!(2), nz!(10), 10).into_fixed(FixedConfig::new(nz!(2), nz!(10)))
+    }
+
+    #[test]
+    fn takes_and_skips_whole_frames() {
+        let source = source()
+            .skip_duration(Duration::from_millis(250))
+            .take_duration(Duration::from_millis(150));
+        assert_eq!(source.total_duration(), Some(Duration::from_millis(100)));
+        assert_eq!(source.collect::<Vec<_>>(), [4.0, 5.0]);
+    }
+
+    #[test]
+    fn fades_in_per_frame() {
+        let source = source().fade_in(Duration::from_millis(200));
+        let faded: Vec<Sample> = source.take(6).collect();
+        assert_eq!(faded, [0.0, 0.0, 1.0, 1.5, 4.0, 5.0]);
+    }
+
+    #[test]
+    fn pauses_on_frame_boundaries() {
+        let mut source = source().amplify(2.0).pausable(false);
+        assert_eq!(source.next(), Some(0.0));
+        source.set_paused(true);
+        assert_eq!(source.next(), Some(2.0));
+        assert_eq!(source.next(), Some(0.0));
+        assert_eq!(source.next(), Some(0.0));
+        source.set_paused(false);
+        assert_eq!(source.next(), Some(4.0));
+    }
+
+    #[test]
+    fn accesses_periodically() {
+        let mut calls = 0;
+        let source = source()
+            .stoppable()
+            .periodic_access(Duration::from_millis(500), |source| {
+                calls += 1;
+                if calls == 2 {
+                    source.stop();
+                }
+            });
+        assert_eq!(source.count(), 10);
+    }
+}
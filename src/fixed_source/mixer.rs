@@ -0,0 +1,160 @@
+//! Mixer for [`FixedSource`]s that all share one format.
+
+use std::time::Duration;
+
+use super::{FixedConfig, FixedSource, FormatMismatch};
+use crate::{ChannelCount, Sample, SampleRate};
+
+#[cfg(feature = "crossbeam-channel")]
+use crossbeam_channel::{unbounded as channel, Receiver, Sender};
+#[cfg(not(feature = "crossbeam-channel"))]
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+/// Builds a new mixer for sources with the format `config`.
+///
+/// Unlike [`crate::mixer::mixer`] no conversion takes place: every source added
+/// must already have this format, see [`Source::into_fixed`](crate::Source::into_fixed).
+/// The output never ends, it plays silence while there is nothing to mix.
+pub fn mixer(config: FixedConfig) -> (FixedMixer, FixedMixerSource) {
+    let (pending_tx, pending_rx) = channel();
+    let input = FixedMixer { pending_tx, config };
+    let output = FixedMixerSource {
+        current_sources: Vec::with_capacity(16),
+        pending_rx,
+        config,
+        channel: 0,
+    };
+    (input, output)
+}
+
+/// The input of a [`FixedMixerSource`].
+#[derive(Clone)]
+pub struct FixedMixer {
+    pending_tx: Sender<Box<dyn FixedSource + Send>>,
+    config: FixedConfig,
+}
+
+impl FixedMixer {
+    /// Adds a new source to mix to the existing ones. It starts playing on the
+    /// next frame.
+    ///
+    /// Fails if the source does not have the format of the mixer.
+    pub fn add<T>(&self, source: T) -> Result<(), FormatMismatch>
+    where
+        T: FixedSource + Send + 'static,
+    {
+        self.config.check(&source)?;
+        // Ignore send errors (channel dropped means FixedMixerSource was dropped)
+        let _ = self.pending_tx.send(Box::new(source));
+        Ok(())
+    }
+
+    /// Returns the format sources must have to be mixed.
+    #[inline]
+    pub fn config(&self) -> FixedConfig {
+        self.config
+    }
+}
+
+/// The output of a [`FixedMixer`]. Implements [`FixedSource`].
+pub struct FixedMixerSource {
+    current_sources: Vec<Box<dyn FixedSource + Send>>,
+    /// Sources added since the last frame started.
+    pending_rx: Receiver<Box<dyn FixedSource + Send>>,
+    config: FixedConfig,
+    /// Channel of the next sample.
+    channel: u16,
+}
+
+impl FixedMixerSource {
+    /// Number of sources being mixed.
+    #[inline]
+    pub fn active_sources(&self) -> usize {
+        self.current_sources.len()
+    }
+}
+
+impl Iterator for FixedMixerSource {
+    type Item = Sample;
+
+    #[inline]
+    fn next(&mut self) -> Option<Sample> {
+        if self.channel == 0 {
+            self.current_sources.extend(self.pending_rx.try_iter());
+        }
+        self.channel = (self.channel + 1) % self.config.channel_count.get();
+
+        let mut sum = 0.0;
+        self.current_sources
+            .retain_mut(|source| match source.next() {
+                Some(value) => {
+                    sum += value;
+                    true
+                }
+                None => false,
+            });
+        Some(sum)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (usize::MAX, None)
+    }
+}
+
+impl FixedSource for FixedMixerSource {
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.config.channel_count
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.config.sample_rate
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::SamplesBuffer;
+    use crate::fixed_source::{stereo, TEST_CONFIG};
+    use crate::math::nz;
+    use crate::Source;
+
+    #[test]
+    fn mixes_and_plays_silence_after() {
+        let (mixer, output) = mixer(TEST_CONFIG);
+        mixer.add(stereo(vec![1.0, 2.0, 3.0, 4.0])).unwrap();
+        mixer.add(stereo(vec![10.0, 20.0])).unwrap();
+        let mixed: Vec<Sample> = output.take(6).collect();
+        assert_eq!(mixed, [11.0, 22.0, 3.0, 4.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn starts_sources_on_frame_boundary() {
+        let (mixer, mut output) = mixer(TEST_CONFIG);
+        assert_eq!(output.next(), Some(0.0));
+        mixer.add(stereo(vec![1.0, 2.0])).unwrap();
+        assert_eq!(output.next(), Some(0.0));
+        assert_eq!(output.next(), Some(1.0));
+        assert_eq!(output.next(), Some(2.0));
+        assert_eq!(output.active_sources(), 1);
+        assert_eq!(output.next(), Some(0.0));
+        assert_eq!(output.active_sources(), 0);
+    }
+
+    #[test]
+    fn refuses_other_format() {
+        let (mixer, _output) = mixer(TEST_CONFIG);
+        let mono = SamplesBuffer::new(nz!(1), nz!(10), vec![1.0])
+            .into_fixed(FixedConfig::new(nz!(1), nz!(10)));
+        let err = mixer.add(mono).unwrap_err();
+        assert_eq!(err.found.channel_count, nz!(1));
+    }
+}
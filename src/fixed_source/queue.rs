@@ -0,0 +1,259 @@
+//! Queue that plays [`FixedSource`]s with one format one after the other.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use super::{FixedConfig, FixedSource, FormatMismatch};
+use crate::{ChannelCount, Sample, SampleRate};
+
+#[cfg(feature = "crossbeam-channel")]
+use crossbeam_channel::{unbounded as channel, Receiver, Sender};
+#[cfg(not(feature = "crossbeam-channel"))]
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+/// Builds a new queue for sources with the format `config`. It consists of an
+/// input and an output.
+///
+/// Unlike [`crate::queue::queue`] no conversion takes place and the boundary
+/// between two sounds needs no span bookkeeping: every source appended must
+/// already have this format, see [`Source::into_fixed`](crate::Source::into_fixed).
+///
+/// The parameter `keep_alive_if_empty` indicates how the queue should behave if
+/// the queue becomes empty:
+///
+/// - If you pass `true`, then the queue is infinite and will play a silence instead until you add
+///   a new sound.
+/// - If you pass `false`, then the queue will report that it has finished playing.
+pub fn queue(
+    config: FixedConfig,
+    keep_alive_if_empty: bool,
+) -> (Arc<FixedQueue>, FixedQueueOutput) {
+    let input = Arc::new(FixedQueue {
+        next_sounds: Mutex::new(VecDeque::new()),
+        keep_alive_if_empty: AtomicBool::new(keep_alive_if_empty),
+        config,
+    });
+
+    let output = FixedQueueOutput {
+        current: None,
+        signal_after_end: None,
+        input: input.clone(),
+        channel: 0,
+    };
+
+    (input, output)
+}
+
+type Sound = Box<dyn FixedSource + Send>;
+type SignalDone = Option<Sender<()>>;
+
+/// The input of a [`FixedQueueOutput`].
+pub struct FixedQueue {
+    next_sounds: Mutex<VecDeque<(Sound, SignalDone)>>,
+
+    // See constructor.
+    keep_alive_if_empty: AtomicBool,
+
+    config: FixedConfig,
+}
+
+impl FixedQueue {
+    /// Adds a new source to the end of the queue.
+    ///
+    /// Fails if the source does not have the format of the queue.
+    #[inline]
+    pub fn append<T>(&self, source: T) -> Result<(), FormatMismatch>
+    where
+        T: FixedSource + Send + 'static,
+    {
+        self.config.check(&source)?;
+        self.next_sounds
+            .lock()
+            .unwrap()
+            .push_back((Box::new(source) as Box<_>, None));
+        Ok(())
+    }
+
+    /// Adds a new source to the end of the queue.
+    ///
+    /// The `Receiver` will be signalled when the sound has finished playing.
+    /// Fails if the source does not have the format of the queue.
+    ///
+    /// Enable the feature flag `crossbeam-channel` in rodio to use a `crossbeam_channel::Receiver` instead.
+    #[inline]
+    pub fn append_with_signal<T>(&self, source: T) -> Result<Receiver<()>, FormatMismatch>
+    where
+        T: FixedSource + Send + 'static,
+    {
+        self.config.check(&source)?;
+        let (tx, rx) = channel();
+        self.next_sounds
+            .lock()
+            .unwrap()
+            .push_back((Box::new(source) as Box<_>, Some(tx)));
+        Ok(rx)
+    }
+
+    /// Sets whether the queue stays alive if there's no more sound to play.
+    ///
+    /// See also the constructor.
+    pub fn set_keep_alive_if_empty(&self, keep_alive_if_empty: bool) {
+        self.keep_alive_if_empty
+            .store(keep_alive_if_empty, Ordering::Release);
+    }
+
+    /// Removes all the sounds from the queue. Returns the number of sounds cleared.
+    pub fn clear(&self) -> usize {
+        let mut sounds = self.next_sounds.lock().unwrap();
+        let len = sounds.len();
+        sounds.clear();
+        len
+    }
+
+    /// Returns the number of sounds waiting in the queue, not counting the one playing.
+    pub fn len(&self) -> usize {
+        self.next_sounds.lock().unwrap().len()
+    }
+
+    /// Returns whether no sounds are waiting in the queue.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the format sources must have to be queued.
+    #[inline]
+    pub fn config(&self) -> FixedConfig {
+        self.config
+    }
+}
+
+/// The output of a [`FixedQueue`]. Implements [`FixedSource`].
+pub struct FixedQueueOutput {
+    // The sound being played.
+    current: Option<Sound>,
+
+    // Signal this sender before picking from `next`.
+    signal_after_end: SignalDone,
+
+    // The next sounds.
+    input: Arc<FixedQueue>,
+
+    // Channel of the next sample. A sound that ends mid-frame is padded with
+    // silence up to the end of the frame.
+    channel: u16,
+}
+
+impl FixedQueueOutput {
+    /// Signals the end of the last sound and starts the next one, if any.
+    fn go_next(&mut self) {
+        if let Some(signal_after_end) = self.signal_after_end.take() {
+            let _ = signal_after_end.send(());
+        }
+        if let Some((sound, signal_after_end)) = self.input.next_sounds.lock().unwrap().pop_front()
+        {
+            self.current = Some(sound);
+            self.signal_after_end = signal_after_end;
+        }
+    }
+
+    #[inline]
+    fn advance_channel(&mut self) {
+        self.channel = (self.channel + 1) % self.input.config.channel_count.get();
+    }
+}
+
+impl Iterator for FixedQueueOutput {
+    type Item = Sample;
+
+    #[inline]
+    fn next(&mut self) -> Option<Sample> {
+        loop {
+            if let Some(sound) = &mut self.current {
+                if let Some(sample) = sound.next() {
+                    self.advance_channel();
+                    return Some(sample);
+                }
+                self.current = None;
+            }
+            if self.channel != 0 {
+                self.advance_channel();
+                return Some(0.0);
+            }
+
+            self.go_next();
+            if self.current.is_none() {
+                if self.input.keep_alive_if_empty.load(Ordering::Acquire) {
+                    self.advance_channel();
+                    return Some(0.0);
+                }
+                return None;
+            }
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let current = self.current.as_ref().map_or(0, |sound| sound.size_hint().0);
+        (current, None)
+    }
+}
+
+impl FixedSource for FixedQueueOutput {
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.input.config.channel_count
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.input.config.sample_rate
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::SamplesBuffer;
+    use crate::fixed_source::{stereo, TEST_CONFIG};
+    use crate::math::nz;
+    use crate::Source;
+
+    #[test]
+    fn plays_sounds_in_order() {
+        let (input, output) = queue(TEST_CONFIG, false);
+        input.append(stereo(vec![1.0, 2.0])).unwrap();
+        let done = input.append_with_signal(stereo(vec![3.0, 4.0])).unwrap();
+        input.append(stereo(vec![5.0, 6.0])).unwrap();
+        assert_eq!(input.len(), 3);
+        assert_eq!(output.collect::<Vec<_>>(), [1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        assert!(done.try_recv().is_ok());
+    }
+
+    #[test]
+    fn pads_sound_ending_mid_frame() {
+        let (input, output) = queue(TEST_CONFIG, false);
+        input.append(stereo(vec![1.0, 2.0, 3.0])).unwrap();
+        input.append(stereo(vec![4.0, 5.0])).unwrap();
+        assert_eq!(output.collect::<Vec<_>>(), [1.0, 2.0, 3.0, 0.0, 4.0, 5.0]);
+    }
+
+    #[test]
+    fn keeps_alive_until_next_sound() {
+        let (input, mut output) = queue(TEST_CONFIG, true);
+        assert_eq!(output.next(), Some(0.0));
+        input.append(stereo(vec![1.0, 2.0])).unwrap();
+        assert_eq!(output.next(), Some(0.0));
+        assert_eq!(output.next(), Some(1.0));
+        assert_eq!(output.next(), Some(2.0));
+
+        input.set_keep_alive_if_empty(false);
+        assert_eq!(output.next(), None);
+    }
+}
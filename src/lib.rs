@@ -177,6 +177,12 @@ pub use cpal::{
 };
 
 mod common;
+#[cfg(any(
+    feature = "wav_output",
+    feature = "flac_output",
+    feature = "opus_output"
+))]
+mod encoder;
 mod player;
 mod spatial_player;
 #[cfg(all(feature = "playback", feature = "experimental"))]
@@ -197,14 +203,24 @@ pub mod math;
 /// Microphone input support for audio recording.
 pub mod microphone;
 pub mod mixer;
+pub mod offline;
 pub mod queue;
 pub mod source;
 pub mod static_buffer;
 
 pub use crate::common::{BitDepth, ChannelCount, Float, Sample, SampleRate};
 pub use crate::decoder::Decoder;
+#[cfg(any(
+    feature = "wav_output",
+    feature = "flac_output",
+    feature = "opus_output"
+))]
+pub use crate::encoder::{
+    encode_to_file, encode_to_writer, EncodeError, EncodeOptions, EncodingFormat,
+};
 #[cfg(feature = "experimental")]
 pub use crate::fixed_source::FixedSource;
+pub use crate::offline::OfflineSink;
 pub use crate::player::Player;
 pub use crate::source::Source;
 pub use crate::spatial_player::SpatialPlayer;
@@ -216,3 +232,8 @@ pub use crate::wav_output::wav_to_file;
 #[cfg(feature = "wav_output")]
 #[cfg_attr(docsrs, doc(cfg(feature = "wav_output")))]
 pub use crate::wav_output::wav_to_writer;
+#[cfg(feature = "wav_output")]
+#[cfg_attr(docsrs, doc(cfg(feature = "wav_output")))]
+pub use crate::wav_output::{
+    BroadcastExtension, ToWavError, WavMetadata, WavOutputSettings, WavSampleFormat, WavWriterSink,
+};
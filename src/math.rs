@@ -6,6 +6,8 @@ use std::time::Duration;
 /// Nanoseconds per second, used for Duration calculations.
 pub(crate) const NANOS_PER_SEC: u64 = 1_000_000_000;
 
+pub(crate) mod simd;
+
 // Re-export float constants with appropriate precision for the Float type.
 // This centralizes all cfg gating for constants in one place.
 #[cfg(not(feature = "64bit"))]
@@ -112,6 +114,27 @@ pub(crate) fn duration_to_coefficient(duration: Duration, sample_rate: SampleRat
     Float::exp(-1.0 / (duration_to_float(duration) * sample_rate.get() as Float))
 }
 
+/// Converts a number of frames to a duration, rounding up to the nanosecond so
+/// that [`duration_to_frames`] gives the same number of frames back.
+#[inline]
+#[must_use]
+pub(crate) fn frames_to_duration(frames: u64, sample_rate: SampleRate) -> Duration {
+    let nanos =
+        (u128::from(frames) * u128::from(NANOS_PER_SEC)).div_ceil(u128::from(sample_rate.get()));
+    Duration::new(
+        (nanos / u128::from(NANOS_PER_SEC)) as u64,
+        (nanos % u128::from(NANOS_PER_SEC)) as u32,
+    )
+}
+
+/// Converts a duration to the number of whole frames that fit within it,
+/// rounding down.
+#[inline]
+#[must_use]
+pub(crate) fn duration_to_frames(duration: Duration, sample_rate: SampleRate) -> u64 {
+    (duration.as_nanos() * u128::from(sample_rate.get()) / u128::from(NANOS_PER_SEC)) as u64
+}
+
 /// Convert Duration to Float with appropriate precision for the Sample type.
 #[inline]
 #[must_use]
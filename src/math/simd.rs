@@ -0,0 +1,118 @@
+//! Vectorised kernels for blocks of samples, used by the mixer and gain filters.
+//!
+//! On `x86_64` the kernels use SSE, which every `x86_64` CPU supports. Other
+//! targets and the `64bit` feature use plain loops that the compiler is free to
+//! vectorise itself. Both produce exactly the same results: there is no fused
+//! multiply-add and the samples are not reordered.
+
+use crate::{Float, Sample};
+
+/// Adds `input` to `output`, sample by sample. Extra samples in the longer
+/// slice are ignored.
+#[inline]
+pub(crate) fn add(output: &mut [Sample], input: &[Sample]) {
+    add_scaled(output, input, 1.0);
+}
+
+/// Adds `input` multiplied by `gain` to `output`, sample by sample. Extra
+/// samples in the longer slice are ignored.
+#[inline]
+pub(crate) fn add_scaled(output: &mut [Sample], input: &[Sample], gain: Float) {
+    let len = output.len().min(input.len());
+    arch::add_scaled(&mut output[..len], &input[..len], gain);
+}
+
+/// Multiplies every sample in `buffer` by `gain`.
+#[inline]
+pub(crate) fn scale(buffer: &mut [Sample], gain: Float) {
+    arch::scale(buffer, gain);
+}
+
+mod fallback {
+    use crate::{Float, Sample};
+
+    #[inline]
+    pub(super) fn add_scaled(output: &mut [Sample], input: &[Sample], gain: Float) {
+        for (out, sample) in output.iter_mut().zip(input) {
+            *out += *sample * gain;
+        }
+    }
+
+    #[inline]
+    pub(super) fn scale(buffer: &mut [Sample], gain: Float) {
+        for sample in buffer {
+            *sample *= gain;
+        }
+    }
+}
+
+#[cfg(all(target_arch = "x86_64", not(feature = "64bit")))]
+mod arch {
+    use std::arch::x86_64::{_mm_add_ps, _mm_loadu_ps, _mm_mul_ps, _mm_set1_ps, _mm_storeu_ps};
+
+    use super::fallback;
+
+    const LANES: usize = 4;
+
+    /// `output` and `input` have the same length.
+    #[inline]
+    pub(super) fn add_scaled(output: &mut [f32], input: &[f32], gain: f32) {
+        let vectorised = output.len() / LANES * LANES;
+        // SAFETY: SSE is part of the x86_64 baseline. `start + LANES` is at most
+        // `vectorised`, which fits in both slices, and the loads and stores are
+        // unaligned.
+        unsafe {
+            let gains = _mm_set1_ps(gain);
+            for start in (0..vectorised).step_by(LANES) {
+                let out = _mm_loadu_ps(output.as_ptr().add(start));
+                let samples = _mm_loadu_ps(input.as_ptr().add(start));
+                let sum = _mm_add_ps(out, _mm_mul_ps(samples, gains));
+                _mm_storeu_ps(output.as_mut_ptr().add(start), sum);
+            }
+        }
+        fallback::add_scaled(&mut output[vectorised..], &input[vectorised..], gain);
+    }
+
+    #[inline]
+    pub(super) fn scale(buffer: &mut [f32], gain: f32) {
+        let vectorised = buffer.len() / LANES * LANES;
+        // SAFETY: see `add_scaled`.
+        unsafe {
+            let gains = _mm_set1_ps(gain);
+            for start in (0..vectorised).step_by(LANES) {
+                let samples = _mm_loadu_ps(buffer.as_ptr().add(start));
+                _mm_storeu_ps(buffer.as_mut_ptr().add(start), _mm_mul_ps(samples, gains));
+            }
+        }
+        fallback::scale(&mut buffer[vectorised..], gain);
+    }
+}
+
+#[cfg(not(all(target_arch = "x86_64", not(feature = "64bit"))))]
+use fallback as arch;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_scalar_math() {
+        let input: Vec<Sample> = (0..11).map(|i| i as Sample * 0.25 - 1.0).collect();
+        let mut output: Vec<Sample> = (0..11).map(|i| i as Sample).collect();
+        let expected: Vec<Sample> = output
+            .iter()
+            .zip(&input)
+            .map(|(out, sample)| out + sample * 0.5)
+            .collect();
+        add_scaled(&mut output, &input, 0.5);
+        assert_eq!(output, expected);
+
+        add(&mut output[..3], &input);
+        assert_eq!(output[..3], [-1.5, -0.125, 1.25]);
+        assert_eq!(output[3], expected[3]);
+
+        scale(&mut output, 2.0);
+        assert_eq!(output[..3], [-3.0, -0.25, 2.5]);
+        assert_eq!(output[10], expected[10] * 2.0);
+    }
+}
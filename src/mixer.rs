@@ -1,16 +1,32 @@
 //! Mixer that plays multiple sounds at the same time.
 
 use crate::common::{ChannelCount, SampleRate};
+use crate::math::{self, simd};
 use crate::source::{SeekError, Source, UniformSourceIterator};
-use crate::Sample;
-use std::sync::Arc;
+use crate::{Float, Sample};
+use std::num::NonZero;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, Weak};
 use std::time::Duration;
 
+pub use self::bus::{Bus, BusInput};
+pub use self::send::AuxSend;
+
+use self::bus::WeakBus;
+use self::send::{SendList, SendTaps};
+
+mod bus;
+pub(crate) mod send;
+
 #[cfg(feature = "crossbeam-channel")]
 use crossbeam_channel::{unbounded as channel, Receiver, Sender};
 #[cfg(not(feature = "crossbeam-channel"))]
 use std::sync::mpsc::{channel, Receiver, Sender};
 
+/// Number of samples output devices and the offline sink render the mixer
+/// output in at once.
+pub(crate) const BLOCK_LEN: usize = 512;
+
 /// Builds a new mixer.
 ///
 /// You can choose the characteristics of the output thanks to this constructor. All the sounds
@@ -18,25 +34,45 @@ use std::sync::mpsc::{channel, Receiver, Sender};
 ///
 /// After creating a mixer, you can add new sounds with the controller.
 ///
-/// Note that mixer without any input source behaves like an `Empty` (not: `Zero`) source,
-/// and thus, just after appending to a player, the mixer is removed from the player.
-/// As a result, input sources added to the mixer later might not be forwarded to the player.
-/// Add `Zero` source to prevent detaching the mixer from player.
+/// Note that by default a mixer without any input source ends, just like an `Empty` source.
+/// Once appended to a player it is removed from that player right away, and sources added to
+/// the mixer later are not played. Use [`Mixer::set_keep_alive_if_empty`] to have the mixer play
+/// silence instead, until it is [closed](Mixer::close).
 pub fn mixer(channels: ChannelCount, sample_rate: SampleRate) -> (Mixer, MixerSource) {
+    new_mixer(channels, sample_rate, None)
+}
+
+fn new_mixer(
+    channels: ChannelCount,
+    sample_rate: SampleRate,
+    parent: Option<Weak<Inner>>,
+) -> (Mixer, MixerSource) {
     let (tx, rx) = channel();
 
     let input = Mixer(Arc::new(Inner {
         pending_tx: tx,
         channels,
         sample_rate,
+        sample_count: Arc::new(AtomicU64::new(0)),
+        render_position: Arc::new(AtomicU64::new(0)),
+        max_voices: AtomicUsize::new(usize::MAX),
+        voice_stealing: AtomicU8::new(VoiceStealing::Oldest as u8),
+        active_sources: AtomicUsize::new(0),
+        buses: Mutex::new(Vec::new()),
+        soloed_buses: Arc::new(AtomicUsize::new(0)),
+        keep_alive_if_empty: AtomicBool::new(false),
+        closed: AtomicBool::new(false),
+        parent,
     }));
 
     let output = MixerSource {
         current_sources: Vec::with_capacity(16),
+        active_sources: 0,
         input: input.clone(),
         sample_count: 0,
         still_pending: vec![],
         pending_rx: rx,
+        scratch: Vec::new(),
     };
 
     (input, output)
@@ -47,41 +83,491 @@ pub fn mixer(channels: ChannelCount, sample_rate: SampleRate) -> (Mixer, MixerSo
 pub struct Mixer(Arc<Inner>);
 
 struct Inner {
-    pending_tx: Sender<Box<dyn Source + Send>>,
+    pending_tx: Sender<Pending>,
     channels: ChannelCount,
     sample_rate: SampleRate,
+    sample_count: Arc<AtomicU64>,
+    // See `MixerClock::render_frames`.
+    render_position: Arc<AtomicU64>,
+    // `usize::MAX` if there is no limit.
+    max_voices: AtomicUsize,
+    voice_stealing: AtomicU8,
+    // Mirrors `MixerSource::active_sources`.
+    active_sources: AtomicUsize,
+    // Does not keep the buses alive, so they can end once their handles are dropped.
+    buses: Mutex<Vec<WeakBus>>,
+    // Number of buses of this mixer that are soloed.
+    soloed_buses: Arc<AtomicUsize>,
+    // See `Mixer::set_keep_alive_if_empty`.
+    keep_alive_if_empty: AtomicBool,
+    // See `Mixer::close`.
+    closed: AtomicBool,
+    // The mixer a bus plays on, `None` for other mixers.
+    parent: Option<Weak<Inner>>,
+}
+
+impl Inner {
+    /// Whether this is a bus whose parent mixer is closed or can no longer get
+    /// new sources.
+    fn parent_ended(&self) -> bool {
+        let Some(parent) = &self.parent else {
+            return false;
+        };
+        match parent.upgrade() {
+            // Held by the output of the parent and by `parent` itself.
+            Some(parent) => {
+                parent.closed.load(Ordering::Acquire) || Arc::strong_count(&parent) <= 2
+            }
+            None => true,
+        }
+    }
+}
+
+// A source waiting to be mixed in, together with the output frame it should
+// start on.
+struct Pending {
+    source: Box<dyn Source + Send>,
+    start_frame: u64,
+    priority: i32,
+    controls: Option<ControlsGuard>,
+    // The output of a bus, never stolen nor counted as a voice.
+    bus: bool,
+}
+
+/// Which playing source makes room when a source is added to a [`Mixer`] that
+/// already plays its maximum number of sources.
+///
+/// Only sources with a priority lower than or equal to that of the new source
+/// are considered. If there is none, the new source is not played.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(u8)]
+pub enum VoiceStealing {
+    /// The source that has been playing the longest.
+    #[default]
+    Oldest = 0,
+    /// The source that was quietest over the last few milliseconds.
+    Quietest = 1,
+    /// The source with the lowest priority, the oldest one if several share it.
+    LowestPriority = 2,
+}
+
+impl VoiceStealing {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            0 => VoiceStealing::Oldest,
+            1 => VoiceStealing::Quietest,
+            _ => VoiceStealing::LowestPriority,
+        }
+    }
 }
 
+/// How long a stolen source takes to fade out.
+const STEAL_FADE_OUT: Duration = Duration::from_millis(10);
+
 impl Mixer {
     /// Adds a new source to mix to the existing ones.
+    ///
+    /// The source starts playing the next time the mixer output is polled,
+    /// which in practice means somewhere within the next output buffer. Use
+    /// [`Mixer::add_at`] when the start time needs to be exact.
     #[inline]
     pub fn add<T>(&self, source: T)
+    where
+        T: Source + Send + 'static,
+    {
+        self.add_at(source, 0);
+    }
+
+    /// Like [`Mixer::add`], with a priority for [voice limiting](Mixer::set_max_voices).
+    ///
+    /// Sources added without a priority have priority 0. A source never takes
+    /// the place of one with a higher priority: if every playing source has a
+    /// higher priority once the limit is reached, the new source is dropped
+    /// without being played.
+    #[inline]
+    pub fn add_with_priority<T>(&self, source: T, priority: i32)
+    where
+        T: Source + Send + 'static,
+    {
+        self.send(source, 0, priority, None);
+    }
+
+    /// Like [`Mixer::add`], returns a handle to stop, pause or change the volume
+    /// of the source while it plays.
+    ///
+    /// This is lighter than a [`Player`](crate::Player) per sound, which is
+    /// handy for one-shot sound effects.
+    ///
+    /// # Example
+    /// ```
+    /// use rodio::mixer;
+    /// use rodio::source::{SineWave, Source};
+    /// use rodio::math::nz;
+    /// use std::time::Duration;
+    ///
+    /// let (mixer, output) = mixer::mixer(nz!(2), nz!(48_000));
+    /// let beep = mixer.add_with_handle(SineWave::new(440.0).take_duration(Duration::from_secs(1)));
+    /// beep.set_volume(0.5);
+    /// beep.stop();
+    /// ```
+    #[inline]
+    pub fn add_with_handle<T>(&self, source: T) -> VoiceHandle
+    where
+        T: Source + Send + 'static,
+    {
+        let controls = Arc::new(VoiceControls {
+            stopped: AtomicBool::new(false),
+            paused: AtomicBool::new(false),
+            volume: AtomicFloatBits::new(Float::to_bits(1.0)),
+            playing: AtomicBool::new(true),
+            sends: SendList::default(),
+        });
+        self.send(source, 0, 0, Some(ControlsGuard(controls.clone())));
+        VoiceHandle { controls }
+    }
+
+    /// Adds a new source that starts playing exactly when the mixer output
+    /// reaches frame `start_frame`.
+    ///
+    /// A frame is one sample for each channel of the mixer. The current frame
+    /// can be read from the [`MixerClock`] returned by [`Mixer::clock`]. If
+    /// `start_frame` has already passed the source starts on the next frame,
+    /// just like with [`Mixer::add`].
+    ///
+    /// # Example
+    /// ```
+    /// use rodio::mixer;
+    /// use rodio::source::{SineWave, Source};
+    /// use rodio::math::nz;
+    /// use std::time::Duration;
+    ///
+    /// let (mixer, output) = mixer::mixer(nz!(2), nz!(48_000));
+    /// let clock = mixer.clock();
+    /// // Start a beep exactly half a second from now.
+    /// let start = clock.frames() + clock.duration_to_frames(Duration::from_millis(500));
+    /// mixer.add_at(SineWave::new(440.0).take_duration(Duration::from_millis(100)), start);
+    /// ```
+    #[inline]
+    pub fn add_at<T>(&self, source: T, start_frame: u64)
+    where
+        T: Source + Send + 'static,
+    {
+        self.send(source, start_frame, 0, None);
+    }
+
+    fn send<T>(&self, source: T, start_frame: u64, priority: i32, controls: Option<ControlsGuard>)
     where
         T: Source + Send + 'static,
     {
         let uniform_source =
             UniformSourceIterator::new(source, self.0.channels, self.0.sample_rate);
+        self.send_pending(Pending {
+            source: Box::new(uniform_source),
+            start_frame,
+            priority,
+            controls,
+            bus: false,
+        });
+    }
+
+    fn send_pending(&self, pending: Pending) {
+        if self.is_closed() {
+            return;
+        }
         // Ignore send errors (channel dropped means MixerSource was dropped)
-        let _ = self.0.pending_tx.send(Box::new(uniform_source));
+        let _ = self.0.pending_tx.send(pending);
+    }
+
+    /// Sets whether the mixer output stays alive while there is no source to
+    /// play. If `true` it plays silence instead of ending, until the mixer is
+    /// [closed](Mixer::close).
+    ///
+    /// Off by default. Turn it on for a mixer nested in another mixer or
+    /// played by a [`Player`](crate::Player), so sources can be added to it
+    /// at any time.
+    ///
+    /// # Example
+    /// ```
+    /// use rodio::mixer;
+    /// use rodio::source::{SineWave, Source};
+    /// use rodio::math::nz;
+    /// use std::time::Duration;
+    ///
+    /// let (main, _output) = mixer::mixer(nz!(2), nz!(48_000));
+    /// let (effects, effects_output) = mixer::mixer(nz!(2), nz!(48_000));
+    /// effects.set_keep_alive_if_empty(true);
+    /// main.add(effects_output);
+    ///
+    /// // Later on.
+    /// effects.add(SineWave::new(440.0).take_duration(Duration::from_millis(100)));
+    /// // Ends the effects mixer once the sine wave is done.
+    /// effects.close();
+    /// ```
+    #[inline]
+    pub fn set_keep_alive_if_empty(&self, keep_alive_if_empty: bool) {
+        self.0
+            .keep_alive_if_empty
+            .store(keep_alive_if_empty, Ordering::Release);
+    }
+
+    /// Closes the mixer. Sources added from now on are dropped without being
+    /// played, and the output ends once the sources added before have
+    /// finished, even if [kept alive](Mixer::set_keep_alive_if_empty). The
+    /// [buses](Mixer::bus) of the mixer are closed too.
+    ///
+    /// The mixer is closed as well once every `Mixer` handle is dropped.
+    #[inline]
+    pub fn close(&self) {
+        self.0.closed.store(true, Ordering::Release);
+        let buses = self.0.buses.lock().unwrap();
+        for bus in buses.iter().filter_map(WeakBus::upgrade) {
+            bus.mixer().close();
+        }
+    }
+
+    /// Whether [`Mixer::close`] was called.
+    #[inline]
+    pub fn is_closed(&self) -> bool {
+        self.0.closed.load(Ordering::Acquire)
+    }
+
+    /// Limits how many sources play at the same time, `None` removes the limit.
+    ///
+    /// Once the limit is reached a new source takes the place of a playing one,
+    /// chosen by [`Mixer::set_voice_stealing`]. That source fades out quickly
+    /// and is then dropped. A new source that may not take the place of any
+    /// playing one, see [`Mixer::add_with_priority`], is dropped instead.
+    /// Lowering the limit below the number of playing sources steals sources
+    /// right away.
+    #[inline]
+    pub fn set_max_voices(&self, max_voices: Option<NonZero<usize>>) {
+        let max_voices = max_voices.map_or(usize::MAX, NonZero::get);
+        self.0.max_voices.store(max_voices, Ordering::Relaxed);
+    }
+
+    /// Sets which source makes room for a new one once the
+    /// [maximum number of sources](Mixer::set_max_voices) play.
+    #[inline]
+    pub fn set_voice_stealing(&self, stealing: VoiceStealing) {
+        self.0
+            .voice_stealing
+            .store(stealing as u8, Ordering::Relaxed);
+    }
+
+    /// Number of sources playing, not counting those fading out after being
+    /// stolen or those that have not started yet.
+    ///
+    /// Updated as the mixer output is played.
+    #[inline]
+    pub fn active_sources(&self) -> usize {
+        self.0.active_sources.load(Ordering::Relaxed)
+    }
+
+    /// Returns the bus called `name`, creating it if there is none yet.
+    ///
+    /// A bus is a mixer of its own whose sum plays on this mixer. Its volume,
+    /// mute and solo apply to all sources added to it. Buses play silence while
+    /// empty. A bus ends once it is empty and either every handle to it is
+    /// dropped, or this mixer is closed or has no handles left. Nest buses by
+    /// calling `bus` on [`Bus::mixer`].
+    ///
+    /// # Example
+    /// ```
+    /// use rodio::mixer;
+    /// use rodio::source::{SineWave, Source};
+    /// use rodio::math::nz;
+    /// use std::time::Duration;
+    ///
+    /// let (mixer, output) = mixer::mixer(nz!(2), nz!(48_000));
+    /// let music = mixer.bus("music");
+    /// music.set_volume(0.5);
+    /// music.mixer().add(SineWave::new(440.0).take_duration(Duration::from_secs(1)));
+    /// ```
+    pub fn bus(&self, name: &str) -> Bus {
+        self.bus_with_effects(name, |input| input)
+    }
+
+    /// Like [`Mixer::bus`], runs the sum of the sources on the bus through
+    /// `effects` once, before volume, mute and solo are applied.
+    ///
+    /// If the bus already exists it is returned as it is and `effects` is not
+    /// used.
+    ///
+    /// # Example
+    /// ```
+    /// use rodio::mixer;
+    /// use rodio::source::{LimitSettings, Source};
+    /// use rodio::math::nz;
+    ///
+    /// let (mixer, output) = mixer::mixer(nz!(2), nz!(48_000));
+    /// let sfx = mixer.bus_with_effects("sfx", |input| {
+    ///     input.high_pass(80).limit(LimitSettings::default())
+    /// });
+    /// ```
+    pub fn bus_with_effects<F, S>(&self, name: &str, effects: F) -> Bus
+    where
+        F: FnOnce(BusInput) -> S,
+        S: Source + Send + 'static,
+    {
+        if let Some(bus) = find_bus(&mut self.0.buses.lock().unwrap(), name) {
+            return bus;
+        }
+
+        // `effects` may add buses to this mixer itself, so it runs unlocked.
+        let (bus, output) = bus::bus(name, self, effects);
+        let mut buses = self.0.buses.lock().unwrap();
+        if let Some(bus) = find_bus(&mut buses, name) {
+            return bus;
+        }
+        self.add_unstealable(output);
+        buses.push(bus.downgrade());
+        bus
+    }
+
+    /// Plays `source` without counting it as a voice, so it is never stolen.
+    fn add_unstealable<T>(&self, source: T)
+    where
+        T: Source + Send + 'static,
+    {
+        let uniform_source =
+            UniformSourceIterator::new(source, self.0.channels, self.0.sample_rate);
+        self.send_pending(Pending {
+            source: Box::new(uniform_source),
+            start_frame: 0,
+            priority: 0,
+            controls: None,
+            bus: true,
+        });
+    }
+
+    /// Returns a clock that follows the number of samples this mixer has
+    /// produced so far. The clock can be cloned and read from any thread.
+    #[inline]
+    pub fn clock(&self) -> MixerClock {
+        MixerClock {
+            sample_count: self.0.sample_count.clone(),
+            render_position: self.0.render_position.clone(),
+            channels: self.0.channels,
+            sample_rate: self.0.sample_rate,
+        }
+    }
+}
+
+/// Forgets buses that are gone and returns the one called `name`, if any.
+fn find_bus(buses: &mut Vec<WeakBus>, name: &str) -> Option<Bus> {
+    buses.retain(|bus| bus.upgrade().is_some_and(|bus| !bus.mixer().is_closed()));
+    buses
+        .iter()
+        .filter_map(WeakBus::upgrade)
+        .find(|bus| bus.name() == name)
+}
+
+/// Read-only view on the output position of a [`Mixer`].
+///
+/// The position advances as the [`MixerSource`] is played, so for a mixer
+/// driving an audio device it follows the samples handed to that device.
+/// Use it together with [`Mixer::add_at`] to schedule sources.
+#[derive(Clone)]
+pub struct MixerClock {
+    sample_count: Arc<AtomicU64>,
+    render_position: Arc<AtomicU64>,
+    channels: ChannelCount,
+    sample_rate: SampleRate,
+}
+
+impl MixerClock {
+    /// Number of samples (summed over all channels) produced by the mixer.
+    #[inline]
+    pub fn samples(&self) -> u64 {
+        self.sample_count.load(Ordering::Acquire)
+    }
+
+    /// Number of whole frames produced by the mixer.
+    #[inline]
+    pub fn frames(&self) -> u64 {
+        self.samples() / self.channels.get() as u64
+    }
+
+    /// The frame being rendered, for sources played by this mixer. Unlike
+    /// [`MixerClock::frames`], which only moves once per block, it is exact
+    /// while a block is filled with [`Source::fill_buffer`].
+    #[inline]
+    pub(crate) fn render_frames(&self) -> u64 {
+        let samples = self
+            .samples()
+            .max(self.render_position.load(Ordering::Relaxed));
+        samples / self.channels.get() as u64
+    }
+
+    /// Time played by the mixer, derived from the number of frames produced.
+    #[inline]
+    pub fn elapsed(&self) -> Duration {
+        self.frames_to_duration(self.frames())
+    }
+
+    /// Converts a number of frames at the mixer's sample rate to a duration.
+    #[inline]
+    pub fn frames_to_duration(&self, frames: u64) -> Duration {
+        math::frames_to_duration(frames, self.sample_rate)
+    }
+
+    /// Converts a duration to the number of frames, at the mixer's sample
+    /// rate, that fit within it. Rounds down.
+    #[inline]
+    pub fn duration_to_frames(&self, duration: Duration) -> u64 {
+        math::duration_to_frames(duration, self.sample_rate)
+    }
+
+    /// Channel count of the mixer this clock belongs to.
+    #[inline]
+    pub fn channels(&self) -> ChannelCount {
+        self.channels
+    }
+
+    /// Sample rate of the mixer this clock belongs to.
+    #[inline]
+    pub fn sample_rate(&self) -> SampleRate {
+        self.sample_rate
+    }
+}
+
+impl std::fmt::Debug for MixerClock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MixerClock")
+            .field("samples", &self.samples())
+            .field("channels", &self.channels)
+            .field("sample_rate", &self.sample_rate)
+            .finish()
     }
 }
 
 /// The output of the mixer. Implements `Source`.
+///
+/// [`Source::fill_buffer`] mixes whole blocks and sums them with SIMD instructions
+/// where available. [`Iterator::next`] mixes one sample at a time and is not
+/// vectorized, so prefer `fill_buffer` when pulling many samples.
 pub struct MixerSource {
-    // The current iterator that produces samples.
-    current_sources: Vec<Box<dyn Source + Send>>,
+    // The sources being mixed.
+    current_sources: Vec<Voice>,
+
+    // The number of sources in `current_sources` that are not fading out.
+    active_sources: usize,
 
     // The pending sounds.
     input: Mixer,
 
-    // The number of samples produced so far.
-    sample_count: usize,
+    // The number of samples produced so far. Mirrored into `Inner::sample_count`
+    // for other threads to read.
+    sample_count: u64,
 
-    // A temporary vec used in start_pending_sources.
-    still_pending: Vec<Box<dyn Source + Send>>,
+    // Sources received from the channel that are not yet due to start.
+    still_pending: Vec<Pending>,
 
     // Receiver for pending sources from the channel.
-    pending_rx: Receiver<Box<dyn Source + Send>>,
+    pending_rx: Receiver<Pending>,
+
+    // Block of samples from one source, used by `fill_buffer`.
+    scratch: Vec<Sample>,
 }
 
 impl Source for MixerSource {
@@ -105,11 +591,65 @@ impl Source for MixerSource {
         None
     }
 
-    #[inline]
-    fn try_seek(&mut self, _: Duration) -> Result<(), SeekError> {
-        Err(SeekError::NotSupported {
-            underlying_source: std::any::type_name::<Self>(),
-        })
+    /// Mixes a block of samples. Stops early right before a pending source is
+    /// due to start, so it starts on the same sample as with `next`.
+    fn fill_buffer(&mut self, buffer: &mut [Sample]) -> usize {
+        self.start_pending_sources();
+
+        let channels = self.input.0.channels.get() as u64;
+        let next_frame_start = self.sample_count.next_multiple_of(channels);
+        let until_next_start = self
+            .still_pending
+            .iter()
+            .map(|pending| (pending.start_frame * channels).max(next_frame_start))
+            .min()
+            .map(|start| (start - self.sample_count) as usize);
+        let len = until_next_start.map_or(buffer.len(), |until| buffer.len().min(until));
+        let buffer = &mut buffer[..len];
+
+        buffer.fill(0.0);
+        let scratch = &mut self.scratch;
+        scratch.resize(len, 0.0);
+        let mut longest = 0;
+        let frame_start = self.sample_count.is_multiple_of(channels);
+        let active_sources = &mut self.active_sources;
+        let position = (self.input.0.render_position.as_ref(), self.sample_count);
+        self.current_sources.retain_mut(|voice| {
+            let filled = voice.fill(scratch, frame_start, position);
+            simd::add(buffer, &scratch[..filled]);
+            longest = longest.max(filled);
+            let playing = filled == len;
+            if !playing && voice.is_active() {
+                *active_sources -= 1;
+            }
+            playing
+        });
+
+        // The clock counts the whole block like `next` does, even if the mixer
+        // ended and the rest of it is left to the caller.
+        self.sample_count += len as u64;
+        self.publish();
+        if self.should_end() {
+            longest
+        } else {
+            len
+        }
+    }
+
+    /// Seeks every playing source to `pos`, measured from the start of that source.
+    ///
+    /// Sources that have not started yet are not affected and the [`MixerClock`] keeps
+    /// counting. All sources are seeked even if one of them fails, in which case the
+    /// first error is returned.
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        let mut result = Ok(());
+        for voice in &mut self.current_sources {
+            let seeked = voice.source.try_seek(pos);
+            if result.is_ok() {
+                result = seeked;
+            }
+        }
+        result
     }
 }
 
@@ -120,11 +660,14 @@ impl Iterator for MixerSource {
     fn next(&mut self) -> Option<Self::Item> {
         self.start_pending_sources();
 
-        self.sample_count += 1;
-
         let sum = self.sum_current_sources();
 
-        if self.current_sources.is_empty() {
+        // Only published after summing so sources reading the clock while
+        // being mixed see the index of the sample they are producing.
+        self.sample_count += 1;
+        self.publish();
+
+        if self.should_end() {
             None
         } else {
             Some(sum)
@@ -138,33 +681,150 @@ impl Iterator for MixerSource {
 }
 
 impl MixerSource {
+    /// Number of sources playing, not counting those fading out after being
+    /// stolen or those that have not started yet.
+    #[inline]
+    pub fn active_sources(&self) -> usize {
+        self.active_sources
+    }
+
+    /// Whether there is nothing left to play and the mixer should end.
+    #[inline]
+    fn should_end(&self) -> bool {
+        if !self.current_sources.is_empty() || !self.still_pending.is_empty() {
+            return false;
+        }
+        let inner = &self.input.0;
+        // Nobody can add sources once only this output holds on to the mixer.
+        let closed = inner.closed.load(Ordering::Acquire)
+            || Arc::strong_count(&self.input.0) == 1
+            || inner.parent_ended();
+        closed || !inner.keep_alive_if_empty.load(Ordering::Acquire)
+    }
+
+    /// Makes the clock and the number of active sources visible to the [`Mixer`].
+    #[inline]
+    fn publish(&self) {
+        let inner = &self.input.0;
+        inner
+            .sample_count
+            .store(self.sample_count, Ordering::Release);
+        inner
+            .active_sources
+            .store(self.active_sources, Ordering::Relaxed);
+    }
+
     // Samples from the #next() function are interlaced for each of the channels.
     // We need to ensure we start playing sources so that their samples are
     // in-step with the modulo of the samples produced so far. Otherwise, the
     // sound will play on the wrong channels, e.g. left / right will be reversed.
     fn start_pending_sources(&mut self) {
-        while let Ok(source) = self.pending_rx.try_recv() {
-            let in_step = self
-                .sample_count
-                .is_multiple_of(source.channels().get() as usize);
+        while let Ok(pending) = self.pending_rx.try_recv() {
+            self.still_pending.push(pending);
+        }
 
-            if in_step {
-                self.current_sources.push(source);
+        let channels = self.input.0.channels.get() as u64;
+        if !self.sample_count.is_multiple_of(channels) {
+            return;
+        }
+
+        // The limit might have been lowered.
+        let max_voices = self.input.0.max_voices.load(Ordering::Relaxed);
+        while self.active_sources > max_voices {
+            let Some(victim) = self.pick_victim(None) else {
+                break;
+            };
+            self.steal(victim);
+        }
+
+        if self.still_pending.is_empty() {
+            return;
+        }
+
+        let frame = self.sample_count / channels;
+        let mut idx = 0;
+        while idx < self.still_pending.len() {
+            if self.still_pending[idx].start_frame <= frame {
+                // The order of sources that are due does not matter.
+                let pending = self.still_pending.swap_remove(idx);
+                if !pending.bus && self.active_sources >= max_voices {
+                    match self.pick_victim(Some(pending.priority)) {
+                        Some(victim) => self.steal(victim),
+                        // Everything playing is more important, drop it.
+                        None => continue,
+                    }
+                }
+                self.current_sources.push(Voice {
+                    source: pending.source,
+                    priority: pending.priority,
+                    started_at: self.sample_count,
+                    level: 0.0,
+                    fade_out: None,
+                    controls: pending.controls,
+                    paused: false,
+                    bus: pending.bus,
+                    sends: SendTaps::default(),
+                });
+                if !pending.bus {
+                    self.active_sources += 1;
+                }
             } else {
-                self.still_pending.push(source);
+                idx += 1;
             }
         }
     }
 
+    /// Picks the playing source that should make room for one with `priority`,
+    /// or for a lowered limit if `priority` is `None`.
+    fn pick_victim(&self, priority: Option<i32>) -> Option<usize> {
+        let stealing = VoiceStealing::from_u8(self.input.0.voice_stealing.load(Ordering::Relaxed));
+        let candidates = self
+            .current_sources
+            .iter()
+            .enumerate()
+            .filter(|(_, voice)| voice.is_active())
+            .filter(|(_, voice)| priority.is_none_or(|priority| voice.priority <= priority));
+        let victim = match stealing {
+            VoiceStealing::Oldest => candidates.min_by_key(|(_, voice)| voice.started_at),
+            VoiceStealing::Quietest => {
+                candidates.min_by(|(_, a), (_, b)| a.level.total_cmp(&b.level))
+            }
+            VoiceStealing::LowestPriority => {
+                candidates.min_by_key(|(_, voice)| (voice.priority, voice.started_at))
+            }
+        };
+        victim.map(|(idx, _)| idx)
+    }
+
+    /// Starts fading out the source at `idx`. Must be called on a frame boundary.
+    fn steal(&mut self, idx: usize) {
+        let channels = self.input.0.channels.get() as u64;
+        let frames = math::duration_to_frames(STEAL_FADE_OUT, self.input.0.sample_rate);
+        let samples = frames.max(1) * channels;
+        self.current_sources[idx].fade_out = Some(FadeOut {
+            left: samples,
+            len: samples,
+        });
+        self.active_sources -= 1;
+    }
+
     fn sum_current_sources(&mut self) -> Sample {
+        let channels = self.input.0.channels.get() as u64;
+        let frame_start = self.sample_count.is_multiple_of(channels);
         let mut sum = 0.0;
-        self.current_sources.retain_mut(|source| {
-            match source.next() {
+        let active_sources = &mut self.active_sources;
+        self.current_sources.retain_mut(|voice| {
+            match voice.next(frame_start) {
                 Some(value) => {
                     sum += value;
                     true // Keep this source
                 }
-                None => false, // Remove exhausted source
+                None => {
+                    if voice.is_active() {
+                        *active_sources -= 1;
+                    }
+                    false // Remove exhausted source
+                }
             }
         });
 
@@ -172,12 +832,290 @@ impl MixerSource {
     }
 }
 
+/// How much the level of a voice drops per sample once it gets quieter, ~70 ms
+/// to halve at 44.1 kHz stereo.
+const LEVEL_DECAY: Float = 0.99989;
+
+// A source being mixed.
+struct Voice {
+    source: Box<dyn Source + Send>,
+    priority: i32,
+    // The output sample the voice started on, finds the oldest voice.
+    started_at: u64,
+    // Recent peak level, finds the quietest voice.
+    level: Float,
+    // Set once the voice has been stolen.
+    fade_out: Option<FadeOut>,
+    controls: Option<ControlsGuard>,
+    // Read from `controls` at the start of every frame.
+    paused: bool,
+    bus: bool,
+    // Follows the sends in `controls`.
+    sends: SendTaps,
+}
+
+#[derive(Clone, Copy)]
+struct FadeOut {
+    // Samples until the voice is silent.
+    left: u64,
+    len: u64,
+}
+
+impl Voice {
+    #[inline]
+    fn is_fading(&self) -> bool {
+        self.fade_out.is_some()
+    }
+
+    /// Whether the voice counts towards the voice limit.
+    #[inline]
+    fn is_active(&self) -> bool {
+        !self.bus && !self.is_fading()
+    }
+
+    #[inline]
+    fn volume(&self) -> Float {
+        self.controls
+            .as_ref()
+            .map_or(1.0, |controls| controls.0.volume())
+    }
+
+    /// Reads the controls of the voice. Returns false if it has been stopped.
+    ///
+    /// Pausing only takes effect at the start of a frame, otherwise the channels
+    /// of the voice would shift.
+    #[inline]
+    fn update_controls(&mut self, frame_start: bool) -> bool {
+        if let Some(ControlsGuard(controls)) = &self.controls {
+            if controls.stopped.load(Ordering::Relaxed) {
+                return false;
+            }
+            if frame_start {
+                self.paused = controls.paused.load(Ordering::Relaxed);
+                self.sends.update(&controls.sends);
+            }
+        }
+        // A stolen voice that is paused is silent already.
+        !(self.paused && self.is_fading())
+    }
+
+    #[inline]
+    fn next(&mut self, frame_start: bool) -> Option<Sample> {
+        if self.fade_out.is_some_and(|fade_out| fade_out.left == 0)
+            || !self.update_controls(frame_start)
+        {
+            return None;
+        }
+        if self.paused {
+            self.level *= LEVEL_DECAY;
+            return Some(0.0);
+        }
+
+        let (channels, sample_rate) = (self.source.channels(), self.source.sample_rate());
+        let mut sample = self.source.next()?;
+        if let Some(fade_out) = &mut self.fade_out {
+            sample *= fade_out.left as Float / fade_out.len as Float;
+            fade_out.left -= 1;
+        }
+        let volume = self.volume();
+        if !self.sends.is_empty() {
+            self.sends.write(&[sample], volume, channels, sample_rate);
+        }
+        let sample = sample * volume;
+        self.level = (self.level * LEVEL_DECAY).max(sample.abs());
+        Some(sample)
+    }
+
+    /// Fills `buffer`, only writing fewer samples if the voice ends. The
+    /// buffer starts at sample `start`, which is stored in `render_position`
+    /// with the samples already filled added before the source is read.
+    fn fill(
+        &mut self,
+        buffer: &mut [Sample],
+        frame_start: bool,
+        (render_position, start): (&AtomicU64, u64),
+    ) -> usize {
+        if !self.update_controls(frame_start) {
+            return 0;
+        }
+        if self.paused {
+            buffer.fill(0.0);
+            self.level *= LEVEL_DECAY.powi(buffer.len() as i32);
+            return buffer.len();
+        }
+
+        let len = match self.fade_out {
+            Some(fade_out) => buffer.len().min(fade_out.left as usize),
+            None => buffer.len(),
+        };
+        let (channels, sample_rate) = (self.source.channels(), self.source.sample_rate());
+        let mut filled = 0;
+        while filled < len {
+            render_position.store(start + filled as u64, Ordering::Relaxed);
+            match self.source.fill_buffer(&mut buffer[filled..len]) {
+                0 => break,
+                written => filled += written,
+            }
+        }
+
+        let samples = &mut buffer[..filled];
+        if let Some(fade_out) = &mut self.fade_out {
+            for sample in samples.iter_mut() {
+                *sample *= fade_out.left as Float / fade_out.len as Float;
+                fade_out.left -= 1;
+            }
+        }
+        let volume = self.volume();
+        if !self.sends.is_empty() {
+            self.sends.write(samples, volume, channels, sample_rate);
+        }
+        if volume != 1.0 {
+            simd::scale(samples, volume);
+        }
+        let peak = samples
+            .iter()
+            .fold(0.0, |peak: Sample, sample| peak.max(sample.abs()));
+        self.level = (self.level * LEVEL_DECAY.powi(filled as i32)).max(peak);
+        filled
+    }
+}
+
+#[cfg(not(feature = "64bit"))]
+type AtomicFloatBits = std::sync::atomic::AtomicU32;
+#[cfg(feature = "64bit")]
+type AtomicFloatBits = AtomicU64;
+
+// Shared between a `VoiceHandle` and its voice.
+struct VoiceControls {
+    stopped: AtomicBool,
+    paused: AtomicBool,
+    volume: AtomicFloatBits,
+    playing: AtomicBool,
+    sends: SendList,
+}
+
+impl VoiceControls {
+    #[inline]
+    fn volume(&self) -> Float {
+        Float::from_bits(self.volume.load(Ordering::Relaxed))
+    }
+}
+
+// Held by the voice, marks it as no longer playing when dropped. That is when
+// the source ended, was stopped or stolen, never got to start or when the
+// mixer itself is dropped.
+struct ControlsGuard(Arc<VoiceControls>);
+
+impl Drop for ControlsGuard {
+    fn drop(&mut self) {
+        self.0.playing.store(false, Ordering::Release);
+    }
+}
+
+/// Controls a source added with [`Mixer::add_with_handle`].
+///
+/// The handle can be cloned and used from any thread. Dropping it does not
+/// stop the source.
+#[derive(Clone)]
+pub struct VoiceHandle {
+    controls: Arc<VoiceControls>,
+}
+
+impl VoiceHandle {
+    /// Stops the source, it is removed from the mixer.
+    #[inline]
+    pub fn stop(&self) {
+        self.controls.stopped.store(true, Ordering::Relaxed);
+    }
+
+    /// Pauses the source. A paused source plays silence and stays in the mixer.
+    ///
+    /// No effect if already paused.
+    #[inline]
+    pub fn pause(&self) {
+        self.controls.paused.store(true, Ordering::Relaxed);
+    }
+
+    /// Resumes a paused source.
+    ///
+    /// No effect if not paused.
+    #[inline]
+    pub fn play(&self) {
+        self.controls.paused.store(false, Ordering::Relaxed);
+    }
+
+    /// Whether the source is paused.
+    #[inline]
+    pub fn is_paused(&self) -> bool {
+        self.controls.paused.load(Ordering::Relaxed)
+    }
+
+    /// Gets the volume of the source.
+    ///
+    /// The value `1.0` is the "normal" volume (unfiltered input).
+    #[inline]
+    pub fn volume(&self) -> Float {
+        self.controls.volume()
+    }
+
+    /// Changes the volume of the source.
+    ///
+    /// The value `1.0` is the "normal" volume (unfiltered input). Any value
+    /// other than `1.0` will multiply each sample by this value.
+    #[inline]
+    pub fn set_volume(&self, value: Float) {
+        self.controls
+            .volume
+            .store(value.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Whether the source is still in the mixer, paused or not. Returns false
+    /// once it ended, was stopped, or was stolen by
+    /// [voice limiting](Mixer::set_max_voices).
+    #[inline]
+    pub fn is_playing(&self) -> bool {
+        self.controls.playing.load(Ordering::Acquire)
+    }
+
+    /// Sends a copy of the source to `bus`, multiplied by `level`. See
+    /// [`AuxSend`].
+    ///
+    /// # Example
+    /// ```
+    /// use rodio::mixer;
+    /// use rodio::source::{SineWave, Source};
+    /// use rodio::math::nz;
+    /// use std::time::Duration;
+    ///
+    /// let (mixer, output) = mixer::mixer(nz!(2), nz!(48_000));
+    /// let room = mixer.bus_with_effects("room", |input| input.low_pass(2_000));
+    /// let beep = mixer.add_with_handle(SineWave::new(440.0).take_duration(Duration::from_secs(1)));
+    /// let send = beep.send_to(&room, 0.3);
+    /// send.set_level(0.5);
+    /// ```
+    #[inline]
+    pub fn send_to(&self, bus: &Bus, level: Float) -> AuxSend {
+        send::connect(&self.controls.sends, bus, level)
+    }
+}
+
+impl std::fmt::Debug for VoiceHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VoiceHandle")
+            .field("paused", &self.is_paused())
+            .field("volume", &self.volume())
+            .field("playing", &self.is_playing())
+            .finish()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::buffer::SamplesBuffer;
     use crate::math::nz;
     use crate::mixer;
     use crate::source::Source;
+    use std::time::Duration;
 
     #[test]
     fn basic() {
@@ -291,4 +1229,234 @@ mod tests {
 
         assert_eq!(rx.next(), None);
     }
+
+    #[test]
+    fn add_at_starts_on_frame() {
+        let (tx, mut rx) = mixer::mixer(nz!(2), nz!(48000));
+        let clock = tx.clock();
+
+        tx.add(SamplesBuffer::new(nz!(2), nz!(48000), vec![1.0; 8]));
+        tx.add_at(SamplesBuffer::new(nz!(2), nz!(48000), vec![2.0; 4]), 2);
+
+        let out: Vec<_> = rx.by_ref().take(8).collect();
+        assert_eq!(out, vec![1.0, 1.0, 1.0, 1.0, 3.0, 3.0, 3.0, 3.0]);
+        assert_eq!(clock.samples(), 8);
+        assert_eq!(clock.frames(), 4);
+        assert_eq!(rx.next(), None);
+    }
+
+    #[test]
+    fn add_at_keeps_mixer_alive_until_start() {
+        let (tx, mut rx) = mixer::mixer(nz!(1), nz!(48000));
+
+        tx.add_at(SamplesBuffer::new(nz!(1), nz!(48000), vec![5.0]), 3);
+
+        assert_eq!(rx.next(), Some(0.0));
+        assert_eq!(rx.next(), Some(0.0));
+        assert_eq!(rx.next(), Some(0.0));
+        assert_eq!(rx.next(), Some(5.0));
+        assert_eq!(rx.next(), None);
+    }
+
+    #[test]
+    fn keep_alive_until_closed() {
+        let (tx, mut rx) = mixer::mixer(nz!(1), nz!(48000));
+        tx.set_keep_alive_if_empty(true);
+        assert_eq!(rx.next(), Some(0.0));
+
+        tx.add(SamplesBuffer::new(nz!(1), nz!(48000), vec![5.0, 5.0]));
+        assert_eq!(rx.next(), Some(5.0));
+        tx.close();
+        // Added after closing, never played.
+        tx.add(SamplesBuffer::new(nz!(1), nz!(48000), vec![1.0]));
+        assert_eq!(rx.next(), Some(5.0));
+        assert_eq!(rx.next(), None);
+
+        let mut buffer = [1.0; 4];
+        assert_eq!(rx.fill_buffer(&mut buffer), 0);
+    }
+
+    #[test]
+    fn keep_alive_ends_without_handles() {
+        let (tx, mut rx) = mixer::mixer(nz!(1), nz!(48000));
+        tx.set_keep_alive_if_empty(true);
+        let mut buffer = [1.0; 4];
+        assert_eq!(rx.fill_buffer(&mut buffer), 4);
+        assert_eq!(buffer, [0.0; 4]);
+
+        drop(tx);
+        assert_eq!(rx.next(), None);
+    }
+
+    #[test]
+    fn seeks_playing_sources() {
+        let (tx, mut rx) = mixer::mixer(nz!(1), nz!(1000));
+
+        tx.add(SamplesBuffer::new(nz!(1), nz!(1000), vec![1.0; 1000]));
+        tx.add(SamplesBuffer::new(nz!(1), nz!(1000), vec![2.0; 500]));
+        assert_eq!(rx.next(), Some(3.0));
+
+        rx.try_seek(Duration::from_millis(600)).unwrap();
+        // The shorter source ended, the longer one has 400 samples left.
+        assert_eq!(rx.next(), Some(1.0));
+        assert_eq!(rx.count(), 399);
+    }
+
+    #[test]
+    fn fill_buffer_matches_next() {
+        let add_sources = |tx: &mixer::Mixer| {
+            tx.add(SamplesBuffer::new(nz!(1), nz!(48000), vec![1.0; 9]));
+            tx.add(SamplesBuffer::new(nz!(2), nz!(48000), vec![2.0, 3.0, 4.0]));
+            tx.add_at(SamplesBuffer::new(nz!(2), nz!(48000), vec![5.0; 6]), 5);
+        };
+
+        let (tx, rx) = mixer::mixer(nz!(2), nz!(48000));
+        add_sources(&tx);
+        let expected: Vec<_> = rx.collect();
+
+        let (tx, mut rx) = mixer::mixer(nz!(2), nz!(48000));
+        let clock = tx.clock();
+        add_sources(&tx);
+        let mut mixed = Vec::new();
+        let mut buffer = [0.0; 4];
+        loop {
+            let written = rx.fill_buffer(&mut buffer);
+            if written == 0 {
+                break;
+            }
+            mixed.extend_from_slice(&buffer[..written]);
+        }
+        assert_eq!(mixed, expected);
+        // Also counts the block in which the mixer found it had ended.
+        assert_eq!(clock.samples(), (expected.len() + buffer.len()) as u64);
+    }
+
+    #[test]
+    fn steals_oldest_voice() {
+        // 10 ms fade-out at 400 Hz is 4 samples.
+        let (tx, mut rx) = mixer::mixer(nz!(1), nz!(400));
+        tx.set_max_voices(Some(nz!(1)));
+
+        tx.add(SamplesBuffer::new(nz!(1), nz!(400), vec![1.0; 10]));
+        assert_eq!(rx.next(), Some(1.0));
+        tx.add(SamplesBuffer::new(nz!(1), nz!(400), vec![10.0; 10]));
+        let out: Vec<_> = rx.by_ref().take(5).collect();
+        assert_eq!(out, [11.0, 10.75, 10.5, 10.25, 10.0]);
+        assert_eq!(rx.active_sources(), 1);
+        assert_eq!(tx.active_sources(), 1);
+    }
+
+    #[test]
+    fn keeps_higher_priority_voices() {
+        let (tx, mut rx) = mixer::mixer(nz!(1), nz!(400));
+        tx.set_max_voices(Some(nz!(1)));
+
+        tx.add_with_priority(SamplesBuffer::new(nz!(1), nz!(400), vec![1.0; 10]), 5);
+        assert_eq!(rx.next(), Some(1.0));
+        tx.add(SamplesBuffer::new(nz!(1), nz!(400), vec![10.0; 10]));
+        assert_eq!(rx.next(), Some(1.0));
+        assert_eq!(rx.active_sources(), 1);
+    }
+
+    #[test]
+    fn drops_sources_that_may_not_steal() {
+        let (tx, mut rx) = mixer::mixer(nz!(1), nz!(400));
+        tx.set_max_voices(Some(nz!(1)));
+
+        tx.add_with_priority(SamplesBuffer::new(nz!(1), nz!(400), vec![1.0; 10]), 5);
+        assert_eq!(rx.next(), Some(1.0));
+        tx.add(SamplesBuffer::new(nz!(1), nz!(400), vec![10.0; 10]));
+        // The new source does not wait for a free voice.
+        let rest: Vec<_> = rx.by_ref().take(20).collect();
+        assert_eq!(rest, [1.0; 9]);
+    }
+
+    #[test]
+    fn steals_by_policy() {
+        let remaining = |stealing: mixer::VoiceStealing| {
+            let (tx, mut rx) = mixer::mixer(nz!(1), nz!(400));
+            tx.set_voice_stealing(stealing);
+            for (level, priority) in [(1.0, 2), (0.25, 2), (0.5, 0)] {
+                tx.add_with_priority(
+                    SamplesBuffer::new(nz!(1), nz!(400), vec![level; 20]),
+                    priority,
+                );
+                rx.next();
+            }
+
+            tx.set_max_voices(Some(nz!(3)));
+            tx.add_with_priority(SamplesBuffer::new(nz!(1), nz!(400), vec![0.0; 20]), 3);
+            // Past the fade-out of the stolen voice.
+            rx.nth(7).unwrap()
+        };
+
+        assert_eq!(remaining(mixer::VoiceStealing::Oldest), 0.75);
+        assert_eq!(remaining(mixer::VoiceStealing::Quietest), 1.5);
+        assert_eq!(remaining(mixer::VoiceStealing::LowestPriority), 1.25);
+    }
+
+    #[test]
+    fn lowering_limit_steals_voices() {
+        let (tx, mut rx) = mixer::mixer(nz!(1), nz!(400));
+        tx.add(SamplesBuffer::new(nz!(1), nz!(400), vec![1.0; 20]));
+        tx.add(SamplesBuffer::new(nz!(1), nz!(400), vec![2.0; 20]));
+        assert_eq!(rx.next(), Some(3.0));
+
+        tx.set_max_voices(Some(nz!(1)));
+        assert_eq!(rx.nth(4), Some(2.0));
+        assert_eq!(rx.active_sources(), 1);
+    }
+
+    #[test]
+    fn voice_handle_controls_source() {
+        let (tx, mut rx) = mixer::mixer(nz!(2), nz!(48000));
+        tx.add(SamplesBuffer::new(nz!(2), nz!(48000), vec![0.0; 100]));
+        let handle = tx.add_with_handle(SamplesBuffer::new(
+            nz!(2),
+            nz!(48000),
+            vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0],
+        ));
+        assert!(handle.is_playing());
+
+        handle.set_volume(0.5);
+        assert_eq!(rx.next(), Some(0.5));
+        // Pausing waits for the frame to end.
+        handle.pause();
+        assert_eq!(rx.next(), Some(1.0));
+        assert_eq!(rx.next(), Some(0.0));
+        assert_eq!(rx.next(), Some(0.0));
+
+        handle.play();
+        let mut buffer = [0.0; 2];
+        assert_eq!(rx.fill_buffer(&mut buffer), 2);
+        assert_eq!(buffer, [1.5, 2.0]);
+        assert!(handle.is_playing());
+
+        handle.stop();
+        assert_eq!(rx.next(), Some(0.0));
+        assert!(!handle.is_playing());
+    }
+
+    #[test]
+    fn voice_handle_reports_end() {
+        let (tx, mut rx) = mixer::mixer(nz!(1), nz!(48000));
+        let handle = tx.add_with_handle(SamplesBuffer::new(nz!(1), nz!(48000), vec![1.0]));
+        assert_eq!(rx.next(), Some(1.0));
+        assert!(handle.is_playing());
+        assert_eq!(rx.next(), None);
+        assert!(!handle.is_playing());
+    }
+
+    #[test]
+    fn clock_conversions() {
+        let (tx, _rx) = mixer::mixer(nz!(2), nz!(48000));
+        let clock = tx.clock();
+
+        assert_eq!(clock.duration_to_frames(Duration::from_millis(500)), 24_000);
+        assert_eq!(
+            clock.frames_to_duration(72_000),
+            Duration::from_millis(1500)
+        );
+        assert_eq!(clock.elapsed(), Duration::ZERO);
+    }
 }
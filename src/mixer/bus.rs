@@ -0,0 +1,440 @@
+//! Submix buses: mixers whose sum plays on a parent mixer.
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use super::{new_mixer, AtomicFloatBits, Inner, Mixer, MixerSource};
+use crate::common::{ChannelCount, SampleRate};
+use crate::math::simd;
+use crate::source::SeekError;
+use crate::{Float, Sample, Source};
+
+/// Builds a bus and the source that plays it on `parent`.
+pub(super) fn bus<F, S>(name: &str, parent: &Mixer, effects: F) -> (Bus, BusOutput<S>)
+where
+    F: FnOnce(BusInput) -> S,
+    S: Source,
+{
+    let parent = &parent.0;
+    let (mixer, source) = new_mixer(
+        parent.channels,
+        parent.sample_rate,
+        Some(Arc::downgrade(parent)),
+    );
+    mixer.set_keep_alive_if_empty(true);
+    let controls = Arc::new(BusControls {
+        name: name.to_owned(),
+        volume: AtomicFloatBits::new(Float::to_bits(1.0)),
+        muted: AtomicBool::new(false),
+        soloed: AtomicBool::new(false),
+        soloed_buses: parent.soloed_buses.clone(),
+    });
+    let output = BusOutput {
+        effects: effects(BusInput { mixer: source }),
+        controls: controls.clone(),
+    };
+    (Bus { mixer, controls }, output)
+}
+
+struct BusControls {
+    name: String,
+    volume: AtomicFloatBits,
+    muted: AtomicBool,
+    soloed: AtomicBool,
+    // Shared by all buses of the parent mixer.
+    soloed_buses: Arc<AtomicUsize>,
+}
+
+impl Drop for BusControls {
+    fn drop(&mut self) {
+        // The other buses play again once a soloed bus is gone.
+        if *self.soloed.get_mut() {
+            self.soloed_buses.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// A submix created with [`Mixer::bus`].
+///
+/// Add sources to it through [`Bus::mixer`]. The bus can be cloned and used
+/// from any thread.
+#[derive(Clone)]
+pub struct Bus {
+    mixer: Mixer,
+    controls: Arc<BusControls>,
+}
+
+impl Bus {
+    pub(super) fn downgrade(&self) -> WeakBus {
+        WeakBus {
+            mixer: Arc::downgrade(&self.mixer.0),
+            controls: Arc::downgrade(&self.controls),
+        }
+    }
+
+    /// The name the bus was created with.
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.controls.name
+    }
+
+    /// The mixer of this bus. Sources added to it play on the bus.
+    #[inline]
+    pub fn mixer(&self) -> &Mixer {
+        &self.mixer
+    }
+
+    /// Gets the volume of the bus.
+    #[inline]
+    pub fn volume(&self) -> Float {
+        Float::from_bits(self.controls.volume.load(Ordering::Relaxed))
+    }
+
+    /// Changes the volume of the bus.
+    ///
+    /// The value `1.0` is the "normal" volume. Any value other than `1.0` will
+    /// multiply each sample of the sum by this value.
+    #[inline]
+    pub fn set_volume(&self, value: Float) {
+        self.controls
+            .volume
+            .store(value.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Whether the bus is muted.
+    #[inline]
+    pub fn is_muted(&self) -> bool {
+        self.controls.muted.load(Ordering::Relaxed)
+    }
+
+    /// Mutes or unmutes the bus. A muted bus plays silence.
+    #[inline]
+    pub fn set_muted(&self, muted: bool) {
+        self.controls.muted.store(muted, Ordering::Relaxed);
+    }
+
+    /// Whether the bus is soloed.
+    #[inline]
+    pub fn is_solo(&self) -> bool {
+        self.controls.soloed.load(Ordering::Relaxed)
+    }
+
+    /// Solos the bus or ends its solo.
+    ///
+    /// While any bus of a mixer is soloed, the other buses of that mixer play
+    /// silence. Sources added to the mixer directly keep playing.
+    #[inline]
+    pub fn set_solo(&self, solo: bool) {
+        if self.controls.soloed.swap(solo, Ordering::Relaxed) != solo {
+            if solo {
+                self.controls.soloed_buses.fetch_add(1, Ordering::Relaxed);
+            } else {
+                self.controls.soloed_buses.fetch_sub(1, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+/// A bus as the parent mixer keeps track of it, without keeping it alive.
+pub(super) struct WeakBus {
+    mixer: Weak<Inner>,
+    controls: Weak<BusControls>,
+}
+
+impl WeakBus {
+    /// The bus, if any handle to it is left.
+    pub(super) fn upgrade(&self) -> Option<Bus> {
+        Some(Bus {
+            mixer: Mixer(self.mixer.upgrade()?),
+            controls: self.controls.upgrade()?,
+        })
+    }
+}
+
+impl std::fmt::Debug for Bus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Bus")
+            .field("name", &self.name())
+            .field("volume", &self.volume())
+            .field("muted", &self.is_muted())
+            .field("solo", &self.is_solo())
+            .finish()
+    }
+}
+
+/// The sum of the sources on a [`Bus`], passed to the effects of
+/// [`Mixer::bus_with_effects`].
+///
+/// It plays silence while the bus is empty and ends as described in
+/// [`Mixer::bus`].
+pub struct BusInput {
+    mixer: MixerSource,
+}
+
+impl Iterator for BusInput {
+    type Item = Sample;
+
+    #[inline]
+    fn next(&mut self) -> Option<Sample> {
+        self.mixer.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.mixer.size_hint()
+    }
+}
+
+impl Source for BusInput {
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        None
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.mixer.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.mixer.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+
+    #[inline]
+    fn fill_buffer(&mut self, buffer: &mut [Sample]) -> usize {
+        self.mixer.fill_buffer(buffer)
+    }
+
+    #[inline]
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.mixer.try_seek(pos)
+    }
+}
+
+/// Plays the effects of a bus on its parent mixer, applying volume, mute and
+/// solo.
+pub(super) struct BusOutput<S> {
+    effects: S,
+    controls: Arc<BusControls>,
+}
+
+impl<S> BusOutput<S> {
+    #[inline]
+    fn gain(&self) -> Float {
+        let controls = &self.controls;
+        let silenced = controls.muted.load(Ordering::Relaxed)
+            || (controls.soloed_buses.load(Ordering::Relaxed) > 0
+                && !controls.soloed.load(Ordering::Relaxed));
+        if silenced {
+            0.0
+        } else {
+            Float::from_bits(controls.volume.load(Ordering::Relaxed))
+        }
+    }
+}
+
+impl<S> Iterator for BusOutput<S>
+where
+    S: Source,
+{
+    type Item = Sample;
+
+    #[inline]
+    fn next(&mut self) -> Option<Sample> {
+        let gain = self.gain();
+        self.effects.next().map(|sample| sample * gain)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.effects.size_hint()
+    }
+}
+
+impl<S> Source for BusOutput<S>
+where
+    S: Source,
+{
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        self.effects.current_span_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.effects.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.effects.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.effects.total_duration()
+    }
+
+    #[inline]
+    fn fill_buffer(&mut self, buffer: &mut [Sample]) -> usize {
+        let gain = self.gain();
+        let written = self.effects.fill_buffer(buffer);
+        simd::scale(&mut buffer[..written], gain);
+        written
+    }
+
+    #[inline]
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.effects.try_seek(pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::buffer::SamplesBuffer;
+    use crate::math::nz;
+    use crate::mixer;
+    use crate::Source;
+
+    #[test]
+    fn bus_plays_on_parent() {
+        let (tx, mut rx) = mixer::mixer(nz!(1), nz!(48000));
+        let music = tx.bus("music");
+        assert_eq!(tx.bus("music").name(), "music");
+        music
+            .mixer()
+            .add(SamplesBuffer::new(nz!(1), nz!(48000), vec![1.0, 2.0]));
+        music.set_volume(0.5);
+
+        assert_eq!(rx.next(), Some(0.5));
+        assert_eq!(rx.next(), Some(1.0));
+        // The bus keeps the parent alive.
+        assert_eq!(rx.next(), Some(0.0));
+        assert_eq!(tx.active_sources(), 0);
+    }
+
+    #[test]
+    fn effects_can_add_buses() {
+        let (tx, _rx) = mixer::mixer(nz!(1), nz!(48000));
+        let parent = tx.clone();
+        let sfx = tx.bus_with_effects("sfx", move |input| {
+            parent.bus("ducking");
+            input
+        });
+        assert_eq!(sfx.name(), "sfx");
+        assert_eq!(tx.bus("ducking").name(), "ducking");
+    }
+
+    #[test]
+    fn closed_bus_is_replaced() {
+        let (tx, mut rx) = mixer::mixer(nz!(1), nz!(48000));
+        let music = tx.bus("music");
+        music.mixer().close();
+        assert_eq!(rx.next(), None);
+
+        let music = tx.bus("music");
+        assert!(!music.mixer().is_closed());
+        assert_eq!(rx.next(), Some(0.0));
+    }
+
+    #[test]
+    fn bus_ends_with_parent() {
+        let (tx, mut rx) = mixer::mixer(nz!(1), nz!(48000));
+        let music = tx.bus("music");
+        let nested = music.mixer().bus("nested");
+        nested
+            .mixer()
+            .add(SamplesBuffer::new(nz!(1), nz!(48000), vec![1.0]));
+        tx.close();
+        assert!(nested.mixer().is_closed());
+        assert_eq!(rx.next(), Some(1.0));
+        assert_eq!(rx.next(), None);
+
+        let (tx, mut rx) = mixer::mixer(nz!(1), nz!(48000));
+        let music = tx.bus("music");
+        drop(tx);
+        assert_eq!(rx.next(), None);
+        assert!(!music.mixer().is_closed());
+    }
+
+    #[test]
+    fn bus_ends_without_handles() {
+        let (tx, mut rx) = mixer::mixer(nz!(1), nz!(48000));
+        let music = tx.bus("music");
+        music
+            .mixer()
+            .add(SamplesBuffer::new(nz!(1), nz!(48000), vec![1.0, 2.0]));
+        drop(music);
+        // Still playing, so the bus can be found again.
+        assert_eq!(tx.bus("music").mixer().active_sources(), 0);
+        assert_eq!(rx.next(), Some(1.0));
+        assert_eq!(rx.next(), Some(2.0));
+        assert_eq!(rx.next(), None);
+    }
+
+    #[test]
+    fn dropped_solo_bus_unsilences_others() {
+        let (tx, mut rx) = mixer::mixer(nz!(1), nz!(48000));
+        let music = tx.bus("music");
+        let voice = tx.bus("voice");
+        music
+            .mixer()
+            .add(SamplesBuffer::new(nz!(1), nz!(48000), vec![1.0; 10]));
+        voice
+            .mixer()
+            .add(SamplesBuffer::new(nz!(1), nz!(48000), vec![2.0]));
+        voice.set_solo(true);
+        drop(voice);
+
+        assert_eq!(rx.next(), Some(2.0));
+        // The voice bus ends while the music bus plays its next sample.
+        assert_eq!(rx.nth(1), Some(1.0));
+    }
+
+    #[test]
+    fn effects_apply_to_sum() {
+        let (tx, mut rx) = mixer::mixer(nz!(1), nz!(48000));
+        let sfx = tx.bus_with_effects("sfx", |input| input.amplify(2.0));
+        sfx.mixer()
+            .add(SamplesBuffer::new(nz!(1), nz!(48000), vec![1.0]));
+        sfx.mixer()
+            .add(SamplesBuffer::new(nz!(1), nz!(48000), vec![0.5]));
+        assert_eq!(rx.next(), Some(3.0));
+    }
+
+    #[test]
+    fn mute_and_solo() {
+        let (tx, mut rx) = mixer::mixer(nz!(1), nz!(48000));
+        let music = tx.bus("music");
+        let voice = tx.bus("voice");
+        let nested = voice.mixer().bus("nested");
+        music
+            .mixer()
+            .add(SamplesBuffer::new(nz!(1), nz!(48000), vec![1.0; 10]));
+        nested
+            .mixer()
+            .add(SamplesBuffer::new(nz!(1), nz!(48000), vec![2.0; 10]));
+        assert_eq!(rx.next(), Some(3.0));
+
+        voice.set_solo(true);
+        voice.set_solo(true);
+        assert_eq!(rx.next(), Some(2.0));
+        voice.set_muted(true);
+        assert_eq!(rx.next(), Some(0.0));
+        voice.set_muted(false);
+        voice.set_solo(false);
+        assert_eq!(rx.next(), Some(3.0));
+
+        let mut buffer = [0.0; 4];
+        music.set_muted(true);
+        assert_eq!(rx.fill_buffer(&mut buffer), 4);
+        assert_eq!(buffer, [2.0; 4]);
+    }
+}
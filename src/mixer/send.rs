@@ -0,0 +1,594 @@
+//! Aux sends: copies of a sound played on a shared bus, for example a reverb
+//! used by every sound in a room.
+//!
+//! The sound writes its samples into a buffer while it plays, a source on the
+//! bus reads them back. That source has the format of the sound and is
+//! converted by the bus mixer like any other source.
+//!
+//! The audio thread never waits for the buffer: if the other side holds its
+//! lock, the sound keeps its samples for the next try and the bus plays silence.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use super::{AtomicFloatBits, Bus, BLOCK_LEN};
+use crate::common::{ChannelCount, SampleRate};
+use crate::math::simd;
+use crate::source::SeekError;
+use crate::{Float, Sample, Source};
+
+/// Samples collected by a sound before they are handed to the bus.
+const FLUSH_LEN: usize = 64;
+/// Samples a sound keeps while the buffer is locked by the bus. Older ones are
+/// dropped after that.
+const STAGED_LEN: usize = 2 * BLOCK_LEN;
+/// Frames of silence the bus plays while nothing was sent.
+const SILENCE_FRAMES: usize = 64;
+/// Samples beyond this are dropped, oldest first, if the bus falls behind or
+/// stopped playing.
+const MAX_BUFFERED: Duration = Duration::from_millis(200);
+
+/// Creates a send to `bus` and registers it in `list`.
+pub(crate) fn connect(list: &SendList, bus: &Bus, level: Float) -> AuxSend {
+    let inner = &bus.mixer().0;
+    let capacity = max_buffered(inner.channels, inner.sample_rate).max(2 * STAGED_LEN);
+    let shared = Arc::new(SendShared {
+        level: AtomicFloatBits::new(level.to_bits()),
+        pre_fader: AtomicBool::new(false),
+        removed: AtomicBool::new(false),
+        channels: inner.channels,
+        sample_rate: inner.sample_rate,
+        buffer: Mutex::new(SendBuffer {
+            samples: VecDeque::with_capacity(capacity),
+            channels: inner.channels,
+            sample_rate: inner.sample_rate,
+        }),
+    });
+    // Like a bus output, the return is not a voice of the bus.
+    bus.mixer().add_unstealable(SendReturn::new(shared.clone()));
+
+    let mut sends = list.sends.lock().unwrap();
+    sends.retain(|send| !send.removed.load(Ordering::Relaxed));
+    sends.push(shared.clone());
+    list.version.fetch_add(1, Ordering::Release);
+    AuxSend { shared }
+}
+
+/// Number of samples in [`MAX_BUFFERED`] of audio in the given format.
+fn max_buffered(channels: ChannelCount, sample_rate: SampleRate) -> usize {
+    let frames = MAX_BUFFERED.as_millis() as usize * sample_rate.get() as usize / 1000;
+    frames * channels.get() as usize
+}
+
+struct SendShared {
+    level: AtomicFloatBits,
+    pre_fader: AtomicBool,
+    removed: AtomicBool,
+    // The format of the bus, which the buffer starts in.
+    channels: ChannelCount,
+    sample_rate: SampleRate,
+    buffer: Mutex<SendBuffer>,
+}
+
+struct SendBuffer {
+    // Never grows past the capacity it was created with.
+    samples: VecDeque<Sample>,
+    channels: ChannelCount,
+    sample_rate: SampleRate,
+}
+
+/// Controls an aux send, created with [`Player::send_to`](crate::Player::send_to)
+/// or [`VoiceHandle::send_to`](super::VoiceHandle::send_to).
+///
+/// The sound keeps playing as usual, and a copy of it multiplied by the send
+/// level plays on the bus. Use this to share one expensive effect, such as a
+/// reverb, between many sounds.
+///
+/// Sends are post-fader by default: the copy follows the volume of the sound.
+/// The handle can be cloned and used from any thread. Dropping it does not
+/// remove the send.
+#[derive(Clone)]
+pub struct AuxSend {
+    shared: Arc<SendShared>,
+}
+
+impl AuxSend {
+    /// Gets the send level.
+    #[inline]
+    pub fn level(&self) -> Float {
+        Float::from_bits(self.shared.level.load(Ordering::Relaxed))
+    }
+
+    /// Changes the send level, the gain applied to the copy sent to the bus.
+    #[inline]
+    pub fn set_level(&self, level: Float) {
+        self.shared.level.store(level.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Whether the send is taken before the volume of the sound is applied.
+    #[inline]
+    pub fn is_pre_fader(&self) -> bool {
+        self.shared.pre_fader.load(Ordering::Relaxed)
+    }
+
+    /// Takes the send before (`true`) or after (`false`) the volume of the
+    /// sound is applied. A pre-fader send keeps playing on the bus while the
+    /// sound is turned down.
+    #[inline]
+    pub fn set_pre_fader(&self, pre_fader: bool) {
+        self.shared.pre_fader.store(pre_fader, Ordering::Relaxed);
+    }
+
+    /// Stops sending to the bus. The send can not be used again.
+    #[inline]
+    pub fn remove(&self) {
+        self.shared.removed.store(true, Ordering::Relaxed);
+    }
+}
+
+impl std::fmt::Debug for AuxSend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuxSend")
+            .field("level", &self.level())
+            .field("pre_fader", &self.is_pre_fader())
+            .finish()
+    }
+}
+
+/// The sends of a sound, shared between its controls and the audio thread.
+#[derive(Default)]
+pub(crate) struct SendList {
+    sends: Mutex<Vec<Arc<SendShared>>>,
+    // Bumped whenever a send is added.
+    version: AtomicUsize,
+}
+
+/// The audio thread side of a [`SendList`].
+#[derive(Default)]
+pub(crate) struct SendTaps {
+    taps: Vec<SendTap>,
+    version: usize,
+}
+
+impl SendTaps {
+    /// Picks up sends added or removed since the last update. Only locks
+    /// `list` if a send was added, and tries again on the next update if it
+    /// is locked.
+    pub(crate) fn update(&mut self, list: &SendList) {
+        self.taps
+            .retain(|tap| !tap.shared.removed.load(Ordering::Relaxed));
+        let version = list.version.load(Ordering::Acquire);
+        if version == self.version {
+            return;
+        }
+        let Ok(sends) = list.sends.try_lock() else {
+            return;
+        };
+        self.version = version;
+        for send in sends.iter() {
+            let known = self.taps.iter().any(|tap| Arc::ptr_eq(&tap.shared, send));
+            if !known && !send.removed.load(Ordering::Relaxed) {
+                self.taps.push(SendTap::new(send.clone()));
+            }
+        }
+    }
+
+    #[inline]
+    pub(crate) fn is_empty(&self) -> bool {
+        self.taps.is_empty()
+    }
+
+    /// Sends `samples`, taken before a fader with gain `fader`.
+    #[inline]
+    pub(crate) fn write(
+        &mut self,
+        samples: &[Sample],
+        fader: Float,
+        channels: ChannelCount,
+        sample_rate: SampleRate,
+    ) {
+        for tap in &mut self.taps {
+            tap.write(samples, fader, channels, sample_rate);
+        }
+    }
+}
+
+struct SendTap {
+    shared: Arc<SendShared>,
+    staged: Vec<Sample>,
+    channels: ChannelCount,
+    sample_rate: SampleRate,
+}
+
+impl SendTap {
+    fn new(shared: Arc<SendShared>) -> Self {
+        SendTap {
+            staged: Vec::with_capacity(STAGED_LEN),
+            channels: shared.channels,
+            sample_rate: shared.sample_rate,
+            shared,
+        }
+    }
+
+    fn write(
+        &mut self,
+        samples: &[Sample],
+        fader: Float,
+        channels: ChannelCount,
+        sample_rate: SampleRate,
+    ) {
+        if (channels, sample_rate) != (self.channels, self.sample_rate) {
+            self.flush();
+            // Dropped if the bus held the buffer, they can not be sent later.
+            self.staged.clear();
+            self.channels = channels;
+            self.sample_rate = sample_rate;
+        }
+
+        let mut gain = Float::from_bits(self.shared.level.load(Ordering::Relaxed));
+        if !self.shared.pre_fader.load(Ordering::Relaxed) {
+            gain *= fader;
+        }
+        for chunk in samples.chunks(STAGED_LEN) {
+            if self.staged.len() + chunk.len() > STAGED_LEN {
+                self.flush();
+                self.drop_staged(chunk.len());
+            }
+            let start = self.staged.len();
+            self.staged.extend_from_slice(chunk);
+            simd::scale(&mut self.staged[start..], gain);
+        }
+
+        if self.staged.len() >= FLUSH_LEN {
+            self.flush();
+        }
+    }
+
+    /// Drops the oldest whole frames until `len` more samples fit in `staged`.
+    fn drop_staged(&mut self, len: usize) {
+        let excess = (self.staged.len() + len).saturating_sub(STAGED_LEN);
+        if excess > 0 {
+            let channels = self.channels.get() as usize;
+            let excess = excess.next_multiple_of(channels).min(self.staged.len());
+            self.staged.drain(..excess);
+        }
+    }
+
+    /// Hands the staged samples to the bus, unless it holds the buffer.
+    fn flush(&mut self) {
+        if self.staged.is_empty() {
+            return;
+        }
+        let Ok(mut buffer) = self.shared.buffer.try_lock() else {
+            return;
+        };
+        if (buffer.channels, buffer.sample_rate) != (self.channels, self.sample_rate) {
+            // Samples in the old format that were not played yet are lost.
+            buffer.samples.clear();
+            buffer.channels = self.channels;
+            buffer.sample_rate = self.sample_rate;
+        }
+
+        // Make room without growing the buffer, dropping the oldest frames.
+        let channels = self.channels.get() as usize;
+        let max_len = max_buffered(self.channels, self.sample_rate)
+            .min(buffer.samples.capacity())
+            .max(STAGED_LEN);
+        let excess = (buffer.samples.len() + self.staged.len()).saturating_sub(max_len);
+        if excess > 0 {
+            let excess = excess.next_multiple_of(channels).min(buffer.samples.len());
+            buffer.samples.drain(..excess);
+        }
+        buffer.samples.extend(self.staged.drain(..));
+    }
+}
+
+impl Drop for SendTap {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+/// Plays what was sent, on the bus.
+struct SendReturn {
+    shared: Arc<SendShared>,
+    // The current span: whole frames taken from the buffer, or silence.
+    span: Vec<Sample>,
+    pos: usize,
+    channels: ChannelCount,
+    sample_rate: SampleRate,
+}
+
+impl SendReturn {
+    fn new(shared: Arc<SendShared>) -> Self {
+        let capacity = shared.buffer.lock().unwrap().samples.capacity();
+        let mut source = SendReturn {
+            span: Vec::with_capacity(capacity),
+            pos: 0,
+            channels: shared.channels,
+            sample_rate: shared.sample_rate,
+            shared,
+        };
+        source.next_span();
+        source
+    }
+
+    /// Takes the samples sent so far, or plays silence while the sound holds
+    /// the buffer. Leaves the span empty once the send is gone and everything
+    /// was played.
+    fn next_span(&mut self) {
+        self.span.clear();
+        self.pos = 0;
+
+        let Ok(mut buffer) = self.shared.buffer.try_lock() else {
+            let channels = self.channels.get() as usize;
+            self.span.resize(SILENCE_FRAMES * channels, 0.0);
+            return;
+        };
+        self.channels = buffer.channels;
+        self.sample_rate = buffer.sample_rate;
+        let channels = self.channels.get() as usize;
+        let len = buffer.samples.len() / channels * channels;
+        if len > 0 {
+            self.span.extend(buffer.samples.drain(..len));
+        } else if !self.shared.removed.load(Ordering::Relaxed)
+            && Arc::strong_count(&self.shared) > 1
+        {
+            self.span.resize(SILENCE_FRAMES * channels, 0.0);
+        }
+    }
+}
+
+impl Iterator for SendReturn {
+    type Item = Sample;
+
+    #[inline]
+    fn next(&mut self) -> Option<Sample> {
+        let sample = *self.span.get(self.pos)?;
+        self.pos += 1;
+        if self.pos == self.span.len() {
+            self.next_span();
+        }
+        Some(sample)
+    }
+}
+
+impl Source for SendReturn {
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        Some(self.span.len() - self.pos)
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.channels
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.sample_rate
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+
+    #[inline]
+    fn fill_buffer(&mut self, buffer: &mut [Sample]) -> usize {
+        let available = &self.span[self.pos..];
+        let len = available.len().min(buffer.len());
+        buffer[..len].copy_from_slice(&available[..len]);
+        self.pos += len;
+        if len > 0 && self.pos == self.span.len() {
+            self.next_span();
+        }
+        len
+    }
+
+    #[inline]
+    fn try_seek(&mut self, _: Duration) -> Result<(), SeekError> {
+        Ok(())
+    }
+}
+
+/// Sends a source to the sends of a [`Player`](crate::Player). The source is
+/// taken right before the volume of the player.
+pub(crate) struct AuxSends<I> {
+    input: I,
+    taps: SendTaps,
+    fader: Float,
+}
+
+impl<I> AuxSends<I> {
+    #[inline]
+    pub(crate) fn new(input: I) -> Self {
+        AuxSends {
+            input,
+            taps: SendTaps::default(),
+            fader: 1.0,
+        }
+    }
+
+    /// Picks up changes to `list` and sets the volume applied after this
+    /// source, for post-fader sends.
+    #[inline]
+    pub(crate) fn update(&mut self, list: &SendList, fader: Float) {
+        self.taps.update(list);
+        self.fader = fader;
+    }
+
+    #[inline]
+    pub(crate) fn inner(&self) -> &I {
+        &self.input
+    }
+
+    #[inline]
+    pub(crate) fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+}
+
+impl<I> Iterator for AuxSends<I>
+where
+    I: Source,
+{
+    type Item = Sample;
+
+    #[inline]
+    fn next(&mut self) -> Option<Sample> {
+        if self.taps.is_empty() {
+            return self.input.next();
+        }
+        let (channels, sample_rate) = (self.input.channels(), self.input.sample_rate());
+        let sample = self.input.next()?;
+        self.taps
+            .write(&[sample], self.fader, channels, sample_rate);
+        Some(sample)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<I> Source for AuxSends<I>
+where
+    I: Source,
+{
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        self.input.current_span_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    #[inline]
+    fn fill_buffer(&mut self, buffer: &mut [Sample]) -> usize {
+        let (channels, sample_rate) = (self.input.channels(), self.input.sample_rate());
+        let written = self.input.fill_buffer(buffer);
+        self.taps
+            .write(&buffer[..written], self.fader, channels, sample_rate);
+        written
+    }
+
+    #[inline]
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::buffer::SamplesBuffer;
+    use crate::math::nz;
+    use crate::{mixer, Player, Sample, Source};
+
+    fn sum(output: impl Iterator<Item = Sample>, len: usize) -> Sample {
+        output.take(len).sum()
+    }
+
+    #[test]
+    fn voice_sends_to_bus() {
+        let (tx, mut rx) = mixer::mixer(nz!(1), nz!(1000));
+        let aux = tx.bus("aux");
+        let voice = tx.add_with_handle(SamplesBuffer::new(nz!(1), nz!(1000), vec![1.0; 200]));
+        voice.set_volume(0.5);
+        voice.send_to(&aux, 0.5);
+
+        // The voice at volume 0.5 plus the send at 0.5 of that.
+        assert_eq!(sum(&mut rx, 1000), 100.0 + 50.0);
+        assert_eq!(rx.next(), Some(0.0));
+
+        let voice = tx.add_with_handle(SamplesBuffer::new(nz!(1), nz!(1000), vec![1.0; 200]));
+        voice.set_volume(0.0);
+        voice.send_to(&aux, 0.5).set_pre_fader(true);
+        assert_eq!(sum(&mut rx, 1000), 100.0);
+    }
+
+    #[test]
+    fn removed_send_stops() {
+        let (tx, mut rx) = mixer::mixer(nz!(1), nz!(1000));
+        let aux = tx.bus("aux");
+        let voice = tx.add_with_handle(SamplesBuffer::new(nz!(1), nz!(1000), vec![1.0; 1000]));
+        let send = voice.send_to(&aux, 1.0);
+        send.remove();
+        assert_eq!(sum(&mut rx, 2000), 1000.0);
+        assert_eq!(aux.mixer().active_sources(), 0);
+    }
+
+    #[test]
+    fn send_is_not_a_voice() {
+        let (tx, mut rx) = mixer::mixer(nz!(1), nz!(1000));
+        let aux = tx.bus("aux");
+        aux.mixer().set_max_voices(Some(nz!(1)));
+        let voice = tx.add_with_handle(SamplesBuffer::new(nz!(1), nz!(1000), vec![1.0; 200]));
+        voice.send_to(&aux, 1.0);
+        aux.mixer()
+            .add(SamplesBuffer::new(nz!(1), nz!(1000), vec![0.5; 200]));
+
+        // Neither source was stolen to make room for the other.
+        let played = sum(&mut rx, 100);
+        assert_eq!(aux.mixer().active_sources(), 1);
+        assert_eq!(played + sum(&mut rx, 400), 200.0 + 200.0 + 100.0);
+    }
+
+    #[test]
+    fn keeps_samples_while_the_bus_holds_the_buffer() {
+        let (tx, mut rx) = mixer::mixer(nz!(1), nz!(1000));
+        let aux = tx.bus("aux");
+        let voice = tx.add_with_handle(SamplesBuffer::new(nz!(1), nz!(1000), vec![1.0; 200]));
+        let send = voice.send_to(&aux, 1.0);
+
+        let buffer = send.shared.buffer.lock().unwrap();
+        // Only the voice plays, the send waits for the buffer.
+        assert_eq!(sum(&mut rx, 100), 100.0);
+        drop(buffer);
+        assert_eq!(sum(&mut rx, 1000), 100.0 + 200.0);
+    }
+
+    fn play(player_volume: Sample, send: Option<bool>) -> Sample {
+        let (tx, mut rx) = mixer::mixer(nz!(2), nz!(48000));
+        let aux = tx.bus("aux");
+        let player = Player::connect_new(&tx);
+        player.set_volume(player_volume);
+        if let Some(pre_fader) = send {
+            player.send_to(&aux, 1.0).set_pre_fader(pre_fader);
+        }
+        player.append(SamplesBuffer::new(nz!(1), nz!(48000), vec![1.0; 1000]));
+
+        let mut block = [0.0; 512];
+        let mut total = 0.0;
+        for _ in 0..20 {
+            let written = rx.fill_buffer(&mut block);
+            total += block[..written].iter().sum::<Sample>();
+        }
+        total
+    }
+
+    #[test]
+    fn player_sends_to_bus() {
+        // Mono upmixed to stereo.
+        let played = play(1.0, None);
+        assert_eq!(played, 2000.0);
+        assert_eq!(play(1.0, Some(false)), played * 2.0);
+        assert_eq!(play(0.0, Some(false)), 0.0);
+        assert_eq!(play(0.0, Some(true)), played);
+    }
+}
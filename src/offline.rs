@@ -0,0 +1,404 @@
+//! Drive a mixer without an audio device.
+//!
+//! An [`OfflineSink`] plays the role of a
+//! [`MixerDeviceSink`](crate::MixerDeviceSink) without a sound card. Sources
+//! and [`Player`](crate::Player)s are added to its [`Mixer`] as usual. The
+//! rendered audio is handed to you instead of a device, which makes it
+//! useful for headless rendering and for testing playback logic in CI.
+//!
+//! There are two ways to render:
+//! - Call [`OfflineSink::render`] yourself. Nothing happens between calls so
+//!   the output is fully deterministic.
+//! - Move the sink to a background thread with [`OfflineSink::spawn`] or
+//!   [`OfflineSink::spawn_writer`]. The thread renders either in real time or
+//!   as fast as possible, see [`Pacing`]. This is needed for calls that wait
+//!   for the audio thread such as [`Player::try_seek`](crate::Player::try_seek).
+//!
+//! # Example
+//! ```
+//! use rodio::offline::OfflineSink;
+//! use rodio::source::{SineWave, Source};
+//! use rodio::math::nz;
+//! use rodio::Player;
+//! use std::time::Duration;
+//!
+//! let mut sink = OfflineSink::new(nz!(2), nz!(44_100));
+//! let player = Player::connect_new(sink.mixer());
+//! player.append(SineWave::new(440.0).take_duration(Duration::from_millis(50)));
+//!
+//! let mut buffer = vec![0.0; 2 * 4410];
+//! sink.render(&mut buffer);
+//! assert!(buffer.iter().any(|s| *s != 0.0));
+//! ```
+
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::common::{ChannelCount, SampleRate};
+use crate::mixer::{mixer, Mixer, MixerSource, BLOCK_LEN};
+use crate::{Sample, Source};
+
+/// How fast a spawned [`OfflineSink`] renders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pacing {
+    /// Render no faster than the audio would play on a real device, as
+    /// measured by the wall clock.
+    RealTime,
+    /// Render as fast as the CPU allows.
+    Unpaced,
+}
+
+/// A sink that renders a [`Mixer`] without an audio device.
+///
+/// See the [module level docs](self) for details.
+pub struct OfflineSink {
+    mixer: Mixer,
+    source: MixerSource,
+    frames_rendered: u64,
+}
+
+impl OfflineSink {
+    /// Creates a sink with a mixer producing `channels` and `sample_rate`.
+    pub fn new(channels: ChannelCount, sample_rate: SampleRate) -> Self {
+        let (mixer, source) = mixer(channels, sample_rate);
+        Self {
+            mixer,
+            source,
+            frames_rendered: 0,
+        }
+    }
+
+    /// Access the sink's mixer.
+    pub fn mixer(&self) -> &Mixer {
+        &self.mixer
+    }
+
+    /// Number of channels of the rendered audio.
+    pub fn channels(&self) -> ChannelCount {
+        self.mixer.clock().channels()
+    }
+
+    /// Sample rate of the rendered audio.
+    pub fn sample_rate(&self) -> SampleRate {
+        self.mixer.clock().sample_rate()
+    }
+
+    /// Number of frames rendered so far. A frame is one sample for every
+    /// channel.
+    pub fn frames_rendered(&self) -> u64 {
+        self.frames_rendered
+    }
+
+    /// Fills `buffer` with interleaved samples from the mixer. Like a real
+    /// device the sink reads the mixer in blocks and plays silence while the
+    /// mixer has nothing to play.
+    ///
+    /// # Panics
+    /// If the length of `buffer` is not a multiple of the channel count.
+    pub fn render(&mut self, buffer: &mut [Sample]) {
+        let channels = self.channels().get() as usize;
+        assert!(
+            buffer.len().is_multiple_of(channels),
+            "buffer must hold whole frames"
+        );
+        // In blocks like a device, so sources see the mixer clock move the
+        // same way.
+        for block in buffer.chunks_mut(BLOCK_LEN) {
+            let mut filled = 0;
+            while filled < block.len() {
+                match self.source.fill_buffer(&mut block[filled..]) {
+                    0 => break,
+                    written => filled += written,
+                }
+            }
+            block[filled..].fill(0.0);
+        }
+        self.frames_rendered += (buffer.len() / channels) as u64;
+    }
+
+    /// Renders `duration` of audio in buffers of `buffer_frames` frames and
+    /// passes them to `output`.
+    pub fn render_duration(
+        &mut self,
+        duration: Duration,
+        buffer_frames: usize,
+        mut output: impl FnMut(&[Sample]),
+    ) {
+        let clock = self.mixer.clock();
+        let channels = self.channels().get() as usize;
+        let mut frames_left = clock.duration_to_frames(duration);
+        let mut buffer = vec![0.0; buffer_frames * channels];
+        while frames_left > 0 {
+            let frames = frames_left.min(buffer_frames as u64) as usize;
+            let buffer = &mut buffer[..frames * channels];
+            self.render(buffer);
+            output(buffer);
+            frames_left -= frames as u64;
+        }
+    }
+
+    /// Renders on a background thread, passing each buffer of
+    /// `buffer_frames` frames to `output`. Rendering continues until the
+    /// returned handle is dropped or [stopped](RunningOfflineSink::stop).
+    pub fn spawn<F>(self, pacing: Pacing, buffer_frames: usize, mut output: F) -> RunningOfflineSink
+    where
+        F: FnMut(&[Sample]) + Send + 'static,
+    {
+        self.spawn_inner(pacing, buffer_frames, move |buffer| {
+            output(buffer);
+            Ok(())
+        })
+    }
+
+    /// Renders on a background thread and writes the samples to `writer` as
+    /// raw interleaved little endian floats. Rendering continues until the
+    /// returned handle is dropped or stopped, or writing fails.
+    pub fn spawn_writer<W>(
+        self,
+        pacing: Pacing,
+        buffer_frames: usize,
+        mut writer: W,
+    ) -> RunningOfflineSink
+    where
+        W: io::Write + Send + 'static,
+    {
+        let mut bytes = Vec::new();
+        self.spawn_inner(pacing, buffer_frames, move |buffer| {
+            bytes.clear();
+            bytes.extend(buffer.iter().flat_map(|sample| sample.to_le_bytes()));
+            writer.write_all(&bytes)?;
+            writer.flush()
+        })
+    }
+
+    fn spawn_inner<F>(
+        self,
+        pacing: Pacing,
+        buffer_frames: usize,
+        mut output: F,
+    ) -> RunningOfflineSink
+    where
+        F: FnMut(&[Sample]) -> io::Result<()> + Send + 'static,
+    {
+        assert!(buffer_frames > 0, "buffer must hold at least one frame");
+
+        let mixer = self.mixer.clone();
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+        let mut sink = self;
+
+        let thread = std::thread::spawn(move || {
+            let clock = sink.mixer.clock();
+            let channels = sink.channels().get() as usize;
+            let mut buffer = vec![0.0; buffer_frames * channels];
+            let start = Instant::now();
+
+            while !thread_stop.load(Ordering::Relaxed) {
+                if pacing == Pacing::RealTime {
+                    // Render ahead by one buffer just like a device would.
+                    let due = clock.frames_to_duration(sink.frames_rendered);
+                    if let Some(wait) = due.checked_sub(start.elapsed()) {
+                        std::thread::sleep(wait);
+                    }
+                }
+                sink.render(&mut buffer);
+                output(&buffer)?;
+            }
+            Ok(())
+        });
+
+        RunningOfflineSink {
+            mixer,
+            stop,
+            thread: Some(thread),
+        }
+    }
+}
+
+impl std::fmt::Debug for OfflineSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OfflineSink")
+            .field("channels", &self.channels())
+            .field("sample_rate", &self.sample_rate())
+            .field("frames_rendered", &self.frames_rendered)
+            .finish()
+    }
+}
+
+/// An [`OfflineSink`] rendering on a background thread.
+///
+/// Rendering stops when this is dropped.
+pub struct RunningOfflineSink {
+    mixer: Mixer,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<io::Result<()>>>,
+}
+
+impl RunningOfflineSink {
+    /// Access the sink's mixer.
+    pub fn mixer(&self) -> &Mixer {
+        &self.mixer
+    }
+
+    /// Stops rendering and waits for the rendering thread to finish.
+    ///
+    /// # Errors
+    /// Returns the error that stopped a writer from
+    /// [`spawn_writer`](OfflineSink::spawn_writer) early.
+    pub fn stop(mut self) -> io::Result<()> {
+        self.stop_and_join()
+    }
+
+    fn stop_and_join(&mut self) -> io::Result<()> {
+        self.stop.store(true, Ordering::Relaxed);
+        match self.thread.take().map(JoinHandle::join) {
+            Some(Ok(result)) => result,
+            Some(Err(panic)) => std::panic::resume_unwind(panic),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for RunningOfflineSink {
+    fn drop(&mut self) {
+        if !std::thread::panicking() {
+            let _ = self.stop_and_join();
+        }
+    }
+}
+
+impl std::fmt::Debug for RunningOfflineSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RunningOfflineSink")
+            .field("clock", &self.mixer.clock())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use super::{OfflineSink, Pacing};
+    use crate::buffer::SamplesBuffer;
+    use crate::math::nz;
+    use crate::{Player, Sample};
+
+    #[test]
+    fn renders_silence_when_empty() {
+        let mut sink = OfflineSink::new(nz!(2), nz!(48_000));
+        let mut buffer = [1.0; 8];
+        sink.render(&mut buffer);
+        assert_eq!(buffer, [0.0; 8]);
+        assert_eq!(sink.frames_rendered(), 4);
+    }
+
+    #[test]
+    fn clock_follows_rendered_silence() {
+        let mut sink = OfflineSink::new(nz!(2), nz!(48_000));
+        let clock = sink.mixer().clock();
+        let mut buffer = [0.0; 4800];
+        sink.render(&mut buffer);
+        assert_eq!(sink.frames_rendered(), 2400);
+        assert_eq!(clock.frames(), 2400);
+    }
+
+    #[test]
+    fn scheduled_player_starts_on_its_frame() {
+        let mut sink = OfflineSink::new(nz!(1), nz!(48_000));
+        let player = Player::connect_new(sink.mixer());
+        let clock = sink.mixer().clock();
+        player.schedule_at(
+            SamplesBuffer::new(nz!(1), nz!(48_000), vec![1.0; 10]),
+            &clock,
+            700,
+        );
+
+        let mut buffer = vec![0.0; 2048];
+        sink.render(&mut buffer);
+        let start = buffer.iter().position(|sample| *sample != 0.0);
+        assert_eq!(start, Some(700));
+    }
+
+    #[test]
+    fn player_is_deterministic() {
+        let render = || {
+            let mut sink = OfflineSink::new(nz!(2), nz!(44_100));
+            let player = Player::connect_new(sink.mixer());
+            player.append(SamplesBuffer::new(nz!(2), nz!(44_100), vec![0.5; 2000]));
+            player.set_volume(0.5);
+
+            let mut rendered = Vec::new();
+            sink.render_duration(Duration::from_millis(100), 64, |buffer| {
+                rendered.extend_from_slice(buffer)
+            });
+            assert_eq!(sink.frames_rendered(), 4410);
+            rendered
+        };
+
+        let rendered = render();
+        assert_eq!(rendered.len(), 2 * 4410);
+        assert!(rendered.contains(&0.25));
+        assert!(rendered[rendered.len() - 100..].iter().all(|s| *s == 0.0));
+        assert_eq!(rendered, render());
+    }
+
+    #[test]
+    fn spawned_sink_renders() {
+        let sink = OfflineSink::new(nz!(1), nz!(1000));
+        let rendered = Arc::new(Mutex::new(Vec::new()));
+        let output = rendered.clone();
+        let running = sink.spawn(Pacing::Unpaced, 16, move |buffer| {
+            output.lock().unwrap().extend_from_slice(buffer)
+        });
+        let player = Player::connect_new(running.mixer());
+        player.append(SamplesBuffer::new(nz!(1), nz!(1000), vec![0.5; 4]));
+        player.sleep_until_end();
+        running.stop().unwrap();
+
+        let rendered = rendered.lock().unwrap();
+        assert!(rendered.contains(&0.5));
+        assert_eq!(rendered.len() % 16, 0);
+    }
+
+    #[derive(Clone, Default)]
+    struct SharedWriter(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for SharedWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn writer_gets_raw_samples() {
+        let sink = OfflineSink::new(nz!(1), nz!(1000));
+        sink.mixer()
+            .add(SamplesBuffer::new(nz!(1), nz!(1000), vec![0.5, -0.25, 1.0]));
+        let writer = SharedWriter::default();
+        let running = sink.spawn_writer(Pacing::Unpaced, 4, writer.clone());
+        while writer.0.lock().unwrap().is_empty() {
+            std::thread::yield_now();
+        }
+        running.stop().unwrap();
+
+        let bytes = writer.0.lock().unwrap();
+        let sample_len = size_of::<Sample>();
+        assert!(!bytes.is_empty());
+        assert_eq!(bytes.len() % (4 * sample_len), 0);
+        let samples: Vec<_> = bytes
+            .chunks_exact(sample_len)
+            .map(|chunk| Sample::from_le_bytes(chunk.try_into().unwrap()))
+            .collect();
+        assert_eq!(samples[..4], [0.5, -0.25, 1.0, 0.0]);
+    }
+}
@@ -8,10 +8,13 @@ use dasp_sample::FromSample;
 #[cfg(not(feature = "crossbeam-channel"))]
 use std::sync::mpsc::{Receiver, Sender};
 
-use crate::mixer::Mixer;
+use crate::common::{ChannelCount, SampleRate};
+use crate::decoder::Chapter;
+use crate::mixer::send::{self, AuxSends, SendList};
+use crate::mixer::{AuxSend, Bus, Mixer, MixerClock};
 use crate::source::SeekError;
 use crate::Float;
-use crate::{queue, source::Done, Source};
+use crate::{queue, source::Done, Sample, Source};
 
 /// Handle to a device that outputs sounds.
 ///
@@ -65,6 +68,7 @@ struct Controls {
     to_clear: Mutex<u32>,
     seek: Mutex<Option<SeekOrder>>,
     position: Mutex<Duration>,
+    sends: SendList,
 }
 
 impl Player {
@@ -92,6 +96,7 @@ impl Player {
                 to_clear: Mutex::new(0),
                 seek: Mutex::new(None),
                 position: Mutex::new(Duration::ZERO),
+                sends: SendList::default(),
             }),
             sound_count: Arc::new(AtomicUsize::new(0)),
             detached: false,
@@ -102,6 +107,48 @@ impl Player {
     /// Appends a sound to the queue of sounds to play.
     #[inline]
     pub fn append<S>(&self, source: S)
+    where
+        S: Source + Send + 'static,
+        f32: FromSample<S::Item>,
+    {
+        self.append_scheduled(source, None);
+    }
+
+    /// Appends a sound that starts exactly when `clock` reaches
+    /// `start_frame`. Until then the player outputs silence.
+    ///
+    /// Use the clock of the mixer this player is connected to, see
+    /// [`Mixer::clock`]. Start times are sample accurate as long as the
+    /// appended source has the mixer's sample rate and channel count.
+    /// Otherwise the start can be off by the resampler's latency.
+    ///
+    /// Append well ahead of the start time: the source only gets queued once
+    /// any sources before it have finished playing.
+    ///
+    /// # Example
+    /// ```
+    /// use rodio::{mixer, Player};
+    /// use rodio::source::{SineWave, Source};
+    /// use rodio::math::nz;
+    /// use std::time::Duration;
+    ///
+    /// let (mixer, _output) = mixer::mixer(nz!(2), nz!(48_000));
+    /// let player = Player::connect_new(&mixer);
+    /// let clock = mixer.clock();
+    ///
+    /// let beat = clock.frames() + clock.duration_to_frames(Duration::from_secs(1));
+    /// let note = SineWave::new(440.0).take_duration(Duration::from_millis(50));
+    /// player.schedule_at(note, &clock, beat);
+    /// ```
+    pub fn schedule_at<S>(&self, source: S, clock: &MixerClock, start_frame: u64)
+    where
+        S: Source + Send + 'static,
+        f32: FromSample<S::Item>,
+    {
+        self.append_scheduled(source, Some((clock.clone(), start_frame)));
+    }
+
+    fn append_scheduled<S>(&self, source: S, start_at: Option<(MixerClock, u64)>)
     where
         S: Source + Send + 'static,
         f32: FromSample<S::Item>,
@@ -122,7 +169,8 @@ impl Player {
             .speed(1.0)
             // Must be placed before pausable but after speed & delay
             .track_position()
-            .pausable(false)
+            .pausable(false);
+        let source = AuxSends::new(source)
             .amplify(1.0)
             .skippable()
             .stoppable()
@@ -140,14 +188,19 @@ impl Player {
                         *controls.position.lock().unwrap() = Duration::ZERO;
                     } else {
                         *controls.position.lock().unwrap() =
-                            src.inner().inner().inner().inner().get_pos();
+                            src.inner().inner().inner().inner().inner().get_pos();
                     }
                 }
                 let amp = src.inner_mut().inner_mut();
-                amp.set_factor(*controls.volume.lock().unwrap());
-                amp.inner_mut()
+                let volume = *controls.volume.lock().unwrap();
+                amp.set_factor(volume);
+                let sends = amp.inner_mut();
+                sends.update(&controls.sends, volume);
+                sends
+                    .inner_mut()
                     .set_paused(controls.pause.load(Ordering::SeqCst));
-                amp.inner_mut()
+                sends
+                    .inner_mut()
                     .inner_mut()
                     .inner_mut()
                     .set_factor(*controls.speed.lock().unwrap());
@@ -158,7 +211,14 @@ impl Player {
             });
         self.sound_count.fetch_add(1, Ordering::Relaxed);
         let source = Done::new(source, self.sound_count.clone());
-        *self.sleep_until_end.lock().unwrap() = Some(self.queue_tx.append_with_signal(source));
+        let signal = match start_at {
+            Some((clock, start_frame)) => {
+                self.queue_tx
+                    .append_with_signal(WaitForClock::new(source, clock, start_frame))
+            }
+            None => self.queue_tx.append_with_signal(source),
+        };
+        *self.sleep_until_end.lock().unwrap() = Some(signal);
     }
 
     /// Gets the volume of the sound.
@@ -179,6 +239,29 @@ impl Player {
         *self.controls.volume.lock().unwrap() = value;
     }
 
+    /// Sends a copy of the sound to `bus`, multiplied by `level`. See
+    /// [`AuxSend`].
+    ///
+    /// The send applies to every source played by this player, current and
+    /// future, until it is removed.
+    ///
+    /// # Example
+    /// ```
+    /// use rodio::{mixer, Player};
+    /// use rodio::math::nz;
+    /// use rodio::source::Source;
+    ///
+    /// let (mixer, _output) = mixer::mixer(nz!(2), nz!(48_000));
+    /// let room = mixer.bus_with_effects("room", |input| input.low_pass(2_000));
+    /// let player = Player::connect_new(&mixer);
+    /// let send = player.send_to(&room, 0.3);
+    /// send.set_pre_fader(true);
+    /// ```
+    #[inline]
+    pub fn send_to(&self, bus: &Bus, level: Float) -> AuxSend {
+        send::connect(&self.controls.sends, bus, level)
+    }
+
     /// Gets the speed of the sound.
     ///
     /// See [`Player::set_speed`] for details on what *speed* means.
@@ -256,6 +339,15 @@ impl Player {
         }
     }
 
+    /// Seeks to the start of a chapter of the file that is playing, see
+    /// [`Decoder::chapters`](crate::Decoder::chapters).
+    ///
+    /// # Errors
+    /// The same as [`Player::try_seek`].
+    pub fn seek_to_chapter(&self, chapter: &Chapter) -> Result<(), SeekError> {
+        self.try_seek(chapter.start())
+    }
+
     /// Pauses playback of this player.
     ///
     /// No effect if already paused.
@@ -342,6 +434,172 @@ impl Player {
     }
 }
 
+/// Outputs silence until a mixer clock reaches a frame, then plays the inner
+/// source. Used by [`Player::schedule_at`].
+struct WaitForClock<I> {
+    input: I,
+    clock: MixerClock,
+    start_frame: u64,
+    started: bool,
+    samples_waited: u64,
+    // The span of silence being played, its length is fixed when it starts.
+    // The silence can end within it when the clock runs ahead, which is fine
+    // as it has the format of the input.
+    span_len: u64,
+    span_end: u64,
+    // The render position of the clock the last time it was read and the
+    // silence `fill_buffer` wrote since, which counts as played as well.
+    clock_seen: u64,
+    filled_since_clock_seen: u64,
+}
+
+impl<I> WaitForClock<I>
+where
+    I: Source,
+{
+    fn new(input: I, clock: MixerClock, start_frame: u64) -> Self {
+        WaitForClock {
+            input,
+            clock_seen: clock.render_frames(),
+            clock,
+            start_frame,
+            started: false,
+            samples_waited: 0,
+            span_len: 0,
+            span_end: 0,
+            filled_since_clock_seen: 0,
+        }
+    }
+
+    /// Notes where the clock is, see `clock_seen`.
+    fn read_clock(&mut self) {
+        let frames = self.clock.render_frames();
+        if frames != self.clock_seen {
+            self.clock_seen = frames;
+            self.filled_since_clock_seen = 0;
+        }
+    }
+
+    /// Number of samples of silence left. Only ends on a frame boundary to
+    /// keep the channels in order.
+    fn silence_left(&self) -> u64 {
+        let frames = self.clock.render_frames();
+        let waited = if frames == self.clock_seen {
+            self.filled_since_clock_seen
+        } else {
+            0
+        };
+
+        let channels = self.input.channels().get() as u64;
+        let frames_left = self.start_frame.saturating_sub(frames)
+            * self.input.sample_rate().get() as u64
+            / self.clock.sample_rate().get() as u64;
+        let left = (frames_left * channels).saturating_sub(waited);
+        let partial = (self.samples_waited + left) % channels;
+        if partial > 0 {
+            left + channels - partial
+        } else {
+            left
+        }
+    }
+
+    /// Starts a new span once the last one is over, given the silence left.
+    fn update_span(&mut self, silence_left: u64) {
+        if self.samples_waited >= self.span_end {
+            self.span_len = silence_left;
+            self.span_end = self.samples_waited + silence_left;
+        }
+    }
+}
+
+impl<I> Iterator for WaitForClock<I>
+where
+    I: Source,
+{
+    type Item = Sample;
+
+    #[inline]
+    fn next(&mut self) -> Option<Sample> {
+        if !self.started {
+            self.read_clock();
+            let left = self.silence_left();
+            if left > 0 {
+                self.update_span(left);
+                self.samples_waited += 1;
+                return Some(0.0);
+            }
+            self.started = true;
+        }
+        self.input.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (min, _) = self.input.size_hint();
+        (min, None)
+    }
+}
+
+impl<I> Source for WaitForClock<I>
+where
+    I: Source,
+{
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        if self.started {
+            return self.input.current_span_len();
+        }
+        if self.samples_waited < self.span_end {
+            return Some(self.span_len as usize);
+        }
+        // The silence up to the start frame, in the input's format.
+        match self.silence_left() {
+            0 => self.input.current_span_len(),
+            next_span => Some(next_span as usize),
+        }
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+
+    #[inline]
+    fn fill_buffer(&mut self, buffer: &mut [Sample]) -> usize {
+        let mut silence = 0;
+        if !self.started {
+            self.read_clock();
+            let left = self.silence_left();
+            self.update_span(left);
+            silence = left.min(buffer.len() as u64) as usize;
+            buffer[..silence].fill(0.0);
+            self.samples_waited += silence as u64;
+            self.filled_since_clock_seen += silence as u64;
+            if silence as u64 == left {
+                self.started = true;
+            } else {
+                return silence;
+            }
+        }
+        silence + self.input.fill_buffer(&mut buffer[silence..])
+    }
+
+    #[inline]
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)
+    }
+}
+
 impl Drop for Player {
     #[inline]
     fn drop(&mut self) {
@@ -442,4 +700,90 @@ mod tests {
             assert_eq!(queue_rx.next(), src.next());
         }
     }
+
+    #[test]
+    fn test_schedule_at() {
+        let (mixer, mut mixer_out) = crate::mixer::mixer(nz!(1), nz!(1));
+        let player = Player::connect_new(&mixer);
+        let clock = mixer.clock();
+
+        player.schedule_at(
+            SamplesBuffer::new(nz!(1), nz!(1), vec![10.0, -10.0]),
+            &clock,
+            3,
+        );
+
+        let out: Vec<_> = mixer_out.by_ref().take(5).collect();
+        assert_eq!(out, vec![0.0, 0.0, 0.0, 10.0, -10.0]);
+    }
+
+    #[test]
+    fn schedule_at_starts_within_a_block() {
+        let play = |block_len: usize| {
+            let (mixer, mut mixer_out) = crate::mixer::mixer(nz!(1), nz!(48000));
+            let player = Player::connect_new(&mixer);
+            player.schedule_at(
+                SamplesBuffer::new(nz!(1), nz!(48000), vec![1.0; 20]),
+                &mixer.clock(),
+                100,
+            );
+            let mut out = vec![0.0; 1024];
+            for block in out.chunks_mut(block_len) {
+                assert_eq!(mixer_out.fill_buffer(block), block.len());
+            }
+            out
+        };
+
+        for block_len in [1, 64, 512] {
+            let out = play(block_len);
+            let start = out.iter().position(|&sample| sample != 0.0);
+            assert_eq!(start, Some(100), "block_len={block_len}");
+            assert_eq!(out[100..120], [1.0; 20]);
+        }
+    }
+
+    #[test]
+    fn schedule_at_starts_within_a_block_after_silence() {
+        let (mixer, mut mixer_out) = crate::mixer::mixer(nz!(1), nz!(48000));
+        let player = Player::connect_new(&mixer);
+        // The player starts playing 512 samples of silence while it is empty.
+        mixer_out.fill_buffer(&mut [0.0; 64]);
+        player.schedule_at(
+            SamplesBuffer::new(nz!(1), nz!(48000), vec![1.0; 20]),
+            &mixer.clock(),
+            520,
+        );
+
+        let mut out = vec![0.0; 1024];
+        for block in out.chunks_mut(512) {
+            assert_eq!(mixer_out.fill_buffer(block), block.len());
+        }
+        let start = out.iter().position(|&sample| sample != 0.0);
+        assert_eq!(start, Some(520 - 64));
+    }
+
+    #[test]
+    fn waiting_span_is_the_silence() {
+        let (mixer, mut mixer_out) = crate::mixer::mixer(nz!(1), nz!(48000));
+        mixer.set_keep_alive_if_empty(true);
+        let mut wait = super::WaitForClock::new(
+            SamplesBuffer::new(nz!(1), nz!(48000), vec![1.0, 2.0]),
+            mixer.clock(),
+            3,
+        );
+        assert_eq!(wait.current_span_len(), Some(3));
+        mixer_out.next();
+        assert_eq!(wait.current_span_len(), Some(2));
+        assert_eq!(wait.fill_buffer(&mut [1.0]), 1);
+        // The span keeps the length it started with while it plays.
+        assert_eq!(wait.current_span_len(), Some(2));
+        mixer_out.next();
+        assert_eq!(wait.current_span_len(), Some(2));
+        assert_eq!(wait.next(), Some(0.0));
+        // A new span starts if the clock is still short of the start frame.
+        assert_eq!(wait.current_span_len(), Some(1));
+        mixer_out.next();
+        mixer_out.next();
+        assert_eq!(wait.next(), Some(1.0));
+    }
 }
@@ -210,7 +210,8 @@ impl Source for SourcesQueueOutput {
         None
     }
 
-    /// Only seeks within the current source.
+    /// Only seeks within the current source. Between two sources, this is the source
+    /// about to play, which is also the one `channels` and `sample_rate` describe.
     // We can not go back to previous sources. We could implement seek such
     // that it advances the queue if the position is beyond the current song.
     //
@@ -221,8 +222,46 @@ impl Source for SourcesQueueOutput {
     // next few songs.
     #[inline]
     fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        let channels = self.current.channels().get() as usize;
+        let frame_complete = self.samples_consumed_in_span.is_multiple_of(channels)
+            && self.padding_samples_remaining == 0;
+        if frame_complete
+            && self.current.is_exhausted()
+            && !self.input.next_sounds.lock().unwrap().is_empty()
+        {
+            self.samples_consumed_in_span = 0;
+            let _ = self.go_next();
+        }
         self.current.try_seek(pos)
     }
+
+    /// Fills `buffer` from the current source only. Silence padding a frame the
+    /// source left incomplete is written by a call of its own, and the next source
+    /// starts at the call after that.
+    #[inline]
+    fn fill_buffer(&mut self, buffer: &mut [Sample]) -> usize {
+        if buffer.is_empty() {
+            return 0;
+        }
+        loop {
+            if self.padding_samples_remaining > 0 {
+                let len = buffer.len().min(self.padding_samples_remaining);
+                buffer[..len].fill(0.0);
+                self.padding_samples_remaining -= len;
+                return len;
+            }
+
+            let written = self.current.fill_buffer(buffer);
+            if written > 0 {
+                self.samples_consumed_in_span += written;
+                return written;
+            }
+
+            if self.source_ended().is_err() {
+                return 0;
+            }
+        }
+    }
 }
 
 impl Iterator for SourcesQueueOutput {
@@ -243,22 +282,7 @@ impl Iterator for SourcesQueueOutput {
                 return Some(sample);
             }
 
-            // Source ended - check if we ended mid-frame and need padding.
-            let channels = self.current.channels().get() as usize;
-            let incomplete_frame_samples = self.samples_consumed_in_span % channels;
-            if incomplete_frame_samples > 0 {
-                // We're mid-frame - need to pad with silence to complete it.
-                self.padding_samples_remaining = channels - incomplete_frame_samples;
-                // Reset counter now since we're transitioning to a new span.
-                self.samples_consumed_in_span = 0;
-                // Continue loop - next iteration will inject silence.
-                continue;
-            }
-
-            // Reset counter and move to next sound.
-            // In order to avoid inlining this expensive operation, the code is in another function.
-            self.samples_consumed_in_span = 0;
-            if self.go_next().is_err() {
+            if self.source_ended().is_err() {
                 return None;
             }
         }
@@ -271,6 +295,26 @@ impl Iterator for SourcesQueueOutput {
 }
 
 impl SourcesQueueOutput {
+    // Called when `current` returned no more samples. Either starts padding the
+    // last frame with silence or moves on to the next sound. Returns an error if
+    // the queue should stop.
+    #[inline]
+    fn source_ended(&mut self) -> Result<(), ()> {
+        // Check if we ended mid-frame and need padding.
+        let channels = self.current.channels().get() as usize;
+        let incomplete_frame_samples = self.samples_consumed_in_span % channels;
+        // Reset counter now since we're transitioning to a new span.
+        self.samples_consumed_in_span = 0;
+        if incomplete_frame_samples > 0 {
+            // We're mid-frame - need to pad with silence to complete it.
+            self.padding_samples_remaining = channels - incomplete_frame_samples;
+            return Ok(());
+        }
+
+        // In order to avoid inlining this expensive operation, the code is in another function.
+        self.go_next()
+    }
+
     // Called when `current` is empty, and we must jump to the next element.
     // Returns `Ok` if the sound should continue playing, or an error if it should stop.
     //
@@ -424,6 +468,25 @@ mod tests {
         }
     }
 
+    #[test]
+    fn seeks_source_about_to_play() {
+        let (tx, mut rx) = queue::queue(false);
+        tx.append(SamplesBuffer::new(nz!(1), nz!(10), vec![1.0; 10]));
+        tx.append(SamplesBuffer::new(
+            nz!(1),
+            nz!(10),
+            vec![2.0, 3.0, 4.0, 5.0],
+        ));
+
+        rx.try_seek(Duration::from_millis(500)).unwrap();
+        assert_eq!(rx.by_ref().take(5).count(), 5);
+
+        rx.try_seek(Duration::from_millis(200)).unwrap();
+        assert_eq!(rx.next(), Some(4.0));
+        assert_eq!(rx.next(), Some(5.0));
+        assert_eq!(rx.next(), None);
+    }
+
     #[test]
     fn span_ending_mid_frame() {
         let mut test_source1 = TestSource::new(&[0.1, 0.2, 0.1, 0.2, 0.1])
@@ -456,6 +519,58 @@ mod tests {
         assert_eq!(source.next(), test_source2.next());
     }
 
+    #[test]
+    fn fill_buffer_matches_next() {
+        let fill_queue = |tx: &queue::SourcesQueueInput| {
+            tx.append(
+                TestSource::new(&[0.1, 0.2, 0.1, 0.2, 0.1])
+                    .with_channels(nz!(2))
+                    .with_false_span_len(Some(6)),
+            );
+            tx.append(SamplesBuffer::new(nz!(1), nz!(48000), vec![0.5; 7]));
+            tx.append(SamplesBuffer::new(nz!(2), nz!(96000), vec![0.3, 0.4]));
+        };
+
+        let (tx, rx) = queue::queue(false);
+        fill_queue(&tx);
+        let expected: Vec<_> = rx.collect();
+
+        let (tx, mut rx) = queue::queue(false);
+        fill_queue(&tx);
+        let mut blocks = Vec::new();
+        let mut buffer = [0.0; 4];
+        loop {
+            let channels = rx.channels();
+            let written = rx.fill_buffer(&mut buffer);
+            if written == 0 {
+                break;
+            }
+            blocks.push((channels, buffer[..written].to_vec()));
+        }
+
+        let lens: Vec<_> = blocks.iter().map(|(_, block)| block.len()).collect();
+        // Never crosses a source boundary: 5 samples, 1 padding, 7, then 2.
+        assert_eq!(lens, [4, 1, 1, 4, 3, 2]);
+        assert_eq!(blocks[4].0, nz!(1));
+        assert_eq!(blocks[5].0, nz!(2));
+        let mixed: Vec<_> = blocks.into_iter().flat_map(|(_, block)| block).collect();
+        assert_eq!(mixed, expected);
+    }
+
+    #[test]
+    fn fill_buffer_keeps_alive() {
+        let (tx, mut rx) = queue::queue(true);
+        tx.append(SamplesBuffer::new(nz!(1), nz!(48000), vec![1.0; 3]));
+
+        let mut buffer = [1.0; 1024];
+        assert_eq!(rx.fill_buffer(&mut buffer), 3);
+        for _ in 0..10 {
+            let written = rx.fill_buffer(&mut buffer);
+            assert!(written > 0);
+            assert!(buffer[..written].iter().all(|&s| s == 0.0));
+        }
+    }
+
     /// Test helper source that allows setting false span length to simulate
     /// sources that end before their promised span length.
     #[derive(Debug, Clone)]
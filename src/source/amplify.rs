@@ -3,7 +3,7 @@ use std::time::Duration;
 use super::SeekError;
 use crate::{
     common::{ChannelCount, Float, SampleRate},
-    math, Source,
+    math, Sample, Source,
 };
 
 /// Internal function that builds a `Amplify` object.
@@ -96,6 +96,13 @@ where
         self.input.total_duration()
     }
 
+    #[inline]
+    fn fill_buffer(&mut self, buffer: &mut [Sample]) -> usize {
+        let written = self.input.fill_buffer(buffer);
+        math::simd::scale(&mut buffer[..written], self.factor);
+        written
+    }
+
     #[inline]
     fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
         self.input.try_seek(pos)
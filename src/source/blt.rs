@@ -173,6 +173,29 @@ where
         self.input.total_duration()
     }
 
+    #[inline]
+    fn fill_buffer(&mut self, buffer: &mut [Sample]) -> usize {
+        let sample_rate = self.input.sample_rate();
+        let applier = self
+            .applier
+            .take()
+            .unwrap_or_else(|| self.formula.to_applier(sample_rate.get()));
+
+        let written = self.input.fill_buffer(buffer);
+        for sample in &mut buffer[..written] {
+            let result = applier.apply(*sample, self.x_n1, self.x_n2, self.y_n1, self.y_n2);
+            self.y_n2 = self.y_n1;
+            self.x_n2 = self.x_n1;
+            self.y_n1 = result;
+            self.x_n1 = *sample;
+            *sample = result;
+        }
+
+        // The next span may have another sample rate.
+        self.applier = (self.input.sample_rate() == sample_rate).then_some(applier);
+        written
+    }
+
     #[inline]
     fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
         self.input.try_seek(pos)
@@ -5,7 +5,7 @@ use std::time::Duration;
 
 use super::SeekError;
 use crate::common::{ChannelCount, SampleRate};
-use crate::math::nz;
+use crate::math::{nz, NANOS_PER_SEC};
 use crate::Source;
 
 /// Internal function that builds a `Buffered` object.
@@ -19,12 +19,18 @@ where
 
     Buffered {
         current_span: first_span,
+        span_start: 0,
+        rewind: None,
         position_in_span: 0,
         total_duration,
     }
 }
 
 /// Iterator that at the same time extracts data from the iterator and stores it in a buffer.
+///
+/// Extracted data is kept for as long as the `Buffered` or one of its clones still has
+/// to play it. Seeking forwards is always possible, seeking back only within the current
+/// span unless [`Buffered::with_rewind`] keeps all data.
 pub struct Buffered<I>
 where
     I: Source,
@@ -32,6 +38,13 @@ where
     /// Immutable reference to the next span of data. Cannot be `Span::Input`.
     current_span: Arc<Span<I>>,
 
+    /// Start of `current_span` in the source, in nanoseconds.
+    span_start: u128,
+
+    /// The earliest span to seek back to and its start in nanoseconds, kept by
+    /// [`Buffered::with_rewind`]. Cannot be `Span::Input`.
+    rewind: Option<(Arc<Span<I>>, u128)>,
+
     /// The position in number of samples of this iterator inside `current_span`.
     position_in_span: usize,
 
@@ -128,25 +141,35 @@ impl<I> Buffered<I>
 where
     I: Source,
 {
+    /// Keeps all data from the current position on for as long as this source or one of
+    /// its clones lives, so it can seek back to any point after it. Call this before
+    /// playing the source to be able to seek back to its start.
+    ///
+    /// # Example
+    /// ```
+    /// use rodio::source::{SineWave, Source};
+    /// use std::time::Duration;
+    ///
+    /// let mut source = SineWave::new(440.0)
+    ///     .take_duration(Duration::from_secs(2))
+    ///     .buffered()
+    ///     .with_rewind();
+    /// source.try_seek(Duration::from_secs(1)).unwrap();
+    /// source.try_seek(Duration::ZERO).unwrap();
+    /// ```
+    pub fn with_rewind(mut self) -> Self {
+        self.rewind = Some((self.current_span.clone(), self.span_start));
+        self
+    }
+
     /// Advances to the next span.
     fn next_span(&mut self) {
-        let next_span = {
-            let mut next_span_ptr = match &*self.current_span {
-                Span::Data(SpanData { next, .. }) => next.lock().unwrap(),
-                _ => unreachable!(),
-            };
-
-            let next_span = match &**next_span_ptr {
-                Span::Data(_) => next_span_ptr.clone(),
-                Span::End => next_span_ptr.clone(),
-                Span::Input(input) => {
-                    let input = input.lock().unwrap().take().unwrap();
-                    extract(input)
-                }
-            };
-
-            *next_span_ptr = next_span.clone();
-            next_span
+        let next_span = match &*self.current_span {
+            Span::Data(data) => {
+                self.span_start += data.nanos();
+                data.next_span()
+            }
+            _ => unreachable!(),
         };
 
         self.current_span = next_span;
@@ -154,6 +177,34 @@ where
     }
 }
 
+impl<I> SpanData<I>
+where
+    I: Source,
+{
+    /// Returns the span after this one, extracting it from the input if needed.
+    fn next_span(&self) -> Arc<Span<I>> {
+        let mut next_span_ptr = self.next.lock().unwrap();
+
+        let next_span = match &**next_span_ptr {
+            Span::Data(_) => next_span_ptr.clone(),
+            Span::End => next_span_ptr.clone(),
+            Span::Input(input) => {
+                let input = input.lock().unwrap().take().unwrap();
+                extract(input)
+            }
+        };
+
+        *next_span_ptr = next_span.clone();
+        next_span
+    }
+
+    /// Length of this span in nanoseconds, counting only whole frames.
+    fn nanos(&self) -> u128 {
+        let frames = (self.data.len() / self.channels.get() as usize) as u128;
+        frames * NANOS_PER_SEC as u128 / u128::from(self.rate.get())
+    }
+}
+
 impl<I> Iterator for Buffered<I>
 where
     I: Source,
@@ -234,13 +285,47 @@ where
         self.total_duration
     }
 
-    /// Can not support seek, in the end state we lose the underlying source
-    /// which makes seeking back impossible.
-    #[inline]
-    fn try_seek(&mut self, _: Duration) -> Result<(), SeekError> {
-        Err(SeekError::NotSupported {
-            underlying_source: std::any::type_name::<Self>(),
-        })
+    /// Seeks within the buffered data. Seeking past what has been extracted so far
+    /// extracts the spans up to `pos` from the input. Seeking back before the current
+    /// span is only supported after [`Buffered::with_rewind`].
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        // Keep the channel of the next sample, so the channel order stays intact.
+        let channel = match &*self.current_span {
+            Span::Data(data) => self.position_in_span % data.channels.get() as usize,
+            _ => 0,
+        };
+
+        let target = pos.as_nanos();
+        let rewind = self.rewind.as_ref().filter(|(_, start)| target >= *start);
+        let (mut span, mut start) = if target >= self.span_start {
+            (self.current_span.clone(), self.span_start)
+        } else if let Some((span, start)) = rewind {
+            (span.clone(), *start)
+        } else {
+            return Err(SeekError::NotSupported {
+                underlying_source: std::any::type_name::<Self>(),
+            });
+        };
+        while let Span::Data(data) = &*span {
+            let end = start + data.nanos();
+            if target < end {
+                let rate = u128::from(data.rate.get());
+                let channels = data.channels.get() as usize;
+                let frame = ((target - start) * rate / NANOS_PER_SEC as u128) as usize;
+                self.position_in_span = frame * channels + channel % channels;
+                self.current_span = span;
+                self.span_start = start;
+                return Ok(());
+            }
+            start = end;
+            span = data.next_span();
+        }
+
+        // Saturate at the end of the source.
+        self.current_span = span;
+        self.span_start = start;
+        self.position_in_span = 0;
+        Ok(())
     }
 }
 
@@ -252,8 +337,94 @@ where
     fn clone(&self) -> Buffered<I> {
         Buffered {
             current_span: self.current_span.clone(),
+            span_start: self.span_start,
+            rewind: self.rewind.clone(),
             position_in_span: self.position_in_span,
             total_duration: self.total_duration,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use super::Buffered;
+    use crate::buffer::SamplesBuffer;
+    use crate::math::nz;
+    use crate::source::SeekError;
+    use crate::{Sample, Source};
+
+    /// Forty seconds of stereo, which is split over three spans.
+    fn source() -> Buffered<SamplesBuffer> {
+        SamplesBuffer::ramp(nz!(2), nz!(1000), 40_000).buffered()
+    }
+
+    #[test]
+    fn seeks_forward_into_unextracted_data() {
+        let mut source = source();
+        source.try_seek(Duration::from_secs(35)).unwrap();
+        assert_eq!(source.next(), Some(70_000.0));
+        assert_eq!(source.count(), 9_999);
+    }
+
+    #[test]
+    fn seeks_back_within_the_current_span() {
+        let mut source = source();
+        source.try_seek(Duration::from_secs(20)).unwrap();
+        source.try_seek(Duration::from_secs(17)).unwrap();
+        assert_eq!(source.next(), Some(34_000.0));
+
+        let error = source.try_seek(Duration::from_secs(10)).unwrap_err();
+        assert!(matches!(error, SeekError::NotSupported { .. }));
+        assert_eq!(source.next(), Some(34_001.0));
+    }
+
+    #[test]
+    fn seeks_back() {
+        let mut source = source().with_rewind();
+        assert_eq!(source.by_ref().count(), 80_000);
+
+        source.try_seek(Duration::from_secs(10)).unwrap();
+        assert_eq!(source.next(), Some(20_000.0));
+
+        source.try_seek(Duration::from_secs(50)).unwrap();
+        assert_eq!(source.next(), None);
+    }
+
+    #[test]
+    fn keeps_channel_order() {
+        let mut source = source().with_rewind();
+        source.try_seek(Duration::from_secs(20)).unwrap();
+        assert_eq!(source.next(), Some(40_000.0));
+        source.try_seek(Duration::from_secs(5)).unwrap();
+        assert_eq!(source.next(), Some(10_001.0));
+    }
+
+    #[test]
+    fn clones_seek_independently() {
+        let mut source = source();
+        let mut clone = source.clone();
+        source.try_seek(Duration::from_secs(30)).unwrap();
+        assert_eq!(clone.next(), Some(0.0));
+        assert_eq!(source.next(), Some(60_000.0));
+    }
+
+    #[test]
+    fn frees_played_spans() {
+        let mut source = source();
+        let first_span = Arc::downgrade(&source.current_span);
+        let mut clone = source.clone();
+
+        source.try_seek(Duration::from_secs(20)).unwrap();
+        assert!(first_span.upgrade().is_some());
+        clone.try_seek(Duration::from_secs(20)).unwrap();
+        assert!(first_span.upgrade().is_none());
+
+        let mut source = self::source().with_rewind();
+        let first_span = Arc::downgrade(&source.current_span);
+        source.try_seek(Duration::from_secs(20)).unwrap();
+        assert!(first_span.upgrade().is_some());
+    }
+}
@@ -4,7 +4,7 @@ use std::time::Duration;
 
 use super::SeekError;
 use crate::common::{ChannelCount, SampleRate};
-use crate::Source;
+use crate::{Sample, Source};
 
 /// When the inner source is empty this decrements a `AtomicUsize`.
 #[derive(Debug, Clone)]
@@ -90,6 +90,16 @@ where
         self.input.total_duration()
     }
 
+    #[inline]
+    fn fill_buffer(&mut self, buffer: &mut [Sample]) -> usize {
+        let written = self.input.fill_buffer(buffer);
+        if !self.signal_sent && written == 0 && !buffer.is_empty() {
+            self.signal.fetch_sub(1, Ordering::Relaxed);
+            self.signal_sent = true;
+        }
+        written
+    }
+
     #[inline]
     fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
         self.input.try_seek(pos)
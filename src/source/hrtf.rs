@@ -0,0 +1,694 @@
+//! Head-related impulse responses (HRIRs), used by [`Spatial`](super::Spatial)
+//! to render sources binaurally for headphones.
+//!
+//! A set holds impulse responses measured (or modelled) for many directions
+//! around a head. To play a source from some direction the responses of the
+//! nearest measured directions are blended: their onset delays, which carry
+//! the interaural time difference, are blended separately from the rest of
+//! the responses so the blend does not smear.
+
+use std::sync::{Arc, OnceLock};
+
+use crate::common::{assert_error_traits, SampleRate};
+use crate::math::{nz, PI};
+use crate::{Float, Sample};
+
+/// A set of head-related impulse responses, one pair (left and right ear) per
+/// measured direction.
+///
+/// Directions are relative to the listener's head. The set can be cloned
+/// cheaply and shared between sources.
+#[derive(Clone)]
+pub struct HrirSet(Arc<Inner>);
+
+struct Inner {
+    sample_rate: SampleRate,
+    // Length of every aligned response.
+    len: usize,
+    // Longest onset delay, in samples at `sample_rate`.
+    max_delay: Float,
+    measurements: Arc<[Measurement]>,
+}
+
+struct Measurement {
+    // Unit vector: x to the right, y up, z to the front of the head.
+    direction: [Float; 3],
+    // The responses with their onset removed.
+    left: Vec<Float>,
+    right: Vec<Float>,
+    // The removed onsets, in samples.
+    left_delay: Float,
+    right_delay: Float,
+}
+
+/// Why [`HrirSet::from_measurements`] could not build a set.
+#[derive(Debug, thiserror::Error, Clone, Copy, PartialEq, Eq)]
+pub enum HrirError {
+    /// There are no measurements, or the impulse responses are empty.
+    #[error("The HRIR set has no measurements")]
+    Empty,
+    /// The impulse response data does not hold two responses of the given
+    /// length for every position.
+    #[error("Expected {expected} samples of impulse response data, found {found}")]
+    DataLength {
+        /// Number of samples needed.
+        expected: usize,
+        /// Number of samples passed.
+        found: usize,
+    },
+}
+assert_error_traits! {HrirError}
+
+impl HrirSet {
+    /// A built-in set computed from a spherical head model with simple pinna
+    /// reflections (Brown and Duda). It gives interaural time and level
+    /// differences and rough elevation and front/back cues.
+    ///
+    /// Measured sets sound more convincing, see
+    /// [`HrirSet::from_measurements`].
+    pub fn spherical_head() -> HrirSet {
+        static SET: OnceLock<HrirSet> = OnceLock::new();
+        SET.get_or_init(spherical_head::build).clone()
+    }
+
+    /// Builds a set from measured impulse responses, laid out like the
+    /// `SimpleFreeFieldHRIR` convention of SOFA files.
+    ///
+    /// - `positions` are the `SourcePosition`s: azimuth and elevation in
+    ///   degrees, then distance. Azimuth goes counterclockwise from the front,
+    ///   so 90 degrees is to the left. The distance is ignored.
+    /// - `data` is `Data.IR`: for every position, the response of the left
+    ///   ear then of the right ear, each `len` samples long.
+    /// - `sample_rate` is `Data.SamplingRate`.
+    ///
+    /// Rodio does not read SOFA files: they are HDF5 files, read the arrays
+    /// above with an HDF5 library and pass them here.
+    ///
+    /// # Errors
+    /// [`HrirError::Empty`] if there is no position or `len` is zero, and
+    /// [`HrirError::DataLength`] if `data` is not `positions.len() * 2 * len`
+    /// samples long.
+    pub fn from_measurements(
+        sample_rate: SampleRate,
+        positions: &[[f32; 3]],
+        len: usize,
+        data: &[Sample],
+    ) -> Result<HrirSet, HrirError> {
+        if positions.is_empty() || len == 0 {
+            return Err(HrirError::Empty);
+        }
+        let expected = positions.len() * 2 * len;
+        if data.len() != expected {
+            return Err(HrirError::DataLength {
+                expected,
+                found: data.len(),
+            });
+        }
+
+        let measurements = positions
+            .iter()
+            .zip(data.chunks_exact(2 * len))
+            .map(|(position, responses)| {
+                let azimuth = Float::from(position[0]).to_radians();
+                let elevation = Float::from(position[1]).to_radians();
+                let direction = [
+                    -elevation.cos() * azimuth.sin(),
+                    elevation.sin(),
+                    elevation.cos() * azimuth.cos(),
+                ];
+                let (left, left_delay) = align(&responses[..len]);
+                let (right, right_delay) = align(&responses[len..]);
+                Measurement {
+                    direction,
+                    left,
+                    right,
+                    left_delay,
+                    right_delay,
+                }
+            })
+            .collect();
+        Ok(HrirSet::new(sample_rate, len, measurements))
+    }
+
+    fn new(sample_rate: SampleRate, len: usize, measurements: Vec<Measurement>) -> HrirSet {
+        let max_delay = measurements
+            .iter()
+            .map(|measurement| measurement.left_delay.max(measurement.right_delay))
+            .fold(0.0, Float::max);
+        HrirSet(Arc::new(Inner {
+            sample_rate,
+            len,
+            max_delay,
+            measurements: measurements.into(),
+        }))
+    }
+
+    /// The sample rate the impulse responses were recorded at. They are
+    /// resampled to the rate of the source they are used on.
+    #[inline]
+    pub fn sample_rate(&self) -> SampleRate {
+        self.0.sample_rate
+    }
+
+    /// Number of measured directions.
+    #[inline]
+    pub fn directions(&self) -> usize {
+        self.0.measurements.len()
+    }
+
+    #[inline]
+    pub(crate) fn ptr_eq(&self, other: &HrirSet) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+
+    /// The measurements resampled to `sample_rate`, ready to build filters
+    /// from.
+    fn responses(&self, sample_rate: SampleRate) -> Responses {
+        let inner = &self.0;
+        let ratio = sample_rate.get() as Float / inner.sample_rate.get() as Float;
+        let measurements = if ratio == 1.0 {
+            inner.measurements.clone()
+        } else {
+            inner
+                .measurements
+                .iter()
+                .map(|measurement| Measurement {
+                    direction: measurement.direction,
+                    left: resample(&measurement.left, ratio),
+                    right: resample(&measurement.right, ratio),
+                    left_delay: measurement.left_delay * ratio,
+                    right_delay: measurement.right_delay * ratio,
+                })
+                .collect()
+        };
+        let len = measurements[0].left.len();
+        Responses {
+            measurements,
+            len,
+            filter_len: len + (inner.max_delay * ratio).ceil() as usize + 1,
+        }
+    }
+}
+
+/// The measurements of a set at the sample rate of a source. Filters are
+/// built from them without allocating, as the source moves.
+#[derive(Clone)]
+struct Responses {
+    measurements: Arc<[Measurement]>,
+    // Length of every response.
+    len: usize,
+    // Length of every filter: a response plus the longest onset delay.
+    filter_len: usize,
+}
+
+impl Responses {
+    /// Builds the filter for a source in `direction` (a unit vector, x to the
+    /// right, y up, z to the front).
+    fn filter(&self, direction: [Float; 3]) -> Filter {
+        let mut filter = Filter::new(self.filter_len);
+        self.filter_into(direction, &mut filter, &mut Filter::new(self.len));
+        filter
+    }
+
+    /// Like [`Responses::filter`], writes the filter into `filter` and uses
+    /// `blend` as scratch space.
+    fn filter_into(&self, direction: [Float; 3], filter: &mut Filter, blend: &mut Filter) {
+        // The three closest measurements, weighted by how close they are.
+        let mut nearest: [(Float, usize); 3] = [(Float::NEG_INFINITY, 0); 3];
+        for (idx, measurement) in self.measurements.iter().enumerate() {
+            let cos = dot(measurement.direction, direction);
+            if let Some(slot) = nearest.iter().position(|(best, _)| cos > *best) {
+                nearest[slot..].rotate_right(1);
+                nearest[slot] = (cos, idx);
+            }
+        }
+        let mut weights = [0.0; 3];
+        for ((cos, _), weight) in nearest.iter().zip(&mut weights) {
+            if cos.is_finite() {
+                *weight = 1.0 / cos.clamp(-1.0, 1.0).acos().max(1e-4);
+            }
+        }
+        let total: Float = weights.iter().sum();
+
+        blend.left.fill(0.0);
+        blend.right.fill(0.0);
+        let (mut left_delay, mut right_delay) = (0.0, 0.0);
+        for ((_, idx), weight) in nearest.iter().zip(weights) {
+            let weight = weight / total;
+            if weight == 0.0 {
+                continue;
+            }
+            let measurement = &self.measurements[*idx];
+            for (out, tap) in blend.left.iter_mut().zip(&measurement.left) {
+                *out += tap * weight;
+            }
+            for (out, tap) in blend.right.iter_mut().zip(&measurement.right) {
+                *out += tap * weight;
+            }
+            left_delay += measurement.left_delay * weight;
+            right_delay += measurement.right_delay * weight;
+        }
+
+        delay_reversed(&blend.left, left_delay, &mut filter.left);
+        delay_reversed(&blend.right, right_delay, &mut filter.right);
+    }
+}
+
+impl std::fmt::Debug for HrirSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HrirSet")
+            .field("sample_rate", &self.0.sample_rate)
+            .field("directions", &self.directions())
+            .field("len", &self.0.len)
+            .finish()
+    }
+}
+
+#[inline]
+fn dot(a: [Float; 3], b: [Float; 3]) -> Float {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+/// Splits a response into its onset delay and the response from the onset
+/// on, padded back to the same length.
+fn align(response: &[Sample]) -> (Vec<Float>, Float) {
+    let peak = response
+        .iter()
+        .fold(0.0, |peak: Float, sample| peak.max(sample.abs()));
+    // A couple of samples are kept before the onset, responses rise gradually.
+    let onset = response
+        .iter()
+        .position(|sample| sample.abs() >= peak * 0.1)
+        .unwrap_or(0)
+        .saturating_sub(2);
+    let mut aligned = response[onset..].to_vec();
+    aligned.resize(response.len(), 0.0);
+    (aligned, onset as Float)
+}
+
+/// Resamples a short response with linear interpolation, keeping its gain.
+fn resample(response: &[Float], ratio: Float) -> Vec<Float> {
+    let len = (response.len() as Float * ratio).ceil() as usize;
+    (0..len)
+        .map(|idx| fractional_tap(response, idx as Float / ratio) / ratio)
+        .collect()
+}
+
+/// Writes the response delayed by `delay` samples into `out`, reversed so
+/// that it lines up with the history of a [`Binaural`] renderer.
+fn delay_reversed(response: &[Float], delay: Float, out: &mut [Float]) {
+    let len = out.len();
+    for (idx, out) in (0..len).rev().zip(out) {
+        *out = fractional_tap(response, idx as Float - delay);
+    }
+}
+
+/// Value of `response` at a fractional position, zero outside of it.
+#[inline]
+fn fractional_tap(response: &[Float], position: Float) -> Float {
+    if position <= -1.0 {
+        return 0.0;
+    }
+    let floor = position.floor();
+    let fraction = position - floor;
+    let tap = |idx: Float| {
+        if idx < 0.0 {
+            0.0
+        } else {
+            response.get(idx as usize).copied().unwrap_or(0.0)
+        }
+    };
+    tap(floor) * (1.0 - fraction) + tap(floor + 1.0) * fraction
+}
+
+/// The filters for one direction, reversed.
+#[derive(Clone)]
+struct Filter {
+    left: Vec<Float>,
+    right: Vec<Float>,
+}
+
+impl Filter {
+    fn new(len: usize) -> Self {
+        Filter {
+            left: vec![0.0; len],
+            right: vec![0.0; len],
+        }
+    }
+}
+
+/// Frames over which the filters of a new direction fade in.
+const CROSSFADE_FRAMES: usize = 128;
+
+/// Renders a mono signal for both ears.
+#[derive(Clone)]
+pub(crate) struct Binaural {
+    hrirs: HrirSet,
+    responses: Responses,
+    direction: [Float; 3],
+    sample_rate: SampleRate,
+    filter: Filter,
+    // The filter being faded out and how many frames are left to do so.
+    previous: Filter,
+    fade_left: usize,
+    // Where to move once the current crossfade is done.
+    next_direction: Option<[Float; 3]>,
+    // Scratch space to build filters in.
+    blend: Filter,
+    // The last `len` input samples, stored twice so they can always be read
+    // as one slice.
+    history: Vec<Sample>,
+    pos: usize,
+}
+
+impl Binaural {
+    pub(crate) fn new(hrirs: HrirSet, direction: [Float; 3], sample_rate: SampleRate) -> Self {
+        let responses = hrirs.responses(sample_rate);
+        let filter = responses.filter(direction);
+        let len = filter.left.len();
+        Binaural {
+            hrirs,
+            direction,
+            sample_rate,
+            previous: filter.clone(),
+            filter,
+            fade_left: 0,
+            next_direction: None,
+            blend: Filter::new(responses.len),
+            responses,
+            history: vec![0.0; 2 * len],
+            pos: 0,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn hrirs(&self) -> &HrirSet {
+        &self.hrirs
+    }
+
+    /// Moves the source to `direction`. The new filters fade in over a few
+    /// milliseconds, after the previous move has finished fading in.
+    pub(crate) fn set_direction(&mut self, direction: [Float; 3], sample_rate: SampleRate) {
+        if sample_rate != self.sample_rate {
+            *self = Binaural::new(self.hrirs.clone(), direction, sample_rate);
+            return;
+        }
+        if self.fade_left > 0 {
+            // Replacing the filter being faded out would click.
+            self.next_direction = Some(direction).filter(|next| *next != self.direction);
+        } else {
+            self.crossfade_to(direction);
+        }
+    }
+
+    fn crossfade_to(&mut self, direction: [Float; 3]) {
+        if direction == self.direction {
+            return;
+        }
+        self.direction = direction;
+        std::mem::swap(&mut self.filter, &mut self.previous);
+        self.responses
+            .filter_into(direction, &mut self.filter, &mut self.blend);
+        self.fade_left = CROSSFADE_FRAMES;
+    }
+
+    /// Pushes the next input sample, returns the samples for the left and
+    /// right ear.
+    #[inline]
+    pub(crate) fn process(&mut self, sample: Sample) -> [Sample; 2] {
+        let len = self.filter.left.len();
+        self.history[self.pos] = sample;
+        self.history[self.pos + len] = sample;
+        self.pos = (self.pos + 1) % len;
+        let history = &self.history[self.pos..self.pos + len];
+
+        let output = convolve(&self.filter, history);
+        if self.fade_left == 0 {
+            return output;
+        }
+        let fade = self.fade_left as Float / CROSSFADE_FRAMES as Float;
+        let faded = convolve(&self.previous, history);
+        self.fade_left -= 1;
+        if self.fade_left == 0 {
+            if let Some(direction) = self.next_direction.take() {
+                self.crossfade_to(direction);
+            }
+        }
+        [
+            output[0] * (1.0 - fade) + faded[0] * fade,
+            output[1] * (1.0 - fade) + faded[1] * fade,
+        ]
+    }
+}
+
+#[inline]
+fn convolve(filter: &Filter, history: &[Sample]) -> [Sample; 2] {
+    let dot = |taps: &[Float]| -> Sample {
+        taps.iter()
+            .zip(history)
+            .map(|(tap, sample)| tap * sample)
+            .sum()
+    };
+    [dot(&filter.left), dot(&filter.right)]
+}
+
+mod spherical_head {
+    //! The spherical head model of Brown and Duda, "A structural model for
+    //! binaural sound synthesis" (1998): head shadow and interaural delay
+    //! from a rigid sphere, plus a few pinna reflections.
+
+    use super::*;
+
+    const SAMPLE_RATE: SampleRate = nz!(48000);
+    const LEN: usize = 128;
+    /// Head radius in meters.
+    const RADIUS: Float = 0.0875;
+    const SPEED_OF_SOUND: Float = 343.0;
+    /// Least head shadow gain factor and the angle it occurs at.
+    const ALPHA_MIN: Float = 0.1;
+    const THETA_MIN: Float = 150.0 / 180.0 * PI;
+    /// Pinna reflections: gain, then the delay parameters A, B and D. Delays
+    /// are in samples at 44.1 kHz.
+    const PINNA: [(Float, Float, Float, Float); 5] = [
+        (0.5, 1.0, 2.0, 1.0),
+        (-1.0, 5.0, 4.0, 0.5),
+        (0.5, 5.0, 7.0, 0.5),
+        (-0.25, 5.0, 11.0, 0.5),
+        (0.25, 5.0, 13.0, 0.5),
+    ];
+
+    pub(super) fn build() -> HrirSet {
+        let mut measurements = Vec::new();
+        for elevation in (-45..=75).step_by(15) {
+            for azimuth in (0..360).step_by(15) {
+                measurements.push(measure(azimuth as Float, elevation as Float));
+            }
+        }
+        measurements.push(measure(0.0, 90.0));
+        HrirSet::new(SAMPLE_RATE, LEN, measurements)
+    }
+
+    /// Models the responses for a source at `azimuth` degrees clockwise from
+    /// the front and `elevation` degrees up.
+    fn measure(azimuth: Float, elevation: Float) -> Measurement {
+        let (azimuth, elevation) = (azimuth.to_radians(), elevation.to_radians());
+        let direction = [
+            elevation.cos() * azimuth.sin(),
+            elevation.sin(),
+            elevation.cos() * azimuth.cos(),
+        ];
+        let (left, left_delay) = ear(direction, -1.0);
+        let (right, right_delay) = ear(direction, 1.0);
+        Measurement {
+            direction,
+            left,
+            right,
+            left_delay,
+            right_delay,
+        }
+    }
+
+    /// Response and delay of the ear on `side` (-1 left, 1 right).
+    fn ear(direction: [Float; 3], side: Float) -> (Vec<Float>, Float) {
+        let rate = SAMPLE_RATE.get() as Float;
+        // Angle between the source and the ear.
+        let theta = (direction[0] * side).clamp(-1.0, 1.0).acos();
+
+        // Head shadow: a one-pole, one-zero filter through the bilinear
+        // transform.
+        let alpha =
+            (1.0 + ALPHA_MIN / 2.0) + (1.0 - ALPHA_MIN / 2.0) * (theta / THETA_MIN * PI).cos();
+        let k = rate * RADIUS / SPEED_OF_SOUND;
+        let (b0, b1, a1) = (
+            (1.0 + alpha * k) / (1.0 + k),
+            (1.0 - alpha * k) / (1.0 + k),
+            (1.0 - k) / (1.0 + k),
+        );
+        let mut shadow = vec![0.0; LEN];
+        let (mut x1, mut y1) = (0.0, 0.0);
+        for (idx, out) in shadow.iter_mut().enumerate() {
+            let x = if idx == 0 { 1.0 } else { 0.0 };
+            let y = b0 * x + b1 * x1 - a1 * y1;
+            (x1, y1) = (x, y);
+            *out = y;
+        }
+
+        // Pinna reflections, seen from the side of this ear.
+        let azimuth = (direction[0] * side).atan2(direction[2]);
+        let elevation = direction[1].clamp(-1.0, 1.0).asin();
+        let mut response = shadow.clone();
+        for (gain, a, b, d) in PINNA {
+            let delay = (a * (azimuth / 2.0).cos() * (d * (PI / 2.0 - elevation)).sin() + b) * rate
+                / 44100.0;
+            for (idx, out) in response.iter_mut().enumerate() {
+                *out += gain * fractional_tap(&shadow, idx as Float - delay);
+            }
+        }
+
+        // Woodworth's formula for the extra path around the head.
+        let path = if theta < PI / 2.0 {
+            1.0 - theta.cos()
+        } else {
+            1.0 + theta - PI / 2.0
+        };
+        (response, path * RADIUS / SPEED_OF_SOUND * rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn energy(taps: &[Float]) -> Float {
+        taps.iter().map(|tap| tap * tap).sum()
+    }
+
+    // Index of the first tap of a reversed filter that reaches a tenth of its
+    // peak.
+    fn onset(reversed: &[Float]) -> usize {
+        let peak = reversed
+            .iter()
+            .fold(0.0, |peak: Float, tap| peak.max(tap.abs()));
+        reversed
+            .iter()
+            .rev()
+            .position(|tap| tap.abs() >= peak * 0.1)
+            .unwrap()
+    }
+
+    #[test]
+    fn source_on_the_right_is_louder_and_earlier_on_the_right() {
+        let set = HrirSet::spherical_head();
+        for sample_rate in [nz!(48000), nz!(44100)] {
+            let filter = set.responses(sample_rate).filter([1.0, 0.0, 0.0]);
+            assert_eq!(filter.left.len(), filter.right.len());
+            assert!(energy(&filter.right) > 2.0 * energy(&filter.left));
+            assert!(onset(&filter.right) + 20 < onset(&filter.left));
+        }
+    }
+
+    #[test]
+    fn front_and_back_differ() {
+        let set = HrirSet::spherical_head();
+        let responses = set.responses(nz!(48000));
+        let front = responses.filter([0.5, 0.0, Float::sqrt(0.75)]);
+        let back = responses.filter([0.5, 0.0, -Float::from(0.75_f32.sqrt())]);
+        let difference: Float = front
+            .right
+            .iter()
+            .zip(&back.right)
+            .map(|(a, b)| (a - b).abs())
+            .sum();
+        assert!(difference > 0.1);
+    }
+
+    #[test]
+    fn interpolates_delays() {
+        // Clicks, later on the left the further the source is to the right.
+        let mut data = vec![0.0; 2 * 2 * 32];
+        data[0] = 1.0; // front, left
+        data[32] = 1.0; // front, right
+        data[64 + 20] = 1.0; // right, left
+        data[96] = 1.0; // right, right
+        let set = HrirSet::from_measurements(
+            nz!(48000),
+            &[[0.0, 0.0, 1.0], [-90.0, 0.0, 1.0]],
+            32,
+            &data,
+        )
+        .unwrap();
+        assert_eq!(set.directions(), 2);
+
+        let half = Float::from(0.5_f32.sqrt());
+        let filter = set.responses(nz!(48000)).filter([half, 0.0, half]);
+        assert_eq!(onset(&filter.right), 0);
+        // Halfway between no delay and the 18 samples before the (aligned) click.
+        assert_eq!(onset(&filter.left), 9);
+    }
+
+    #[test]
+    fn rejects_bad_data() {
+        assert_eq!(
+            HrirSet::from_measurements(nz!(48000), &[], 32, &[]).unwrap_err(),
+            HrirError::Empty
+        );
+        assert_eq!(
+            HrirSet::from_measurements(nz!(48000), &[[0.0; 3]], 4, &[0.0; 4]).unwrap_err(),
+            HrirError::DataLength {
+                expected: 8,
+                found: 4
+            }
+        );
+    }
+
+    #[test]
+    fn crossfades_to_new_direction() {
+        // Head shadowing is strongest at high frequencies, so play the highest.
+        let mut sign = 1.0;
+        let mut nyquist = move || {
+            sign = -sign;
+            sign
+        };
+        let mut binaural = Binaural::new(HrirSet::spherical_head(), [1.0, 0.0, 0.0], nz!(48000));
+        let warmup: Vec<_> = (0..1000).map(|_| binaural.process(nyquist())).collect();
+        let [left, right] = warmup[999];
+        assert!(right.abs() > left.abs());
+
+        binaural.set_direction([-1.0, 0.0, 0.0], nz!(48000));
+        let [first_left, first_right] = binaural.process(nyquist());
+        assert!((first_left + left).abs() < 0.1);
+        assert!((first_right + right).abs() < 0.1);
+        let last = (0..CROSSFADE_FRAMES)
+            .map(|_| binaural.process(nyquist()))
+            .last();
+        let [left, right] = last.unwrap();
+        assert!(left.abs() > right.abs());
+    }
+
+    #[test]
+    fn moves_during_crossfade_wait_for_it() {
+        let mut sign = 1.0;
+        let mut nyquist = move || {
+            sign = -sign;
+            sign
+        };
+        let mut binaural = Binaural::new(HrirSet::spherical_head(), [1.0, 0.0, 0.0], nz!(48000));
+        for _ in 0..1000 {
+            binaural.process(nyquist());
+        }
+        binaural.set_direction([-1.0, 0.0, 0.0], nz!(48000));
+        binaural.process(nyquist());
+
+        // The first crossfade goes on as if nothing happened.
+        let mut unmoved = binaural.clone();
+        binaural.set_direction([0.0, 0.0, 1.0], nz!(48000));
+        let input = nyquist();
+        assert_eq!(binaural.process(input), unmoved.process(input));
+
+        let last = (0..2 * CROSSFADE_FRAMES)
+            .map(|_| binaural.process(nyquist()))
+            .last();
+        let [left, right] = last.unwrap();
+        assert!((left - right).abs() < 0.01);
+    }
+}
@@ -27,6 +27,7 @@ pub use self::fadein::FadeIn;
 pub use self::fadeout::FadeOut;
 pub use self::from_factory::{from_factory, FromFactoryIter};
 pub use self::from_iter::{from_iter, FromIter};
+pub use self::hrtf::{HrirError, HrirSet};
 pub use self::limit::{Limit, LimitSettings};
 pub use self::linear_ramp::LinearGainRamp;
 pub use self::mix::Mix;
@@ -34,12 +35,14 @@ pub use self::pausable::Pausable;
 pub use self::periodic::PeriodicAccess;
 pub use self::position::TrackPosition;
 pub use self::repeat::Repeat;
+pub use self::reverse::Reverse;
 pub use self::sawtooth::SawtoothWave;
+pub use self::scrub::{Scrub, ScrubControl};
 pub use self::signal_generator::{Function, GeneratorFunction, SignalGenerator};
 pub use self::sine::SineWave;
 pub use self::skip::SkipDuration;
 pub use self::skippable::Skippable;
-pub use self::spatial::Spatial;
+pub use self::spatial::{DistanceModel, Rolloff, SoundCone, Spatial};
 pub use self::speed::Speed;
 pub use self::square::SquareWave;
 pub use self::stoppable::Stoppable;
@@ -64,14 +67,18 @@ mod fadein;
 mod fadeout;
 mod from_factory;
 mod from_iter;
+mod hrtf;
 mod limit;
 mod linear_ramp;
 mod mix;
 mod pausable;
 mod periodic;
 mod position;
+pub(crate) mod read_ahead;
 mod repeat;
+mod reverse;
 mod sawtooth;
+mod scrub;
 mod signal_generator;
 mod sine;
 mod skip;
@@ -92,6 +99,13 @@ pub mod dither;
 #[cfg_attr(docsrs, doc(cfg(feature = "dither")))]
 pub use self::dither::{Algorithm as DitherAlgorithm, Dither};
 
+#[cfg(feature = "prefetch")]
+#[cfg_attr(docsrs, doc(cfg(feature = "prefetch")))]
+mod prefetch;
+#[cfg(feature = "prefetch")]
+#[cfg_attr(docsrs, doc(cfg(feature = "prefetch")))]
+pub use self::prefetch::{Prefetch, UnderrunBehavior};
+
 #[cfg(feature = "noise")]
 #[cfg_attr(docsrs, doc(cfg(feature = "noise")))]
 pub mod noise;
@@ -204,6 +218,22 @@ pub trait Source: Iterator<Item = Sample> {
     /// `None` indicates at the same time "infinite" or "unknown".
     fn total_duration(&self) -> Option<Duration>;
 
+    /// Writes the next samples of this source into `buffer` and returns how many
+    /// were written.
+    ///
+    /// This is a faster alternative to calling [`Iterator::next`] once per sample.
+    /// All samples written by one call belong to the same span: they have the
+    /// channel count and sample rate reported before the call. Fewer samples than
+    /// requested may be written, for example at the end of a span. Zero is only
+    /// returned once the source has ended or if `buffer` is empty.
+    ///
+    /// The default implementation calls `next` for every sample. Sources and
+    /// filters that can process whole blocks at once override it.
+    #[inline]
+    fn fill_buffer(&mut self, buffer: &mut [Sample]) -> usize {
+        fill_buffer_from_next(self, buffer)
+    }
+
     /// Stores the source in a buffer in addition to returning it. This iterator can be cloned.
     #[inline]
     fn buffered(self) -> Buffered<Self>
@@ -260,6 +290,30 @@ pub trait Source: Iterator<Item = Sample> {
         repeat::repeat(self)
     }
 
+    /// Plays this source backwards, frame by frame. The source must be finite.
+    ///
+    /// Sources that can seek and know their total duration, like most decoders, are read
+    /// backwards one chunk at a time. Other sources are first read completely into
+    /// memory. [`SamplesBuffer::reversed`] and
+    /// [`StaticSamplesBuffer::reversed`](crate::static_buffer::StaticSamplesBuffer::reversed)
+    /// reverse a buffer without copying it.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use std::fs::File;
+    /// use rodio::{Decoder, Source};
+    ///
+    /// let cymbal = Decoder::try_from(File::open("cymbal.wav").unwrap()).unwrap();
+    /// let swell = cymbal.reverse();
+    /// ```
+    #[inline]
+    fn reverse(self) -> Reverse<Self>
+    where
+        Self: Sized,
+    {
+        reverse::reverse(self)
+    }
+
     /// Takes a certain duration of this source and then stops.
     #[inline]
     fn take_duration(self, duration: Duration) -> TakeDuration<Self>
@@ -551,6 +605,23 @@ pub trait Source: Iterator<Item = Sample> {
         periodic::periodic(self, period, access)
     }
 
+    /// Plays the sound with a playhead that can be driven at any rate, including backwards
+    /// and standing still.
+    ///
+    /// The playhead is controlled through the [`ScrubControl`] returned by
+    /// [`Scrub::control`], either with a velocity or with a target position it glides to.
+    /// Samples between frames are interpolated with a cubic curve. Everything the playhead
+    /// reaches is kept in memory.
+    ///
+    /// See [`Scrub`] for details.
+    #[inline]
+    fn scrub(self) -> Scrub<Self>
+    where
+        Self: Sized,
+    {
+        scrub::scrub(self)
+    }
+
     /// Changes the play speed of the sound. Does not adjust the samples, only the playback speed.
     ///
     /// # Note:
@@ -621,6 +692,73 @@ pub trait Source: Iterator<Item = Sample> {
         self.mix(echo)
     }
 
+    /// Decodes this source ahead of playback on a worker thread, keeping up to
+    /// `duration` of samples in a ring buffer.
+    ///
+    /// Use this for sources that are slow to produce samples, such as a decoder reading
+    /// from a slow disk, so they do not cause underruns on the audio thread. What happens
+    /// when the worker falls behind anyway is set with
+    /// [`Prefetch::with_underrun_behavior`].
+    ///
+    /// Creating the prefetch blocks the calling thread until the worker has decoded the
+    /// first samples. [Seeking](Source::try_seek) does not wait for the worker, silence
+    /// plays until it has decoded from the new position.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use std::fs::File;
+    /// use std::time::Duration;
+    /// use rodio::source::UnderrunBehavior;
+    /// use rodio::{Decoder, Source};
+    ///
+    /// let decoder = Decoder::try_from(File::open("music.flac").unwrap()).unwrap();
+    /// let source = decoder
+    ///     .prefetch(Duration::from_secs(2))
+    ///     .unwrap()
+    ///     .with_underrun_behavior(UnderrunBehavior::Silence);
+    /// ```
+    ///
+    /// # Errors
+    /// If the worker thread cannot be spawned.
+    #[cfg(feature = "prefetch")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "prefetch")))]
+    #[inline]
+    fn prefetch(self, duration: Duration) -> std::io::Result<Prefetch>
+    where
+        Self: Sized + Send + 'static,
+    {
+        prefetch::prefetch(self, duration)
+    }
+
+    /// Converts this source into a [`FixedSource`](crate::FixedSource) with the channel
+    /// count and sample rate of `config`.
+    ///
+    /// Spans that already have this format are passed on as they are, others are
+    /// resampled and remixed. The result works with the span-free filters, mixer and
+    /// queue in [`fixed_source`](crate::fixed_source).
+    ///
+    /// # Example
+    /// ```
+    /// use rodio::fixed_source::{FixedConfig, FixedSource};
+    /// use rodio::math::nz;
+    /// use rodio::source::{SineWave, Source};
+    ///
+    /// let fixed = SineWave::new(440.0).into_fixed(FixedConfig::new(nz!(2), nz!(48_000)));
+    /// assert_eq!(fixed.channels(), nz!(2));
+    /// ```
+    #[cfg(feature = "experimental")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "experimental")))]
+    #[inline]
+    fn into_fixed(
+        self,
+        config: crate::fixed_source::FixedConfig,
+    ) -> crate::fixed_source::IntoFixed<Self>
+    where
+        Self: Sized,
+    {
+        crate::fixed_source::convert::into_fixed(self, config)
+    }
+
     /// Makes the sound pausable.
     // TODO: add example
     #[inline]
@@ -798,6 +936,27 @@ impl SeekError {
     }
 }
 
+/// Fills `buffer` by calling `next` for every sample, stopping early when the
+/// span ends and the format changes. Implements the default
+/// [`Source::fill_buffer`].
+pub(crate) fn fill_buffer_from_next<S>(source: &mut S, buffer: &mut [Sample]) -> usize
+where
+    S: Source + ?Sized,
+{
+    let channels = source.channels();
+    let sample_rate = source.sample_rate();
+    for (written, slot) in buffer.iter_mut().enumerate() {
+        match source.next() {
+            Some(sample) => *slot = sample,
+            None => return written,
+        }
+        if source.channels() != channels || source.sample_rate() != sample_rate {
+            return written + 1;
+        }
+    }
+    buffer.len()
+}
+
 macro_rules! source_pointer_impl {
     ($($sig:tt)+) => {
         impl $($sig)+ {
@@ -821,6 +980,11 @@ macro_rules! source_pointer_impl {
                 (**self).total_duration()
             }
 
+            #[inline]
+            fn fill_buffer(&mut self, buffer: &mut [Sample]) -> usize {
+                (**self).fill_buffer(buffer)
+            }
+
             #[inline]
             fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
                 (**self).try_seek(pos)
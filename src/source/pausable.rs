@@ -1,8 +1,8 @@
 use std::time::Duration;
 
-use super::SeekError;
+use super::{fill_buffer_from_next, SeekError};
 use crate::common::{ChannelCount, SampleRate};
-use crate::Source;
+use crate::{Sample, Source};
 
 /// Builds a `Pausable` object.
 pub fn pausable<I>(source: I, paused: bool) -> Pausable<I>
@@ -126,6 +126,16 @@ where
         self.input.total_duration()
     }
 
+    #[inline]
+    fn fill_buffer(&mut self, buffer: &mut [Sample]) -> usize {
+        if self.paused_channels.is_some() || self.remaining_paused_samples > 0 {
+            // Silence while paused, finishing the frame that was started.
+            fill_buffer_from_next(self, buffer)
+        } else {
+            self.input.fill_buffer(buffer)
+        }
+    }
+
     #[inline]
     fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
         self.input.try_seek(pos)
@@ -3,7 +3,7 @@ use std::time::Duration;
 use super::SeekError;
 use crate::{
     common::{ChannelCount, SampleRate},
-    Source,
+    Sample, Source,
 };
 
 /// Internal function that builds a `PeriodicAccess` object.
@@ -117,6 +117,21 @@ where
         self.input.total_duration()
     }
 
+    #[inline]
+    fn fill_buffer(&mut self, buffer: &mut [Sample]) -> usize {
+        if self.samples_until_update == 1 {
+            (self.modifier)(&mut self.input);
+            // The sample after the access counts towards the next period.
+            self.samples_until_update = self.update_frequency.saturating_add(1);
+        }
+
+        // Stop right before the next access is due.
+        let len = buffer.len().min(self.samples_until_update as usize - 1);
+        let written = self.input.fill_buffer(&mut buffer[..len]);
+        self.samples_until_update -= written as u32;
+        written
+    }
+
     #[inline]
     fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
         self.input.try_seek(pos)
@@ -1,9 +1,9 @@
 use std::time::Duration;
 
-use super::SeekError;
+use super::{fill_buffer_from_next, SeekError};
 use crate::common::{ChannelCount, SampleRate};
 use crate::math::nz;
-use crate::Source;
+use crate::{Sample, Source};
 
 /// Internal function that builds a `TrackPosition` object. See trait docs for
 /// details
@@ -142,6 +142,33 @@ where
         self.input.total_duration()
     }
 
+    #[inline]
+    fn fill_buffer(&mut self, buffer: &mut [Sample]) -> usize {
+        if self.current_span_len.is_none() {
+            self.set_current_span();
+        }
+
+        // Never read past the end of the span, the position is counted per span.
+        let len = match self.current_span_len {
+            Some(span_len) if span_len > self.samples_counted => {
+                buffer.len().min(span_len - self.samples_counted)
+            }
+            Some(_) => return fill_buffer_from_next(self, buffer),
+            None => buffer.len(),
+        };
+        let written = self.input.fill_buffer(&mut buffer[..len]);
+        self.samples_counted += written;
+        if written > 0 && Some(self.samples_counted) == self.current_span_len() {
+            self.offset_duration += self.samples_counted as f64
+                / self.current_span_sample_rate.get() as f64
+                / self.current_span_channels.get() as f64;
+
+            self.samples_counted = 0;
+            self.set_current_span();
+        }
+        written
+    }
+
     #[inline]
     fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
         let result = self.input.try_seek(pos);
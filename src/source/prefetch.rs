@@ -0,0 +1,541 @@
+use std::io;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread::{self, Thread};
+use std::time::Duration;
+
+use rtrb::{Consumer, Producer, RingBuffer};
+
+use super::read_ahead::{self, SetOnDrop, BLOCK_FRAMES};
+use super::SeekError;
+use crate::common::{ChannelCount, SampleRate};
+use crate::{Sample, Source};
+
+/// How long the worker waits for room in the buffer, and a blocking
+/// [`Prefetch`] for data, before checking again.
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Internal function that builds a `Prefetch` object.
+pub fn prefetch<I>(input: I, duration: Duration) -> io::Result<Prefetch>
+where
+    I: Source + Send + 'static,
+{
+    let channels = input.channels();
+    let sample_rate = input.sample_rate();
+    let total_duration = input.total_duration();
+    let frames = (duration.as_secs_f64() * f64::from(sample_rate.get())) as usize;
+    let capacity = frames.max(2 * BLOCK_FRAMES) * channels.get() as usize;
+    let (samples, samples_out) = RingBuffer::new(capacity);
+    let (spans, spans_out) = RingBuffer::new(capacity / 64);
+
+    let shared = Arc::new(Shared {
+        seek_target: AtomicU64::new(0),
+        seek_generation: AtomicU64::new(0),
+        stopped: AtomicBool::new(false),
+        closed: AtomicBool::new(false),
+    });
+    let worker = Worker {
+        input,
+        samples,
+        spans,
+        block: Vec::with_capacity(BLOCK_FRAMES * channels.get() as usize),
+        shared: shared.clone(),
+    };
+    let worker = thread::Builder::new()
+        .name("rodio-prefetch".to_owned())
+        .spawn(move || worker.run())?
+        .thread()
+        .clone();
+
+    let mut prefetch = Prefetch {
+        samples: samples_out,
+        spans: spans_out,
+        shared,
+        worker,
+        underrun: UnderrunBehavior::Silence,
+        channels,
+        sample_rate,
+        total_duration,
+        generation: 0,
+        seek_error: None,
+        remaining: 0,
+        len: 0,
+        silent: false,
+        ended: false,
+    };
+    prefetch.next_span(UnderrunBehavior::Block);
+    Ok(prefetch)
+}
+
+/// What a [`Prefetch`] does when the worker thread has not decoded the next
+/// samples in time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnderrunBehavior {
+    /// Plays silence until decoded samples are available again. This keeps
+    /// the audio thread from waiting, at the cost of a gap in the sound.
+    #[default]
+    Silence,
+    /// Waits for the worker thread. No audio is skipped, but the wait may
+    /// cause an underrun of the output device instead.
+    Block,
+}
+
+/// A source that is decoded ahead of playback on a worker thread.
+///
+/// Created with [`Source::prefetch`]. The worker stays up to the given duration
+/// ahead, so a slow disk or an expensive decoder does not hold up the audio
+/// thread. Seeks are forwarded to the worker, which discards what it decoded
+/// ahead.
+///
+/// Creating the prefetch waits until the worker has decoded the first samples.
+/// Seeking does not wait: silence plays until the worker has decoded from the
+/// new position, after which the [`UnderrunBehavior`] applies again. A seek the
+/// worker could not perform is reported by [`Prefetch::take_seek_error`].
+///
+/// If the worker thread panics, the prefetch ends.
+pub struct Prefetch {
+    samples: Consumer<Sample>,
+    spans: Consumer<Span>,
+    shared: Arc<Shared>,
+    worker: Thread,
+    underrun: UnderrunBehavior,
+    channels: ChannelCount,
+    sample_rate: SampleRate,
+    total_duration: Option<Duration>,
+    /// Number of seeks so far. Spans decoded before the last seek are discarded.
+    generation: u64,
+    /// The error of the last seek that failed on the worker.
+    seek_error: Option<SeekError>,
+    /// Samples left in the current span.
+    remaining: usize,
+    /// Length of the current span.
+    len: usize,
+    /// Whether the current span is silence played on an underrun.
+    silent: bool,
+    /// Set once the last span of the source has been taken.
+    ended: bool,
+}
+
+/// Samples of one format, written to the ring buffer before their `Span`.
+#[derive(Debug)]
+struct Span {
+    len: usize,
+    channels: ChannelCount,
+    sample_rate: SampleRate,
+    /// The seek this span was decoded after.
+    generation: u64,
+    /// Whether this is the last span of the source.
+    last: bool,
+    /// Set on the first span after a seek that failed.
+    seek_error: Option<SeekError>,
+}
+
+/// State shared between a [`Prefetch`] and its worker thread.
+struct Shared {
+    /// Position of the last requested seek, in nanoseconds.
+    seek_target: AtomicU64,
+    /// Increased by the [`Prefetch`] after storing `seek_target`.
+    seek_generation: AtomicU64,
+    /// Set when the worker thread returns or panics.
+    stopped: AtomicBool,
+    /// Set when the [`Prefetch`] is dropped.
+    closed: AtomicBool,
+}
+
+impl Prefetch {
+    /// Sets what to do when the worker thread falls behind.
+    /// By default this is [`UnderrunBehavior::Silence`].
+    #[inline]
+    pub fn with_underrun_behavior(mut self, underrun: UnderrunBehavior) -> Self {
+        self.underrun = underrun;
+        self
+    }
+
+    /// Returns the behavior on underruns.
+    #[inline]
+    pub fn underrun_behavior(&self) -> UnderrunBehavior {
+        self.underrun
+    }
+
+    /// Returns the error of the last seek the worker thread could not perform,
+    /// once playback has reached the samples decoded after it.
+    ///
+    /// [`Source::try_seek`] returns before the worker seeks, so it cannot report
+    /// these errors itself.
+    #[inline]
+    pub fn take_seek_error(&mut self) -> Option<SeekError> {
+        self.seek_error.take()
+    }
+
+    /// Moves to the next decoded span, or to silence if there is none yet.
+    fn next_span(&mut self, underrun: UnderrunBehavior) {
+        loop {
+            if let Some(span) = self.pop_span() {
+                if span.seek_error.is_some() {
+                    self.seek_error = span.seek_error;
+                }
+                self.ended = span.last;
+                if span.len == 0 {
+                    continue;
+                }
+                self.channels = span.channels;
+                self.sample_rate = span.sample_rate;
+                self.len = span.len;
+                self.remaining = span.len;
+                self.silent = false;
+                return;
+            }
+            if self.ended {
+                self.len = 0;
+                return;
+            }
+            match underrun {
+                UnderrunBehavior::Silence => {
+                    self.len = read_ahead::silence_len(self.channels, self.sample_rate);
+                    self.remaining = self.len;
+                    self.silent = true;
+                    return;
+                }
+                UnderrunBehavior::Block => thread::sleep(POLL_INTERVAL),
+            }
+        }
+    }
+
+    /// Takes the next span decoded after the last seek, discarding older ones.
+    fn pop_span(&mut self) -> Option<Span> {
+        loop {
+            let span = match self.spans.pop() {
+                Ok(span) => span,
+                // The worker writes its spans before it stops.
+                Err(_) if self.shared.stopped.load(Ordering::Acquire) => match self.spans.pop() {
+                    Ok(span) => span,
+                    Err(_) => {
+                        self.ended = true;
+                        return None;
+                    }
+                },
+                Err(_) => return None,
+            };
+            if span.generation == self.generation {
+                return Some(span);
+            }
+            self.skip_samples(span.len);
+        }
+    }
+
+    /// Discards `len` samples the worker has written.
+    fn skip_samples(&mut self, len: usize) {
+        if let Ok(chunk) = self.samples.read_chunk(len) {
+            chunk.commit_all();
+        }
+    }
+}
+
+impl Drop for Prefetch {
+    fn drop(&mut self) {
+        self.shared.closed.store(true, Ordering::Release);
+        // Wake the worker if it waits for a seek.
+        self.worker.unpark();
+    }
+}
+
+impl Iterator for Prefetch {
+    type Item = Sample;
+
+    #[inline]
+    fn next(&mut self) -> Option<Sample> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let sample = if self.silent {
+            0.0
+        } else {
+            self.samples.pop().unwrap_or(0.0)
+        };
+        if self.remaining == 0 {
+            self.next_span(self.underrun);
+        }
+        Some(sample)
+    }
+}
+
+impl Source for Prefetch {
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        Some(self.len)
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.channels
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.sample_rate
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.total_duration
+    }
+
+    /// Hands the seek to the worker thread and returns without waiting for it.
+    /// Errors of the seek itself are reported by [`Prefetch::take_seek_error`].
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        if self.shared.stopped.load(Ordering::Acquire) {
+            return Err(SeekError::Other(Arc::new(io::Error::other(
+                "the prefetch thread has stopped",
+            ))));
+        }
+        if !self.silent {
+            self.skip_samples(self.remaining);
+        }
+
+        self.generation += 1;
+        let target = u64::try_from(pos.as_nanos()).unwrap_or(u64::MAX);
+        self.shared.seek_target.store(target, Ordering::Relaxed);
+        self.shared
+            .seek_generation
+            .store(self.generation, Ordering::Release);
+        self.worker.unpark();
+
+        self.ended = false;
+        self.next_span(UnderrunBehavior::Silence);
+        Ok(())
+    }
+}
+
+/// Decodes the source on the worker thread.
+struct Worker<I> {
+    input: I,
+    samples: Producer<Sample>,
+    spans: Producer<Span>,
+    block: Vec<Sample>,
+    shared: Arc<Shared>,
+}
+
+impl<I: Source> Worker<I> {
+    fn run(mut self) {
+        let shared = self.shared.clone();
+        let _stopped = SetOnDrop(&shared.stopped);
+        let mut generation = 0;
+        let mut seek_error = None;
+        let mut input_ended = false;
+        'decode: loop {
+            if self.shared.closed.load(Ordering::Acquire) {
+                return;
+            }
+            let requested = self.shared.seek_generation.load(Ordering::Acquire);
+            if requested != generation {
+                generation = requested;
+                let target = self.shared.seek_target.load(Ordering::Relaxed);
+                seek_error = self.input.try_seek(Duration::from_nanos(target)).err();
+                input_ended = false;
+                continue;
+            }
+            if input_ended {
+                // Woken by a seek or when the `Prefetch` is dropped.
+                thread::park();
+                continue;
+            }
+
+            let format = read_ahead::decode_block(&mut self.input, &mut self.block);
+            input_ended = format.input_ended;
+
+            // Wait for room in the buffer.
+            while self.samples.slots() < self.block.len() || self.spans.is_full() {
+                if self.samples.is_abandoned() {
+                    return;
+                }
+                if self.shared.seek_generation.load(Ordering::Acquire) != generation {
+                    continue 'decode;
+                }
+                thread::park_timeout(POLL_INTERVAL);
+            }
+
+            let len = self.block.len();
+            if let Ok(chunk) = self.samples.write_chunk_uninit(len) {
+                chunk.fill_from_iter(self.block.drain(..));
+            }
+            let _ = self.spans.push(Span {
+                len,
+                channels: format.channels,
+                sample_rate: format.sample_rate,
+                generation,
+                last: input_ended,
+                seek_error: seek_error.take(),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+    use crate::buffer::SamplesBuffer;
+    use crate::math::nz;
+
+    fn source() -> SamplesBuffer {
+        SamplesBuffer::ramp(nz!(2), nz!(1000), 20_000)
+    }
+
+    /// Delays every sample and seek, and panics or fails seeks if asked to.
+    struct Slow {
+        inner: SamplesBuffer,
+        delay: Duration,
+        panic_after: Option<usize>,
+        seek_error: bool,
+    }
+
+    impl Slow {
+        fn new(inner: SamplesBuffer, delay: Duration) -> Self {
+            Slow {
+                inner,
+                delay,
+                panic_after: None,
+                seek_error: false,
+            }
+        }
+    }
+
+    impl Iterator for Slow {
+        type Item = Sample;
+        fn next(&mut self) -> Option<Sample> {
+            thread::sleep(self.delay);
+            if let Some(left) = &mut self.panic_after {
+                assert!(*left > 0, "decoder failed");
+                *left -= 1;
+            }
+            self.inner.next()
+        }
+    }
+
+    impl Source for Slow {
+        fn current_span_len(&self) -> Option<usize> {
+            self.inner.current_span_len()
+        }
+        fn channels(&self) -> ChannelCount {
+            self.inner.channels()
+        }
+        fn sample_rate(&self) -> SampleRate {
+            self.inner.sample_rate()
+        }
+        fn total_duration(&self) -> Option<Duration> {
+            self.inner.total_duration()
+        }
+        fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+            thread::sleep(Duration::from_millis(200));
+            if self.seek_error {
+                return Err(SeekError::NotSupported {
+                    underlying_source: "Slow",
+                });
+            }
+            self.inner.try_seek(pos)
+        }
+    }
+
+    #[test]
+    fn plays_all_samples() {
+        let prefetch = source()
+            .prefetch(Duration::from_millis(100))
+            .unwrap()
+            .with_underrun_behavior(UnderrunBehavior::Block);
+        assert_eq!(prefetch.total_duration(), Some(Duration::from_secs(20)));
+        let played: Vec<Sample> = prefetch.collect();
+        assert!(played == source().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn plays_silence_on_underrun() {
+        let samples = vec![1.0; 2 * BLOCK_FRAMES * 2];
+        let slow = Slow::new(
+            SamplesBuffer::new(nz!(2), nz!(1000), samples),
+            Duration::from_micros(50),
+        );
+        let mut prefetch = slow.prefetch(Duration::ZERO).unwrap();
+        // The first block is decoded before playback starts.
+        assert_eq!(prefetch.current_span_len(), Some(2 * BLOCK_FRAMES));
+        assert_eq!(
+            prefetch.by_ref().take(2 * BLOCK_FRAMES).count(),
+            2 * BLOCK_FRAMES
+        );
+        assert_eq!(prefetch.next(), Some(0.0));
+        assert_eq!(prefetch.current_span_len(), Some(20));
+        let played: Vec<Sample> = prefetch.collect();
+        assert_eq!(
+            played.iter().filter(|s| **s == 1.0).count(),
+            2 * BLOCK_FRAMES
+        );
+    }
+
+    #[test]
+    fn seeks_on_worker() {
+        let mut prefetch = source()
+            .prefetch(Duration::from_secs(1))
+            .unwrap()
+            .with_underrun_behavior(UnderrunBehavior::Block);
+        assert_eq!(prefetch.next(), Some(0.0));
+
+        prefetch.try_seek(Duration::from_secs(10)).unwrap();
+        let played = prefetch.by_ref().find(|s| *s != 0.0);
+        assert_eq!(played, Some(20_000.0));
+
+        // Seeking back after the end restarts the worker.
+        assert_eq!(prefetch.by_ref().count(), 19_999);
+        prefetch.try_seek(Duration::from_secs(19)).unwrap();
+        let played: Vec<Sample> = prefetch.filter(|s| *s != 0.0).collect();
+        assert_eq!(played.len(), 2000);
+        assert_eq!(played[0], 38_000.0);
+    }
+
+    #[test]
+    fn seeks_without_waiting() {
+        let slow = Slow::new(source(), Duration::ZERO);
+        let mut prefetch = slow
+            .prefetch(Duration::from_millis(100))
+            .unwrap()
+            .with_underrun_behavior(UnderrunBehavior::Block);
+
+        let start = Instant::now();
+        prefetch.try_seek(Duration::from_secs(10)).unwrap();
+        assert!(start.elapsed() < Duration::from_millis(100));
+        assert_eq!(prefetch.next(), Some(0.0));
+        assert_eq!(prefetch.by_ref().find(|s| *s != 0.0), Some(20_000.0));
+    }
+
+    #[test]
+    fn reports_failed_seeks() {
+        let mut slow = Slow::new(source(), Duration::ZERO);
+        slow.seek_error = true;
+        let mut prefetch = slow
+            .prefetch(Duration::from_millis(100))
+            .unwrap()
+            .with_underrun_behavior(UnderrunBehavior::Block);
+
+        prefetch.try_seek(Duration::from_secs(10)).unwrap();
+        assert!(prefetch.take_seek_error().is_none());
+        // The source keeps playing from where the worker was.
+        assert!(prefetch.by_ref().find(|s| *s != 0.0).unwrap() < 20_000.0);
+        assert!(matches!(
+            prefetch.take_seek_error(),
+            Some(SeekError::NotSupported { .. })
+        ));
+    }
+
+    #[test]
+    fn ends_when_the_worker_panics() {
+        let mut slow = Slow::new(source(), Duration::ZERO);
+        slow.panic_after = Some(3 * BLOCK_FRAMES * 2);
+        let mut prefetch = slow
+            .prefetch(Duration::ZERO)
+            .unwrap()
+            .with_underrun_behavior(UnderrunBehavior::Block);
+
+        assert_eq!(prefetch.by_ref().count(), 3 * BLOCK_FRAMES * 2);
+        assert!(prefetch.try_seek(Duration::ZERO).is_err());
+    }
+}
@@ -0,0 +1,67 @@
+//! Decoding in blocks on a worker thread, shared by `Prefetch` and `StreamDecoder`.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::common::{ChannelCount, SampleRate};
+use crate::{Sample, Source};
+
+/// Number of frames a worker thread decodes before handing them over.
+pub(crate) const BLOCK_FRAMES: usize = 1024;
+
+/// Length of the silence played when the worker falls behind, in frames per second.
+/// One hundredth of a second lets playback resume quickly.
+const SILENCE_DIVISOR: u32 = 100;
+
+/// The format of a block filled by [`decode_block`].
+pub(crate) struct BlockFormat {
+    pub(crate) channels: ChannelCount,
+    pub(crate) sample_rate: SampleRate,
+    /// Whether the input ended while filling the block.
+    pub(crate) input_ended: bool,
+}
+
+/// Replaces the contents of `block` with up to [`BLOCK_FRAMES`] frames from `input`.
+/// The block is cut short at the frame where the format of `input` changes.
+pub(crate) fn decode_block<S: Source>(input: &mut S, block: &mut Vec<Sample>) -> BlockFormat {
+    let channels = input.channels();
+    let sample_rate = input.sample_rate();
+    let frame = channels.get() as usize;
+    let mut input_ended = false;
+
+    block.clear();
+    while block.len() < BLOCK_FRAMES * frame {
+        let Some(sample) = input.next() else {
+            input_ended = true;
+            break;
+        };
+        block.push(sample);
+        if block.len().is_multiple_of(frame)
+            && (input.channels() != channels || input.sample_rate() != sample_rate)
+        {
+            break;
+        }
+    }
+
+    BlockFormat {
+        channels,
+        sample_rate,
+        input_ended,
+    }
+}
+
+/// Number of samples of silence to play when the worker has not decoded the next
+/// block in time.
+pub(crate) fn silence_len(channels: ChannelCount, sample_rate: SampleRate) -> usize {
+    let frames = (sample_rate.get() / SILENCE_DIVISOR).max(1) as usize;
+    frames * channels.get() as usize
+}
+
+/// Sets a flag when the worker thread returns or unwinds, so playback does not wait
+/// for a worker that has panicked.
+pub(crate) struct SetOnDrop<'a>(pub(crate) &'a AtomicBool);
+
+impl Drop for SetOnDrop<'_> {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Release);
+    }
+}
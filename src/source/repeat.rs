@@ -11,7 +11,7 @@ pub fn repeat<I>(input: I) -> Repeat<I>
 where
     I: Source,
 {
-    let input = input.buffered();
+    let input = input.buffered().with_rewind();
     Repeat {
         inner: input.clone(),
         next: input,
@@ -0,0 +1,331 @@
+use std::time::Duration;
+
+use super::{SeekError, UniformSourceIterator};
+use crate::common::{ChannelCount, SampleRate};
+use crate::math::{duration_to_frames, frames_to_duration};
+use crate::{Sample, Source};
+
+/// Internal function that builds a `Reverse` object.
+pub fn reverse<I>(input: I) -> Reverse<I>
+where
+    I: Source,
+{
+    let channels = input.channels();
+    let sample_rate = input.sample_rate();
+    let total_duration = input.total_duration();
+    let mut reverse = Reverse {
+        input: UniformSourceIterator::new(input, channels, sample_rate),
+        channels,
+        sample_rate,
+        total_duration,
+        mode: Mode::Chunked,
+        chunk: Vec::new(),
+        frames_left: 0,
+        channel: 0,
+        chunk_end: None,
+        to_input_end: true,
+    };
+
+    match total_duration {
+        Some(duration) => {
+            reverse.chunk_end = Some(duration_to_frames(duration, sample_rate));
+            // A failed seek leaves the source empty.
+            let _ = reverse.next_chunk();
+        }
+        None => reverse.load_all(),
+    }
+    reverse
+}
+
+/// Duration of the chunks read from seekable sources, in seconds.
+const CHUNK_SECONDS: u64 = 1;
+
+/// A source that plays another, finite source backwards.
+///
+/// Frames are played in reverse order while the channels within each frame keep their
+/// order. Sources that can seek are read one chunk at a time, starting from the end, so
+/// a long file does not need to fit in memory. Other sources are read completely first.
+///
+/// The channel count and sample rate of the first span are used throughout. Later spans
+/// with a different format are converted.
+pub struct Reverse<I>
+where
+    I: Source,
+{
+    input: UniformSourceIterator<I>,
+    channels: ChannelCount,
+    sample_rate: SampleRate,
+    total_duration: Option<Duration>,
+    mode: Mode,
+    /// Samples of the frames being played, in their original order.
+    chunk: Vec<Sample>,
+    /// Frames of `chunk` still to be played, from the back.
+    frames_left: usize,
+    /// Channel of the next sample within its frame.
+    channel: usize,
+    /// Frame of the input at which the next chunk ends, `None` once the start of the
+    /// input has been played.
+    chunk_end: Option<u64>,
+    /// Whether the next chunk is read up to the end of the input instead of
+    /// `chunk_end`, in case the total duration was rounded down.
+    to_input_end: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    /// Reading the input backwards one chunk at a time by seeking.
+    Chunked,
+    /// The whole input is in `chunk`.
+    Loaded,
+}
+
+impl<I> Reverse<I>
+where
+    I: Source,
+{
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input.into_inner()
+    }
+
+    #[inline]
+    fn frame_len(&self) -> usize {
+        self.channels.get() as usize
+    }
+
+    /// Reads the whole input into `chunk`.
+    fn load_all(&mut self) {
+        self.mode = Mode::Loaded;
+        self.chunk = self.input.by_ref().collect();
+        self.chunk
+            .truncate(self.chunk.len() / self.frame_len() * self.frame_len());
+        self.frames_left = self.chunk.len() / self.frame_len();
+        self.channel = 0;
+    }
+
+    /// Reads the chunk before the one just played. Leaves `frames_left` at zero
+    /// once the start of the input has been played, or if the input failed to
+    /// seek.
+    fn next_chunk(&mut self) -> Result<(), SeekError> {
+        self.channel = 0;
+        self.frames_left = 0;
+        let chunk_frames = CHUNK_SECONDS * u64::from(self.sample_rate.get());
+        let Some(end) = self.chunk_end.filter(|end| *end > 0 || self.to_input_end) else {
+            return Ok(());
+        };
+
+        let start = end.saturating_sub(chunk_frames);
+        if let Err(err) = self
+            .input
+            .try_seek(frames_to_duration(start, self.sample_rate))
+        {
+            if err.source_intact() && self.chunk.is_empty() {
+                // The input cannot seek, read all of it instead.
+                self.load_all();
+                return Ok(());
+            }
+            self.chunk_end = None;
+            return Err(err);
+        }
+
+        let frames = if self.to_input_end {
+            usize::MAX
+        } else {
+            (end - start) as usize
+        };
+        self.to_input_end = false;
+        let samples = frames.saturating_mul(self.frame_len());
+        self.chunk.clear();
+        self.chunk.extend(self.input.by_ref().take(samples));
+        self.chunk
+            .truncate(self.chunk.len() / self.frame_len() * self.frame_len());
+        self.frames_left = self.chunk.len() / self.frame_len();
+        self.chunk_end = Some(start);
+        Ok(())
+    }
+}
+
+impl<I> Iterator for Reverse<I>
+where
+    I: Source,
+{
+    type Item = Sample;
+
+    #[inline]
+    fn next(&mut self) -> Option<Sample> {
+        if self.frames_left == 0 {
+            return None;
+        }
+        let sample = self.chunk[(self.frames_left - 1) * self.frame_len() + self.channel];
+        self.channel += 1;
+        if self.channel == self.frame_len() {
+            self.channel = 0;
+            self.frames_left -= 1;
+            if self.frames_left == 0 && self.mode == Mode::Chunked {
+                // There is no way to report the error, the source ends.
+                let _ = self.next_chunk();
+            }
+        }
+        Some(sample)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = self.frames_left * self.frame_len() - self.channel;
+        match self.mode {
+            Mode::Loaded => (left, Some(left)),
+            Mode::Chunked => (left, None),
+        }
+    }
+}
+
+impl<I> Source for Reverse<I>
+where
+    I: Source,
+{
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        if self.frames_left == 0 {
+            Some(0)
+        } else {
+            Some(self.chunk.len())
+        }
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.channels
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.sample_rate
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.total_duration
+    }
+
+    /// Seeks to `pos` counted from the start of the reversed source, which is the end
+    /// of the input.
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        let pos = duration_to_frames(pos, self.sample_rate);
+        match self.mode {
+            Mode::Loaded => {
+                let frames = (self.chunk.len() / self.frame_len()) as u64;
+                self.frames_left = frames.saturating_sub(pos) as usize;
+                self.channel = 0;
+            }
+            Mode::Chunked => {
+                let total = self
+                    .total_duration
+                    .map(|total| duration_to_frames(total, self.sample_rate))
+                    .unwrap_or_default();
+                self.chunk_end = Some(total.saturating_sub(pos));
+                self.to_input_end = pos == 0;
+                self.next_chunk()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::buffer::SamplesBuffer;
+    use crate::math::nz;
+    use crate::source::from_iter;
+    use crate::static_buffer::StaticSamplesBuffer;
+
+    fn reversed_stereo(frames: usize) -> Vec<Sample> {
+        (0..frames)
+            .rev()
+            .flat_map(|frame| [2 * frame, 2 * frame + 1])
+            .map(|i| i as Sample)
+            .collect()
+    }
+
+    #[test]
+    fn reverses_seekable_source_in_chunks() {
+        let mut reverse = SamplesBuffer::ramp(nz!(2), nz!(1000), 2500).reverse();
+        assert_eq!(reverse.mode, Mode::Chunked);
+        // The first chunk is the last second of the input.
+        assert_eq!(reverse.current_span_len(), Some(2000));
+        assert_eq!(reverse.by_ref().collect::<Vec<_>>(), reversed_stereo(2500));
+        assert_eq!(reverse.current_span_len(), Some(0));
+    }
+
+    #[test]
+    fn reverses_source_that_cannot_seek() {
+        static SAMPLES: [Sample; 6] = [0.0, 1.0, 2.0, 3.0, 4.0, 5.0];
+        let reverse = StaticSamplesBuffer::new(nz!(2), nz!(1000), &SAMPLES).reverse();
+        assert_eq!(reverse.mode, Mode::Loaded);
+        assert_eq!(reverse.collect::<Vec<_>>(), reversed_stereo(3));
+    }
+
+    #[test]
+    fn reverses_source_without_duration() {
+        let reverse = from_iter([SamplesBuffer::ramp(nz!(2), nz!(1000), 300)]).reverse();
+        assert_eq!(reverse.mode, Mode::Loaded);
+        assert_eq!(reverse.collect::<Vec<_>>(), reversed_stereo(300));
+    }
+
+    #[test]
+    fn seeks_from_the_end() {
+        let mut reverse = SamplesBuffer::ramp(nz!(2), nz!(1000), 2500).reverse();
+        reverse.try_seek(Duration::from_millis(100)).unwrap();
+        assert_eq!(reverse.next(), Some((2 * 2399) as Sample));
+        assert_eq!(reverse.next(), Some((2 * 2399 + 1) as Sample));
+        assert_eq!(reverse.count(), 2399 * 2);
+    }
+
+    #[test]
+    fn seek_errors_are_returned() {
+        struct FailingSeek {
+            input: SamplesBuffer,
+            fail: Arc<AtomicBool>,
+        }
+        impl Iterator for FailingSeek {
+            type Item = Sample;
+            fn next(&mut self) -> Option<Sample> {
+                self.input.next()
+            }
+        }
+        impl Source for FailingSeek {
+            fn current_span_len(&self) -> Option<usize> {
+                self.input.current_span_len()
+            }
+            fn channels(&self) -> ChannelCount {
+                self.input.channels()
+            }
+            fn sample_rate(&self) -> SampleRate {
+                self.input.sample_rate()
+            }
+            fn total_duration(&self) -> Option<Duration> {
+                self.input.total_duration()
+            }
+            fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+                if self.fail.load(Ordering::Relaxed) {
+                    return Err(SeekError::Other(Arc::new(std::io::Error::other("broken"))));
+                }
+                self.input.try_seek(pos)
+            }
+        }
+
+        let fail = Arc::new(AtomicBool::new(false));
+        let mut reverse = FailingSeek {
+            input: SamplesBuffer::ramp(nz!(2), nz!(1000), 2500),
+            fail: fail.clone(),
+        }
+        .reverse();
+        assert_eq!(reverse.mode, Mode::Chunked);
+        fail.store(true, Ordering::Relaxed);
+        assert!(reverse.try_seek(Duration::from_millis(100)).is_err());
+    }
+}
@@ -0,0 +1,401 @@
+//! Scrubbing, playing a source at a continuously changing rate.
+//!
+//! The main concept of this module is the [`Scrub`] source together with its
+//! [`ScrubControl`] handle. Unlike [`Speed`](super::Speed), which relabels the sample rate,
+//! `Scrub` moves a playhead through the buffered input and interpolates between frames. The
+//! playhead can move backwards, stand still or be pulled towards a target position, like a
+//! DJ's hand on a record or a video editor's timeline.
+//!
+//! ```
+//! use std::time::Duration;
+//! use rodio::source::{SineWave, Source};
+//!
+//! let source = SineWave::new(440.0).take_duration(Duration::from_secs(10)).scrub();
+//! let control = source.control();
+//!
+//! // Play backwards at half speed.
+//! control.set_velocity(-0.5);
+//! // Glide to the five second mark and stay there.
+//! control.set_target(Duration::from_secs(5));
+//! ```
+
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::{SeekError, UniformSourceIterator};
+use crate::common::{ChannelCount, SampleRate};
+use crate::math::{frames_to_duration, NANOS_PER_SEC};
+use crate::{Float, Sample, Source};
+
+/// Internal function that builds a `Scrub` object.
+pub fn scrub<I>(input: I) -> Scrub<I>
+where
+    I: Source,
+{
+    let channels = input.channels();
+    let sample_rate = input.sample_rate();
+    let smoothing = (-1.0 / (VELOCITY_SMOOTHING.as_secs_f64() * sample_rate.get() as f64)).exp();
+    Scrub {
+        input: Some(UniformSourceIterator::new(input, channels, sample_rate)),
+        channels,
+        sample_rate,
+        data: Vec::new(),
+        position: 0.0,
+        velocity: 1.0,
+        smoothing,
+        frame: Vec::with_capacity(channels.get() as usize),
+        channel: 0,
+        shared: Arc::new(Shared {
+            velocity: AtomicU32::new(1.0f32.to_bits()),
+            target: AtomicU64::new(NO_TARGET),
+            position: AtomicU64::new(0),
+        }),
+    }
+}
+
+/// Time over which velocity changes are smoothed, to avoid clicks.
+const VELOCITY_SMOOTHING: Duration = Duration::from_millis(10);
+
+/// Time in which the playhead covers most of the distance to its target.
+const TARGET_RESPONSE: Duration = Duration::from_millis(100);
+
+/// Highest speed at which the playhead moves towards its target.
+const MAX_TARGET_VELOCITY: f64 = 8.0;
+
+/// Highest speed set with [`ScrubControl::set_velocity`], in either direction.
+const MAX_VELOCITY: f32 = 1024.0;
+
+/// Stored in `Shared::target` while the playhead is driven by velocity.
+const NO_TARGET: u64 = u64::MAX;
+
+/// A source whose playhead is driven by a velocity or a target position.
+///
+/// Created with [`Source::scrub`]. The input is buffered as the playhead reaches it and
+/// kept in memory, so the playhead can move back over everything played so far. Output
+/// samples are interpolated with a cubic (Catmull-Rom) curve between frames.
+///
+/// The playhead starts at the beginning at normal speed. It stops at the start of the
+/// input and plays silence once it is past the end. The source never ends by itself.
+pub struct Scrub<I>
+where
+    I: Source,
+{
+    /// The part of the input that is not yet buffered, `None` once it has ended.
+    input: Option<UniformSourceIterator<I>>,
+    channels: ChannelCount,
+    sample_rate: SampleRate,
+    /// Buffered samples of the input.
+    data: Vec<Sample>,
+    /// Playhead position, in frames.
+    position: f64,
+    /// Smoothed velocity of the playhead, in frames per output frame.
+    velocity: f64,
+    /// Coefficient of the one-pole filter that smooths velocity changes.
+    smoothing: f64,
+    /// Interpolated frame being played.
+    frame: Vec<Sample>,
+    /// Channel of the next sample within `frame`.
+    channel: usize,
+    shared: Arc<Shared>,
+}
+
+/// State shared between a [`Scrub`] source and its controls.
+#[derive(Debug)]
+struct Shared {
+    /// Requested velocity as `f32` bits.
+    velocity: AtomicU32,
+    /// Target position in nanoseconds, or `NO_TARGET`.
+    target: AtomicU64,
+    /// Playhead position in nanoseconds, published by the source.
+    position: AtomicU64,
+}
+
+/// Controls a [`Scrub`] source from any thread.
+#[derive(Debug, Clone)]
+pub struct ScrubControl {
+    shared: Arc<Shared>,
+}
+
+impl ScrubControl {
+    /// Moves the playhead at `velocity` times the normal speed. Negative values play
+    /// backwards and zero holds the playhead in place.
+    ///
+    /// Changes are smoothed over a few milliseconds. This cancels [`set_target`](Self::set_target).
+    /// The velocity is limited to 1024 times the normal speed, NaN stops the playhead.
+    pub fn set_velocity(&self, velocity: f32) {
+        let velocity = if velocity.is_nan() {
+            0.0
+        } else {
+            velocity.clamp(-MAX_VELOCITY, MAX_VELOCITY)
+        };
+        self.shared
+            .velocity
+            .store(velocity.to_bits(), Ordering::Relaxed);
+        self.shared.target.store(NO_TARGET, Ordering::Relaxed);
+    }
+
+    /// Pulls the playhead towards `position`, where it comes to rest.
+    ///
+    /// The playhead moves faster the further away the target is, up to eight times the
+    /// normal speed. Call this repeatedly to follow a mouse or a jog wheel. This cancels
+    /// [`set_velocity`](Self::set_velocity).
+    pub fn set_target(&self, position: Duration) {
+        let nanos = u64::try_from(position.as_nanos()).unwrap_or(u64::MAX);
+        self.shared
+            .target
+            .store(nanos.min(NO_TARGET - 1), Ordering::Relaxed);
+    }
+
+    /// Returns the position of the playhead, counted from the start of the input.
+    pub fn position(&self) -> Duration {
+        Duration::from_nanos(self.shared.position.load(Ordering::Relaxed))
+    }
+}
+
+impl<I> Scrub<I>
+where
+    I: Source,
+{
+    /// Returns a handle to control the playhead.
+    pub fn control(&self) -> ScrubControl {
+        ScrubControl {
+            shared: self.shared.clone(),
+        }
+    }
+
+    /// Returns the position of the playhead, counted from the start of the input.
+    pub fn position(&self) -> Duration {
+        frames_to_duration(self.position.round() as u64, self.sample_rate)
+    }
+
+    #[inline]
+    fn frame_len(&self) -> usize {
+        self.channels.get() as usize
+    }
+
+    /// Buffers the input up to and including `frame`, if it has that many frames.
+    fn load_until(&mut self, frame: usize) {
+        let needed = frame.saturating_add(1).saturating_mul(self.frame_len());
+        let Some(input) = &mut self.input else {
+            return;
+        };
+        if self.data.len() >= needed {
+            return;
+        }
+        self.data
+            .extend(input.by_ref().take(needed - self.data.len()));
+        if self.data.len() < needed {
+            let frames_len = self.data.len() / self.frame_len() * self.frame_len();
+            self.data.truncate(frames_len);
+            self.input = None;
+        }
+    }
+
+    /// Returns the sample of `channel` in `frame`, silence outside the input.
+    #[inline]
+    fn sample(&self, frame: isize, channel: usize) -> Sample {
+        if frame < 0 {
+            return 0.0;
+        }
+        (frame as usize)
+            .checked_mul(self.frame_len())
+            .and_then(|start| self.data.get(start + channel))
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    /// Number of frames buffered so far, or in the whole input once it has ended.
+    fn buffered_frames(&self) -> f64 {
+        (self.data.len() / self.frame_len()) as f64
+    }
+
+    /// Interpolates the frame at the playhead and moves the playhead on.
+    fn render_frame(&mut self) {
+        let index = self.position.floor();
+        let t = (self.position - index) as Float;
+        // Saturates, the input can not be that long.
+        let index = index as isize;
+        self.load_until((index as usize).saturating_add(2));
+
+        self.frame.clear();
+        for channel in 0..self.frame_len() {
+            let y0 = self.sample(index - 1, channel);
+            let y1 = self.sample(index, channel);
+            let y2 = self.sample(index.saturating_add(1), channel);
+            let y3 = self.sample(index.saturating_add(2), channel);
+            self.frame.push(catmull_rom(y0, y1, y2, y3, t));
+        }
+
+        self.velocity += (self.requested_velocity() - self.velocity) * (1.0 - self.smoothing);
+        self.position += self.velocity;
+        if self.position < 0.0 {
+            self.position = 0.0;
+        }
+        if self.input.is_none() {
+            self.position = self.position.min(self.buffered_frames());
+        }
+        let position = frames_to_duration(self.position.round() as u64, self.sample_rate);
+        self.shared
+            .position
+            .store(position.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    /// Velocity asked for by the controls, in frames per output frame.
+    fn requested_velocity(&self) -> f64 {
+        let target = self.shared.target.load(Ordering::Relaxed);
+        if target == NO_TARGET {
+            return f32::from_bits(self.shared.velocity.load(Ordering::Relaxed)) as f64;
+        }
+        let target = target as f64 * self.sample_rate.get() as f64 / NANOS_PER_SEC as f64;
+        let response = TARGET_RESPONSE.as_secs_f64() * self.sample_rate.get() as f64;
+        ((target - self.position) / response).clamp(-MAX_TARGET_VELOCITY, MAX_TARGET_VELOCITY)
+    }
+}
+
+impl<I> Iterator for Scrub<I>
+where
+    I: Source,
+{
+    type Item = Sample;
+
+    #[inline]
+    fn next(&mut self) -> Option<Sample> {
+        if self.channel == self.frame.len() {
+            self.render_frame();
+            self.channel = 0;
+        }
+        let sample = self.frame[self.channel];
+        self.channel += 1;
+        Some(sample)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (usize::MAX, None)
+    }
+}
+
+impl<I> Source for Scrub<I>
+where
+    I: Source,
+{
+    #[inline]
+    fn current_span_len(&self) -> Option<usize> {
+        None
+    }
+
+    #[inline]
+    fn channels(&self) -> ChannelCount {
+        self.channels
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> SampleRate {
+        self.sample_rate
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Moves the playhead to `pos` immediately. The velocity or target is kept.
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        // The rest of the current frame is still played, so the channel order stays intact.
+        let position = pos.as_secs_f64() * self.sample_rate.get() as f64;
+        self.position = if self.input.is_none() {
+            position.min(self.buffered_frames())
+        } else {
+            position
+        };
+        Ok(())
+    }
+}
+
+/// Cubic Catmull-Rom interpolation between `y1` and `y2`, with `t` in `[0, 1)`.
+#[inline]
+fn catmull_rom(y0: Sample, y1: Sample, y2: Sample, y3: Sample, t: Float) -> Sample {
+    let a = -0.5 * y0 + 1.5 * y1 - 1.5 * y2 + 0.5 * y3;
+    let b = y0 - 2.5 * y1 + 2.0 * y2 - 0.5 * y3;
+    let c = -0.5 * y0 + 0.5 * y2;
+    ((a * t + b) * t + c) * t + y1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::SamplesBuffer;
+    use crate::math::nz;
+
+    #[test]
+    fn plays_at_normal_speed() {
+        let scrub = SamplesBuffer::ramp(nz!(2), nz!(1000), 100).scrub();
+        let played: Vec<Sample> = scrub.take(200).collect();
+        let expected: Vec<Sample> = (0..200).map(|i| i as Sample).collect();
+        assert_eq!(played, expected);
+    }
+
+    #[test]
+    fn plays_backwards() {
+        let mut scrub = SamplesBuffer::ramp(nz!(2), nz!(1000), 1000).scrub();
+        scrub.try_seek(Duration::from_millis(500)).unwrap();
+        scrub.control().set_velocity(-1.0);
+        // Let the velocity settle.
+        let mut scrub = scrub.skip(200);
+        let first = scrub.next().unwrap();
+        scrub.next();
+        let second = scrub.next().unwrap();
+        assert!((first - second - 2.0).abs() < 0.01, "{first} {second}");
+    }
+
+    #[test]
+    fn interpolates_between_frames() {
+        let mut scrub = SamplesBuffer::ramp(nz!(2), nz!(1000), 100).scrub();
+        scrub.control().set_velocity(0.0);
+        scrub.velocity = 0.0;
+        scrub.try_seek(Duration::from_micros(10_500)).unwrap();
+        // Samples rise linearly, which cubic interpolation reproduces exactly.
+        assert!((scrub.next().unwrap() - 21.0).abs() < 1e-3);
+        assert!((scrub.next().unwrap() - 22.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn glides_to_target_and_stops() {
+        let scrub = SamplesBuffer::ramp(nz!(2), nz!(1000), 1000).scrub();
+        let control = scrub.control();
+        control.set_target(Duration::from_millis(300));
+        let mut scrub = scrub.skip(2 * 2000);
+        let first = scrub.next().unwrap();
+        assert!(control.position().abs_diff(Duration::from_millis(300)) < Duration::from_millis(1));
+        scrub.next();
+        assert!((scrub.next().unwrap() - first).abs() < 0.01);
+    }
+
+    #[test]
+    fn stays_within_input() {
+        let mut scrub = SamplesBuffer::ramp(nz!(2), nz!(1000), 10).scrub();
+        scrub.control().set_velocity(-4.0);
+        assert!(scrub.by_ref().take(100).all(|s| s >= 0.0));
+        assert_eq!(scrub.position(), Duration::ZERO);
+
+        scrub.control().set_velocity(4.0);
+        assert!(scrub.by_ref().skip(100).take(10).all(|s| s == 0.0));
+        assert_eq!(scrub.position(), Duration::from_millis(10));
+    }
+
+    #[test]
+    fn limits_velocity() {
+        let mut scrub = SamplesBuffer::ramp(nz!(2), nz!(1000), 10).scrub();
+        scrub.control().set_velocity(f32::INFINITY);
+        assert!(scrub.by_ref().take(100).all(|s| s.is_finite()));
+        assert_eq!(scrub.position(), Duration::from_millis(10));
+
+        // NaN stops the playhead, after the usual smoothing.
+        scrub.control().set_velocity(f32::NAN);
+        scrub.by_ref().take(4000).for_each(drop);
+        scrub.try_seek(Duration::from_millis(5)).unwrap();
+        scrub.by_ref().take(100).for_each(drop);
+        assert_eq!(scrub.position(), Duration::from_millis(5));
+    }
+}
@@ -1,5 +1,5 @@
 use crate::common::{ChannelCount, SampleRate};
-use crate::Source;
+use crate::{Sample, Source};
 use std::time::Duration;
 
 use super::SeekError;
@@ -94,6 +94,15 @@ where
         self.input.total_duration()
     }
 
+    #[inline]
+    fn fill_buffer(&mut self, buffer: &mut [Sample]) -> usize {
+        if self.do_skip {
+            0
+        } else {
+            self.input.fill_buffer(buffer)
+        }
+    }
+
     #[inline]
     fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
         self.input.try_seek(pos)
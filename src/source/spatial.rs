@@ -1,19 +1,211 @@
 use std::time::Duration;
 
-use super::SeekError;
+use super::hrtf::Binaural;
+use super::{HrirSet, SeekError};
 use crate::common::{ChannelCount, SampleRate};
+use crate::math::PI;
 use crate::source::ChannelVolume;
-use crate::{Float, Source};
+use crate::{Float, Sample, Source};
+
+/// Speed of sound in air in metres per second, used for the Doppler effect.
+const SPEED_OF_SOUND: f32 = 343.3;
+
+/// Longest span in samples while the Doppler effect is on, a changed Doppler
+/// shift takes effect when it ends.
+const DOPPLER_SPAN_LEN: usize = 1024;
 
 /// A simple spatial audio source. The underlying source is transformed to Mono
 /// and then played in stereo. The left and right channel's volume are amplified
 /// differently depending on the distance of the left and right ear to the source.
+///
+/// With [`Spatial::set_hrirs`] the source is rendered binaurally instead, for
+/// headphones: it is filtered with the head-related impulse responses for its
+/// direction, which adds interaural delay and elevation and front/back cues.
+///
+/// Distances are in metres. By default the volume falls with the square of the
+/// distance to each ear; [`Spatial::set_distance_model`] picks a configurable
+/// falloff instead. The Doppler effect ([`Spatial::set_velocities`]), air
+/// absorption ([`Spatial::set_air_absorption`]) and directional emitters
+/// ([`Spatial::set_cone`]) are off until set.
 #[derive(Clone)]
 pub struct Spatial<I>
 where
     I: Source,
 {
     input: ChannelVolume<I>,
+    positions: [[f32; 3]; 3],
+    up: [f32; 3],
+    distance_model: Option<DistanceModel>,
+    cone: Option<SoundCone>,
+    // Velocities of the emitter and the listener.
+    velocities: [[f32; 3]; 2],
+    doppler_factor: f32,
+    // Pitch change from the Doppler effect, applied when the current span ends.
+    doppler_shift: f32,
+    // Pitch change from the Doppler effect in the current span.
+    span_doppler_shift: f32,
+    // The length of the current span is fixed by its first sample, until then
+    // the next span is described.
+    span_started: bool,
+    span_len: Option<usize>,
+    // Samples left before the current span ends.
+    span_left: usize,
+    air_absorption: f32,
+    // Coefficient of the air absorption low-pass, 1.0 lets everything through.
+    lowpass_coeff: Float,
+    lowpass_state: [Sample; 2],
+    binaural: Option<Binaural>,
+    // The right ear sample of a binaural frame whose left sample was returned.
+    right_sample: Option<Sample>,
+    // A left sample was passed through without binaural rendering.
+    mid_frame: bool,
+}
+
+/// How the volume falls off with distance in a [`DistanceModel`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rolloff {
+    /// `reference / (reference + rolloff_factor * (distance - reference))`
+    Inverse,
+    /// `1 - rolloff_factor * (distance - reference) / (max - reference)`
+    Linear,
+    /// `(distance / reference) ^ -rolloff_factor`
+    Exponential,
+}
+
+/// Distance attenuation for [`Spatial`], like the clamped distance models of
+/// OpenAL.
+///
+/// Sources closer than the reference distance play at full volume and the
+/// volume stops falling beyond the max distance.
+///
+/// # Example
+/// ```
+/// use rodio::source::DistanceModel;
+///
+/// let model = DistanceModel::inverse()
+///     .with_reference_distance(2.0)
+///     .with_max_distance(50.0);
+/// assert_eq!(model.gain(1.0), 1.0);
+/// assert_eq!(model.gain(4.0), 0.5);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DistanceModel {
+    /// Shape of the falloff.
+    pub rolloff: Rolloff,
+    /// Distance up to which the volume is not attenuated. Must be positive.
+    pub reference_distance: f32,
+    /// Distance beyond which the volume does not fall further.
+    pub max_distance: f32,
+    /// How fast the volume falls off, `0.0` disables the attenuation.
+    pub rolloff_factor: f32,
+}
+
+impl DistanceModel {
+    /// Inverse distance falloff, the volume halves each time the distance
+    /// doubles. This is the model OpenAL uses by default.
+    pub fn inverse() -> Self {
+        Self::new(Rolloff::Inverse, f32::INFINITY)
+    }
+
+    /// Linear falloff, silent at `max_distance`.
+    pub fn linear(max_distance: f32) -> Self {
+        Self::new(Rolloff::Linear, max_distance)
+    }
+
+    /// Exponential falloff. With a rolloff factor of 2 the volume falls with
+    /// the square of the distance.
+    pub fn exponential() -> Self {
+        Self::new(Rolloff::Exponential, f32::INFINITY)
+    }
+
+    fn new(rolloff: Rolloff, max_distance: f32) -> Self {
+        Self {
+            rolloff,
+            reference_distance: 1.0,
+            max_distance,
+            rolloff_factor: 1.0,
+        }
+    }
+
+    /// Sets the distance up to which the volume is not attenuated, `1.0` by
+    /// default.
+    pub fn with_reference_distance(mut self, distance: f32) -> Self {
+        self.reference_distance = distance;
+        self
+    }
+
+    /// Sets the distance beyond which the volume does not fall further.
+    pub fn with_max_distance(mut self, distance: f32) -> Self {
+        self.max_distance = distance;
+        self
+    }
+
+    /// Sets how fast the volume falls off, `1.0` by default.
+    pub fn with_rolloff_factor(mut self, factor: f32) -> Self {
+        self.rolloff_factor = factor;
+        self
+    }
+
+    /// The volume of a source at `distance`.
+    pub fn gain(&self, distance: f32) -> Float {
+        let reference = self.reference_distance;
+        if reference <= 0.0 {
+            return 1.0;
+        }
+        let max = self.max_distance.max(reference);
+        let distance = distance.clamp(reference, max);
+        let gain = match self.rolloff {
+            Rolloff::Inverse => {
+                reference / (reference + self.rolloff_factor * (distance - reference))
+            }
+            Rolloff::Linear if max > reference => {
+                1.0 - self.rolloff_factor * (distance - reference) / (max - reference)
+            }
+            Rolloff::Linear => 1.0,
+            Rolloff::Exponential => (distance / reference).powf(-self.rolloff_factor),
+        };
+        gain.clamp(0.0, 1.0) as Float
+    }
+}
+
+/// Makes a [`Spatial`] emitter directional, like the sound cones of OpenAL.
+///
+/// Inside the inner cone the source plays at full volume, outside the outer
+/// cone at `outer_gain`. In between the volume changes linearly.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SoundCone {
+    /// The direction the emitter faces.
+    pub direction: [f32; 3],
+    /// Full angle of the inner cone, in degrees.
+    pub inner_angle: f32,
+    /// Full angle of the outer cone, in degrees.
+    pub outer_angle: f32,
+    /// Volume outside the outer cone.
+    pub outer_gain: Float,
+}
+
+impl SoundCone {
+    /// The volume for a listener at `listener` of an emitter at `emitter`.
+    pub fn gain(&self, emitter: [f32; 3], listener: [f32; 3]) -> Float {
+        let (Some(facing), Some(to_listener)) =
+            (normalize(self.direction), normalize(sub(listener, emitter)))
+        else {
+            return 1.0;
+        };
+        let angle = 2.0
+            * dot(facing, to_listener)
+                .clamp(-1.0, 1.0)
+                .acos()
+                .to_degrees();
+        if angle <= self.inner_angle {
+            1.0
+        } else if angle >= self.outer_angle {
+            self.outer_gain
+        } else {
+            let t = ((angle - self.inner_angle) / (self.outer_angle - self.inner_angle)) as Float;
+            1.0 + (self.outer_gain - 1.0) * t
+        }
+    }
 }
 
 fn dist_sq(a: [f32; 3], b: [f32; 3]) -> f32 {
@@ -23,6 +215,53 @@ fn dist_sq(a: [f32; 3], b: [f32; 3]) -> f32 {
         .sum::<f32>()
 }
 
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn normalize(a: [f32; 3]) -> Option<[f32; 3]> {
+    let len = dot(a, a).sqrt();
+    (len > f32::EPSILON).then(|| [a[0] / len, a[1] / len, a[2] / len])
+}
+
+fn midpoint(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        (a[0] + b[0]) / 2.0,
+        (a[1] + b[1]) / 2.0,
+        (a[2] + b[2]) / 2.0,
+    ]
+}
+
+/// Frequency at which air absorption is specified.
+const AIR_ABSORPTION_FREQUENCY: f32 = 5000.0;
+/// Attenuation at that frequency per metre for an air absorption factor of 1.
+const AIR_ABSORPTION_DB_PER_METRE: f32 = 0.05;
+
+/// Coefficient of the one-pole low-pass that attenuates
+/// `AIR_ABSORPTION_FREQUENCY` as much as `distance` metres of air would.
+fn air_absorption_coeff(factor: f32, distance: f32, sample_rate: SampleRate) -> Float {
+    let attenuation_db = factor * distance * AIR_ABSORPTION_DB_PER_METRE;
+    if attenuation_db <= 0.001 {
+        return 1.0;
+    }
+    let gain = 10f32.powf(-attenuation_db / 20.0);
+    let cutoff = AIR_ABSORPTION_FREQUENCY / (1.0 / (gain * gain) - 1.0).sqrt();
+    let cutoff = cutoff as Float;
+    1.0 - (-2.0 * PI * cutoff / sample_rate.get() as Float).exp()
+}
+
 impl<I> Spatial<I>
 where
     I: Source,
@@ -39,11 +278,137 @@ where
     {
         let mut ret = Spatial {
             input: ChannelVolume::new(input, vec![0.0, 0.0]),
+            positions: [emitter_position, left_ear, right_ear],
+            up: [0.0, 1.0, 0.0],
+            distance_model: None,
+            cone: None,
+            velocities: [[0.0; 3]; 2],
+            doppler_factor: 1.0,
+            doppler_shift: 1.0,
+            span_doppler_shift: 1.0,
+            span_started: false,
+            span_len: None,
+            span_left: 0,
+            air_absorption: 0.0,
+            lowpass_coeff: 1.0,
+            lowpass_state: [0.0; 2],
+            binaural: None,
+            right_sample: None,
+            mid_frame: false,
         };
         ret.set_positions(emitter_position, left_ear, right_ear);
         ret
     }
 
+    /// Renders the source binaurally with `hrirs`, or goes back to the simple
+    /// stereo panning if `None`.
+    ///
+    /// # Example
+    /// ```
+    /// use rodio::source::{HrirSet, SineWave, Spatial};
+    ///
+    /// let mut spatial = Spatial::new(SineWave::new(440.0), [1.0, 0.0, -2.0], [-0.1, 0.0, 0.0], [0.1, 0.0, 0.0]);
+    /// spatial.set_hrirs(Some(HrirSet::spherical_head()));
+    /// ```
+    pub fn set_hrirs(&mut self, hrirs: Option<HrirSet>) {
+        let unchanged = match (&self.binaural, &hrirs) {
+            (Some(binaural), Some(hrirs)) => binaural.hrirs().ptr_eq(hrirs),
+            (None, None) => true,
+            _ => false,
+        };
+        if unchanged {
+            return;
+        }
+        self.binaural =
+            hrirs.map(|hrirs| Binaural::new(hrirs, self.direction(), self.input.sample_rate()));
+        self.update();
+    }
+
+    /// Sets which way is up for the listener, `[0.0, 1.0, 0.0]` by default.
+    ///
+    /// Together with the ears it gives the direction the listener faces, which
+    /// binaural rendering needs to tell front from back. With the default up
+    /// direction and the right ear towards +x the listener faces -z.
+    pub fn set_up_direction(&mut self, up: [f32; 3]) {
+        self.up = up;
+        self.update();
+    }
+
+    /// Sets how the volume falls off with the distance between the emitter and
+    /// the middle of the ears. `None` restores the default, where the volume of
+    /// each ear falls with the square of its distance.
+    ///
+    /// # Example
+    /// ```
+    /// use rodio::source::{DistanceModel, SineWave, Spatial};
+    ///
+    /// let mut spatial = Spatial::new(SineWave::new(440.0), [10.0, 0.0, 0.0], [-0.1, 0.0, 0.0], [0.1, 0.0, 0.0]);
+    /// spatial.set_distance_model(Some(DistanceModel::linear(100.0)));
+    /// ```
+    pub fn set_distance_model(&mut self, model: Option<DistanceModel>) {
+        self.distance_model = model;
+        self.update();
+    }
+
+    /// Makes the emitter directional, or omnidirectional again if `None`.
+    pub fn set_cone(&mut self, cone: Option<SoundCone>) {
+        self.cone = cone;
+        self.update();
+    }
+
+    /// Sets the velocities of the emitter and the listener in metres per
+    /// second, for the Doppler effect. Both are zero by default.
+    ///
+    /// The Doppler effect changes the pitch by changing the sample rate the
+    /// source reports, like [`Source::speed`] does. To do so the source is
+    /// split into spans of at most 512 frames while a velocity is set, and a
+    /// new pitch starts with the next span. Sources without spans play on in
+    /// one span, set a velocity before playing them to hear the effect.
+    pub fn set_velocities(&mut self, emitter: [f32; 3], listener: [f32; 3]) {
+        self.velocities = [emitter, listener];
+        self.update();
+    }
+
+    /// Exaggerates (above `1.0`) or reduces (below `1.0`) the Doppler effect.
+    /// `0.0` disables it. The default is `1.0`.
+    pub fn set_doppler_factor(&mut self, factor: f32) {
+        self.doppler_factor = factor;
+        self.update();
+    }
+
+    /// Sets how much the air muffles distant sources, `0.0` (the default)
+    /// disables it.
+    ///
+    /// At `1.0` high frequencies around 5 kHz lose 0.05 dB per metre, about as
+    /// much as they do in real air.
+    pub fn set_air_absorption(&mut self, factor: f32) {
+        self.air_absorption = factor;
+        self.update();
+    }
+
+    /// Direction of the emitter relative to the listener's head: x to the
+    /// right, y up, z to the front.
+    fn direction(&self) -> [Float; 3] {
+        let [emitter_pos, left_ear, right_ear] = self.positions;
+        let center = midpoint(left_ear, right_ear);
+        let front = [0.0, 0.0, 1.0];
+        let Some(right) = normalize(sub(right_ear, left_ear)) else {
+            return front;
+        };
+        let Some(forward) = normalize(cross(self.up, right)) else {
+            return front;
+        };
+        let up = cross(right, forward);
+        let Some(to_emitter) = normalize(sub(emitter_pos, center)) else {
+            return front;
+        };
+        [
+            Float::from(dot(to_emitter, right)),
+            Float::from(dot(to_emitter, up)),
+            Float::from(dot(to_emitter, forward)),
+        ]
+    }
+
     /// Sets the position of the emitter and ears in the 3D world.
     pub fn set_positions(
         &mut self,
@@ -52,6 +417,50 @@ where
         right_ear: [f32; 3],
     ) {
         debug_assert!(left_ear != right_ear);
+        self.positions = [emitter_pos, left_ear, right_ear];
+        self.update();
+    }
+
+    /// Pitch change from the Doppler effect, as defined by OpenAL. The
+    /// velocities are limited to half the speed of sound.
+    fn doppler_shift(&self, emitter_pos: [f32; 3], center: [f32; 3]) -> f32 {
+        if self.doppler_factor <= 0.0 {
+            return 1.0;
+        }
+        let Some(to_listener) = normalize(sub(center, emitter_pos)) else {
+            return 1.0;
+        };
+        let limit = SPEED_OF_SOUND / self.doppler_factor / 2.0;
+        let [emitter_velocity, listener_velocity] = self.velocities;
+        let emitter_speed = dot(to_listener, emitter_velocity).clamp(-limit, limit);
+        let listener_speed = dot(to_listener, listener_velocity).clamp(-limit, limit);
+        (SPEED_OF_SOUND - self.doppler_factor * listener_speed)
+            / (SPEED_OF_SOUND - self.doppler_factor * emitter_speed)
+    }
+
+    fn update(&mut self) {
+        let [emitter_pos, left_ear, right_ear] = self.positions;
+        let center = midpoint(left_ear, right_ear);
+        let distance = dist_sq(center, emitter_pos).sqrt();
+        let sample_rate = self.input.sample_rate();
+
+        self.doppler_shift = self.doppler_shift(emitter_pos, center);
+        self.lowpass_coeff = air_absorption_coeff(self.air_absorption, distance, sample_rate);
+        let cone_gain = self.cone.map_or(1.0, |cone| cone.gain(emitter_pos, center));
+        let distance_gain = self.distance_model.map(|model| model.gain(distance));
+
+        if self.binaural.is_some() {
+            let gain =
+                distance_gain.unwrap_or_else(|| (1.0 / (distance * distance)).min(1.0) as Float);
+            self.input.set_volume(0, gain * cone_gain);
+            self.input.set_volume(1, gain * cone_gain);
+            let direction = self.direction();
+            if let Some(binaural) = &mut self.binaural {
+                binaural.set_direction(direction, sample_rate);
+            }
+            return;
+        }
+
         let left_dist_sq = dist_sq(left_ear, emitter_pos);
         let right_dist_sq = dist_sq(right_ear, emitter_pos);
         let max_diff = dist_sq(left_ear, right_ear).sqrt();
@@ -60,15 +469,35 @@ where
         let left_diff_modifier = (((left_dist - right_dist) / max_diff + 1.0) / 4.0 + 0.5).min(1.0);
         let right_diff_modifier =
             (((right_dist - left_dist) / max_diff + 1.0) / 4.0 + 0.5).min(1.0);
-        let left_dist_modifier = (1.0 / left_dist_sq).min(1.0);
-        let right_dist_modifier = (1.0 / right_dist_sq).min(1.0);
-        self.input
-            .set_volume(0, (left_diff_modifier * left_dist_modifier) as Float);
-        self.input
-            .set_volume(1, (right_diff_modifier * right_dist_modifier) as Float);
+        let (left_dist_modifier, right_dist_modifier) = match distance_gain {
+            Some(gain) => (gain, gain),
+            None => (
+                (1.0 / left_dist_sq).min(1.0) as Float,
+                (1.0 / right_dist_sq).min(1.0) as Float,
+            ),
+        };
+        self.input.set_volume(
+            0,
+            left_diff_modifier as Float * left_dist_modifier * cone_gain,
+        );
+        self.input.set_volume(
+            1,
+            right_diff_modifier as Float * right_dist_modifier * cone_gain,
+        );
     }
 }
 
+/// One step of the air absorption low-pass.
+#[inline]
+fn absorb(state: &mut Sample, coeff: Float, sample: Sample) -> Sample {
+    if coeff == 1.0 {
+        *state = sample;
+    } else {
+        *state += coeff * (sample - *state);
+    }
+    *state
+}
+
 impl<I> Iterator for Spatial<I>
 where
     I: Source,
@@ -77,7 +506,15 @@ where
 
     #[inline]
     fn next(&mut self) -> Option<I::Item> {
-        self.input.next()
+        if !self.span_started {
+            self.start_span();
+        }
+        let sample = self.next_sample()?;
+        if self.span_len.is_some() {
+            self.span_left -= 1;
+            self.span_started = self.span_left > 0;
+        }
+        Some(sample)
     }
 
     #[inline]
@@ -86,6 +523,55 @@ where
     }
 }
 
+impl<I> Spatial<I>
+where
+    I: Source,
+{
+    fn start_span(&mut self) {
+        self.span_doppler_shift = self.doppler_shift;
+        self.span_len = self.next_span_len();
+        self.span_left = self.span_len.unwrap_or(0);
+        self.span_started = true;
+    }
+
+    /// The input's span, ended early while the Doppler effect is on so that a
+    /// new shift can take effect.
+    fn next_span_len(&self) -> Option<usize> {
+        let span_len = self.input.current_span_len().filter(|&len| len > 0);
+        let doppler = self.doppler_factor > 0.0
+            && self.velocities.iter().flatten().any(|&speed| speed != 0.0);
+        if doppler {
+            Some(span_len.map_or(DOPPLER_SPAN_LEN, |len| len.min(DOPPLER_SPAN_LEN)))
+        } else {
+            span_len
+        }
+    }
+
+    #[inline]
+    fn next_sample(&mut self) -> Option<I::Item> {
+        if let Some(right) = self.right_sample.take() {
+            return Some(right);
+        }
+        match &mut self.binaural {
+            // Both channels carry the same mono sample.
+            Some(binaural) if !self.mid_frame => {
+                let sample = self.input.next()?;
+                self.input.next();
+                let sample = absorb(&mut self.lowpass_state[0], self.lowpass_coeff, sample);
+                let [left, right] = binaural.process(sample);
+                self.right_sample = Some(right);
+                Some(left)
+            }
+            _ => {
+                let state = &mut self.lowpass_state[usize::from(self.mid_frame)];
+                self.mid_frame = !self.mid_frame;
+                let sample = self.input.next()?;
+                Some(absorb(state, self.lowpass_coeff, sample))
+            }
+        }
+    }
+}
+
 impl<I> ExactSizeIterator for Spatial<I> where I: Source + ExactSizeIterator {}
 
 impl<I> Source for Spatial<I>
@@ -94,7 +580,11 @@ where
 {
     #[inline]
     fn current_span_len(&self) -> Option<usize> {
-        self.input.current_span_len()
+        if self.span_started {
+            self.span_len
+        } else {
+            self.next_span_len()
+        }
     }
 
     #[inline]
@@ -104,7 +594,17 @@ where
 
     #[inline]
     fn sample_rate(&self) -> SampleRate {
-        self.input.sample_rate()
+        let sample_rate = self.input.sample_rate();
+        let shift = if self.span_started {
+            self.span_doppler_shift
+        } else {
+            self.doppler_shift
+        };
+        if shift == 1.0 {
+            return sample_rate;
+        }
+        let shifted = (sample_rate.get() as f32 * shift).round() as u32;
+        SampleRate::new(shifted).unwrap_or(sample_rate)
     }
 
     #[inline]
@@ -114,6 +614,181 @@ where
 
     #[inline]
     fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
-        self.input.try_seek(pos)
+        self.input.try_seek(pos)?;
+        self.right_sample = None;
+        self.mid_frame = false;
+        self.span_started = false;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DistanceModel, SoundCone, Spatial};
+    use crate::buffer::SamplesBuffer;
+    use crate::math::nz;
+    use crate::source::{HrirSet, SineWave, UniformSourceIterator};
+    use crate::{Float, Source};
+    use std::mem;
+    use std::time::Duration;
+
+    fn impulse() -> SamplesBuffer {
+        let mut samples = vec![0.0; 256];
+        samples[0] = 1.0;
+        SamplesBuffer::new(nz!(1), nz!(48000), samples)
+    }
+
+    #[test]
+    fn binaural_favours_the_near_ear() {
+        let mut spatial = Spatial::new(
+            impulse(),
+            [1.0, 0.0, 0.0],
+            [-0.1, 0.0, 0.0],
+            [0.1, 0.0, 0.0],
+        );
+        spatial.set_hrirs(Some(HrirSet::spherical_head()));
+        let output: Vec<_> = spatial.collect();
+        assert_eq!(output.len(), 512);
+        let energy = |channel: usize| -> Float {
+            output.iter().skip(channel).step_by(2).map(|s| s * s).sum()
+        };
+        assert!(energy(1) > 2.0 * energy(0));
+    }
+
+    #[test]
+    fn switching_mid_frame_keeps_channels() {
+        let source = SamplesBuffer::new(nz!(1), nz!(48000), vec![1.0; 256]);
+        let mut spatial = Spatial::new(source, [1.0, 0.0, 0.0], [-0.1, 0.0, 0.0], [0.1, 0.0, 0.0]);
+        spatial.next();
+        spatial.set_hrirs(Some(HrirSet::spherical_head()));
+        // The right sample of the first frame still comes from the panning.
+        spatial.next();
+        let output: Vec<_> = spatial.collect();
+        assert_eq!(output.len(), 510);
+        // The right ear hears the onset first.
+        let onset = output.iter().position(|s| s.abs() > 0.01);
+        assert_eq!(onset.map(|idx| idx % 2), Some(1));
+    }
+
+    #[test]
+    fn distance_models() {
+        let inverse = DistanceModel::inverse().with_max_distance(10.0);
+        assert_eq!(inverse.gain(0.5), 1.0);
+        assert!((inverse.gain(2.0) - 0.5).abs() < 1e-6);
+        assert!((inverse.gain(20.0) - 0.1).abs() < 1e-6);
+
+        let linear = DistanceModel::linear(11.0);
+        assert!((linear.gain(6.0) - 0.5).abs() < 1e-6);
+        assert_eq!(linear.gain(20.0), 0.0);
+
+        let exponential = DistanceModel::exponential().with_rolloff_factor(2.0);
+        assert!((exponential.gain(4.0) - 1.0 / 16.0).abs() < 1e-6);
+        assert_eq!(exponential.with_rolloff_factor(0.0).gain(4.0), 1.0);
+    }
+
+    #[test]
+    fn cone_gain() {
+        let cone = SoundCone {
+            direction: [0.0, 0.0, -1.0],
+            inner_angle: 90.0,
+            outer_angle: 270.0,
+            outer_gain: 0.2,
+        };
+        assert_eq!(cone.gain([0.0; 3], [0.0, 0.0, -5.0]), 1.0);
+        assert!((cone.gain([0.0; 3], [5.0, 0.0, 0.0]) - 0.6).abs() < 1e-4);
+        assert_eq!(cone.gain([0.0; 3], [0.0, 0.0, 5.0]), 0.2);
+    }
+
+    #[test]
+    fn distance_model_and_cone_set_volume() {
+        let source = SamplesBuffer::new(nz!(1), nz!(48000), vec![1.0; 4]);
+        let mut spatial = Spatial::new(source, [0.0, 0.0, -4.0], [-0.1, 0.0, 0.0], [0.1, 0.0, 0.0]);
+        spatial.set_distance_model(Some(DistanceModel::inverse()));
+        spatial.set_cone(Some(SoundCone {
+            direction: [0.0, 0.0, -1.0],
+            inner_angle: 90.0,
+            outer_angle: 180.0,
+            outer_gain: 0.5,
+        }));
+        // Straight ahead both ears are equally far: 0.75 from the panning.
+        let expected = 0.75 * 0.25 * 0.5;
+        assert!((spatial.next().unwrap() - expected).abs() < 1e-4);
+        assert!((spatial.next().unwrap() - expected).abs() < 1e-4);
+    }
+
+    #[test]
+    fn doppler_changes_sample_rate() {
+        let source = SamplesBuffer::new(nz!(1), nz!(48000), vec![0.0; 4096]);
+        let mut spatial = Spatial::new(source, [0.0, 0.0, -4.0], [-0.1, 0.0, 0.0], [0.1, 0.0, 0.0]);
+        assert_eq!(spatial.sample_rate(), nz!(48000));
+        // Without a velocity the spans are the input's.
+        assert_eq!(spatial.current_span_len(), Some(4096));
+
+        // Coming closer at a tenth of the speed of sound.
+        spatial.set_velocities([0.0, 0.0, 34.33], [0.0; 3]);
+        assert_eq!(spatial.sample_rate(), nz!(53333));
+        assert_eq!(spatial.current_span_len(), Some(1024));
+        spatial.next();
+        // The span keeps its length and pitch until it ends.
+        spatial.set_doppler_factor(0.0);
+        assert_eq!(spatial.sample_rate(), nz!(53333));
+        assert_eq!(spatial.current_span_len(), Some(1024));
+        spatial.by_ref().take(1023).count();
+        assert_eq!(spatial.sample_rate(), nz!(48000));
+        assert_eq!(spatial.current_span_len(), Some(4096));
+
+        // The listener moving away.
+        spatial.set_doppler_factor(1.0);
+        spatial.set_velocities([0.0; 3], [0.0, 0.0, 34.33]);
+        assert_eq!(spatial.sample_rate(), nz!(43200));
+    }
+
+    #[test]
+    fn doppler_changes_pitch_of_endless_sources() {
+        let mut spatial = Spatial::new(
+            SineWave::new(1000.0),
+            [0.0, 0.0, -4.0],
+            [-0.1, 0.0, 0.0],
+            [0.1, 0.0, 0.0],
+        );
+        // Passing by, then coming closer at a tenth of the speed of sound
+        // after 0.1 seconds.
+        spatial.set_velocities([34.33, 0.0, 0.0], [0.0; 3]);
+        let mut started = false;
+        let spatial = spatial.periodic_access(Duration::from_millis(100), move |spatial| {
+            if mem::replace(&mut started, true) {
+                spatial.set_velocities([0.0, 0.0, 34.33], [0.0; 3]);
+            }
+        });
+        let mut output = UniformSourceIterator::new(spatial, nz!(2), nz!(48000));
+        // Zero crossings of the left channel in 0.1 seconds.
+        let crossings = |output: &mut UniformSourceIterator<_>| {
+            let left: Vec<_> = output.by_ref().take(9600).step_by(2).collect();
+            left.windows(2)
+                .filter(|w| (w[0] < 0.0) != (w[1] < 0.0))
+                .count()
+        };
+        assert!((198..=202).contains(&crossings(&mut output)));
+        // The pitch rises by 11% once the span ends.
+        output.by_ref().take(2048).count();
+        assert!((220..=224).contains(&crossings(&mut output)));
+    }
+
+    #[test]
+    fn air_absorption_muffles_distant_sources() {
+        let play = |air_absorption| {
+            let samples = (0..512).map(|i| if i % 4 < 2 { 1.0 } else { -1.0 });
+            let source = SamplesBuffer::new(nz!(1), nz!(48000), samples.collect::<Vec<_>>());
+            let mut spatial = Spatial::new(
+                source,
+                [0.0, 0.0, -200.0],
+                [-0.1, 0.0, 0.0],
+                [0.1, 0.0, 0.0],
+            );
+            spatial.set_distance_model(Some(DistanceModel::inverse().with_rolloff_factor(0.0)));
+            spatial.set_air_absorption(air_absorption);
+            spatial.skip(256).map(|s| s.abs()).sum::<Float>()
+        };
+        assert!(play(1.0) < 0.5 * play(0.0));
     }
 }
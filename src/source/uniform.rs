@@ -1,10 +1,10 @@
 use std::cmp;
 use std::time::Duration;
 
-use super::SeekError;
+use super::{fill_buffer_from_next, SeekError};
 use crate::common::{ChannelCount, SampleRate};
 use crate::conversions::{ChannelCountConverter, SampleRateConverter};
-use crate::Source;
+use crate::{Sample, Source};
 
 /// An iterator that reads from a `Source` and converts the samples to a
 /// specific type, sample-rate and channels count.
@@ -20,6 +20,8 @@ where
     target_channels: ChannelCount,
     target_sample_rate: SampleRate,
     total_duration: Option<Duration>,
+    /// Input samples of a block that needs channel conversion.
+    scratch: Vec<Sample>,
 }
 
 impl<I> UniformSourceIterator<I>
@@ -42,9 +44,20 @@ where
             target_channels,
             target_sample_rate,
             total_duration,
+            scratch: Vec::new(),
         }
     }
 
+    /// Returns the inner source. Samples buffered for conversion are lost.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.inner
+            .expect("only None while switching spans in next()")
+            .into_inner()
+            .into_inner()
+            .iter
+    }
+
     #[inline]
     fn bootstrap(
         input: I,
@@ -119,6 +132,29 @@ where
         self.total_duration
     }
 
+    #[inline]
+    fn fill_buffer(&mut self, buffer: &mut [Sample]) -> usize {
+        let inner = self
+            .inner
+            .as_mut()
+            .expect("only None while switching spans in next()");
+        // Only channel conversion can be done in blocks, resampling is done per sample.
+        let written = if inner.inner_mut().is_passthrough() {
+            inner.fill_buffer(buffer, &mut self.scratch, |resampler, buffer| {
+                resampler.inner_mut().fill_buffer(buffer)
+            })
+        } else {
+            None
+        };
+
+        match written {
+            // The span ended, `next` switches to the next one.
+            Some(0) if !buffer.is_empty() => fill_buffer_from_next(self, &mut buffer[..1]),
+            Some(written) => written,
+            None => fill_buffer_from_next(self, buffer),
+        }
+    }
+
     #[inline]
     fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
         if let Some(input) = self.inner.as_mut() {
@@ -142,6 +178,24 @@ impl<I> Take<I> {
     }
 }
 
+impl<I> Take<I>
+where
+    I: Source,
+{
+    #[inline]
+    fn fill_buffer(&mut self, buffer: &mut [Sample]) -> usize {
+        match &mut self.n {
+            Some(n) => {
+                let len = buffer.len().min(*n);
+                let written = self.iter.fill_buffer(&mut buffer[..len]);
+                *n -= written;
+                written
+            }
+            None => self.iter.fill_buffer(buffer),
+        }
+    }
+}
+
 impl<I> Iterator for Take<I>
 where
     I: Iterator,
@@ -4,8 +4,8 @@ use std::time::Duration;
 
 use dasp_sample::FromSample;
 
-use crate::mixer::Mixer;
-use crate::source::{SeekError, Spatial};
+use crate::mixer::{AuxSend, Bus, Mixer};
+use crate::source::{DistanceModel, HrirSet, SeekError, SoundCone, Spatial};
 use crate::{Float, Player, Source};
 
 /// A sink that allows changing the position of the source and the listeners
@@ -20,6 +20,27 @@ struct SoundPositions {
     emitter_position: [f32; 3],
     left_ear: [f32; 3],
     right_ear: [f32; 3],
+    up: [f32; 3],
+    hrirs: Option<HrirSet>,
+    distance_model: Option<DistanceModel>,
+    cone: Option<SoundCone>,
+    emitter_velocity: [f32; 3],
+    listener_velocity: [f32; 3],
+    doppler_factor: f32,
+    air_absorption: f32,
+}
+
+impl SoundPositions {
+    fn apply<I: Source>(&self, spatial: &mut Spatial<I>) {
+        spatial.set_up_direction(self.up);
+        spatial.set_hrirs(self.hrirs.clone());
+        spatial.set_distance_model(self.distance_model);
+        spatial.set_cone(self.cone);
+        spatial.set_velocities(self.emitter_velocity, self.listener_velocity);
+        spatial.set_doppler_factor(self.doppler_factor);
+        spatial.set_air_absorption(self.air_absorption);
+        spatial.set_positions(self.emitter_position, self.left_ear, self.right_ear);
+    }
 }
 
 impl SpatialPlayer {
@@ -36,6 +57,14 @@ impl SpatialPlayer {
                 emitter_position,
                 left_ear,
                 right_ear,
+                up: [0.0, 1.0, 0.0],
+                hrirs: None,
+                distance_model: None,
+                cone: None,
+                emitter_velocity: [0.0; 3],
+                listener_velocity: [0.0; 3],
+                doppler_factor: 1.0,
+                air_absorption: 0.0,
             })),
         }
     }
@@ -55,6 +84,53 @@ impl SpatialPlayer {
         self.positions.lock().unwrap().right_ear = pos;
     }
 
+    /// Sets which way is up for the listener. See [`Spatial::set_up_direction`].
+    pub fn set_up_direction(&self, up: [f32; 3]) {
+        self.positions.lock().unwrap().up = up;
+    }
+
+    /// Renders the sounds binaurally with `hrirs`, or with simple stereo
+    /// panning if `None`. See [`Spatial::set_hrirs`].
+    ///
+    /// Applies to sounds already in the queue too.
+    pub fn set_hrirs(&self, hrirs: Option<HrirSet>) {
+        self.positions.lock().unwrap().hrirs = hrirs;
+    }
+
+    /// Sets how the volume falls off with distance. See
+    /// [`Spatial::set_distance_model`].
+    pub fn set_distance_model(&self, model: Option<DistanceModel>) {
+        self.positions.lock().unwrap().distance_model = model;
+    }
+
+    /// Makes the emitter directional, or omnidirectional again if `None`.
+    pub fn set_cone(&self, cone: Option<SoundCone>) {
+        self.positions.lock().unwrap().cone = cone;
+    }
+
+    /// Sets the velocity of the sound emitter in metres per second, for the
+    /// Doppler effect. See [`Spatial::set_velocities`].
+    pub fn set_emitter_velocity(&self, velocity: [f32; 3]) {
+        self.positions.lock().unwrap().emitter_velocity = velocity;
+    }
+
+    /// Sets the velocity of the listener in metres per second, for the
+    /// Doppler effect. See [`Spatial::set_velocities`].
+    pub fn set_listener_velocity(&self, velocity: [f32; 3]) {
+        self.positions.lock().unwrap().listener_velocity = velocity;
+    }
+
+    /// Scales the Doppler effect. See [`Spatial::set_doppler_factor`].
+    pub fn set_doppler_factor(&self, factor: f32) {
+        self.positions.lock().unwrap().doppler_factor = factor;
+    }
+
+    /// Sets how much the air muffles distant sounds. See
+    /// [`Spatial::set_air_absorption`].
+    pub fn set_air_absorption(&self, factor: f32) {
+        self.positions.lock().unwrap().air_absorption = factor;
+    }
+
     /// Appends a sound to the queue of sounds to play.
     #[inline]
     pub fn append<S>(&self, source: S)
@@ -64,15 +140,15 @@ impl SpatialPlayer {
     {
         let positions = self.positions.clone();
         let pos_lock = self.positions.lock().unwrap();
-        let source = Spatial::new(
+        let mut source = Spatial::new(
             source,
             pos_lock.emitter_position,
             pos_lock.left_ear,
             pos_lock.right_ear,
-        )
-        .periodic_access(Duration::from_millis(10), move |i| {
-            let pos = positions.lock().unwrap();
-            i.set_positions(pos.emitter_position, pos.left_ear, pos.right_ear);
+        );
+        pos_lock.apply(&mut source);
+        let source = source.periodic_access(Duration::from_millis(10), move |i| {
+            positions.lock().unwrap().apply(i);
         });
         self.player.append(source);
     }
@@ -95,6 +171,13 @@ impl SpatialPlayer {
         self.player.set_volume(value);
     }
 
+    /// Sends a copy of the sound to `bus`, multiplied by `level`. See
+    /// [`Player::send_to`].
+    #[inline]
+    pub fn send_to(&self, bus: &Bus, level: Float) -> AuxSend {
+        self.player.send_to(bus, level)
+    }
+
     /// Changes the play speed of the sound. Does not adjust the samples, only the playback speed.
     ///
     /// # Note:
@@ -108,7 +108,7 @@ use crate::common::assert_error_traits;
 mod builder;
 mod config;
 
-pub use builder::SpeakersBuilder;
+pub use builder::{PlayError, QueueSink, SinkHandle, SpeakersBuilder};
 pub use config::OutputConfig;
 
 /// Error that can occur when we can not list the output devices
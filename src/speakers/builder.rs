@@ -1,4 +1,4 @@
-use std::{fmt::Debug, marker::PhantomData};
+use std::{fmt::Debug, marker::PhantomData, sync::Arc};
 
 use cpal::{
     traits::{DeviceTrait, HostTrait, StreamTrait},
@@ -6,8 +6,11 @@ use cpal::{
 };
 
 use crate::{
-    common::assert_error_traits, speakers::config::OutputConfig, ChannelCount, DeviceSinkError,
-    FixedSource, MixerDeviceSink, SampleRate,
+    common::assert_error_traits,
+    fixed_source::{self, FixedConfig, FixedQueue},
+    speakers::config::OutputConfig,
+    stream::ReconnectSettings,
+    ChannelCount, DeviceSinkError, FixedSource, MixerDeviceSink, SampleRate,
 };
 
 /// Error configuring or opening speakers output
@@ -62,6 +65,7 @@ where
     device: Option<(cpal::Device, Vec<SupportedStreamConfigRange>)>,
     config: Option<super::config::OutputConfig>,
     error_callback: E,
+    reconnect: Option<ReconnectSettings>,
 
     device_set: PhantomData<Device>,
     config_set: PhantomData<Config>,
@@ -91,6 +95,7 @@ impl Default for SpeakersBuilder<DeviceNotSet, ConfigNotSet> {
             device: None,
             config: None,
             error_callback: default_error_callback,
+            reconnect: None,
 
             device_set: PhantomData,
             config_set: PhantomData,
@@ -148,6 +153,7 @@ where
             device: Some((device, supported_configs)),
             config: self.config,
             error_callback: self.error_callback.clone(),
+            reconnect: self.reconnect.clone(),
             device_set: PhantomData,
             config_set: PhantomData,
         })
@@ -178,10 +184,33 @@ where
             device: Some((default_device, supported_configs)),
             config: self.config,
             error_callback: self.error_callback.clone(),
+            reconnect: self.reconnect.clone(),
             device_set: PhantomData,
             config_set: PhantomData,
         })
     }
+
+    /// Keeps playing when the output device is lost by moving to another
+    /// device. Optionally follows changes of the OS default output device.
+    /// Only applies to [`open_mixer`](SpeakersBuilder::open_mixer).
+    ///
+    /// See [`ReconnectSettings`] for the details.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use rodio::speakers::SpeakersBuilder;
+    /// # use rodio::stream::ReconnectSettings;
+    /// let speakers = SpeakersBuilder::new()
+    ///     .reconnect(ReconnectSettings::default())
+    ///     .default_device()?
+    ///     .default_config()?
+    ///     .open_mixer()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn reconnect(mut self, settings: ReconnectSettings) -> Self {
+        self.reconnect = Some(settings);
+        self
+    }
 }
 
 impl<Config, E> SpeakersBuilder<DeviceIsSet, Config, E>
@@ -225,6 +254,7 @@ where
             device: self.device.clone(),
             config: Some(config),
             error_callback: self.error_callback.clone(),
+            reconnect: self.reconnect.clone(),
             device_set: PhantomData,
             config_set: PhantomData,
         })
@@ -257,6 +287,7 @@ where
             device: self.device.clone(),
             config: Some(config),
             error_callback: self.error_callback.clone(),
+            reconnect: self.reconnect.clone(),
             device_set: PhantomData,
             config_set: PhantomData,
         })
@@ -310,6 +341,7 @@ where
             device: self.device.clone(),
             config: Some(new_config),
             error_callback: self.error_callback.clone(),
+            reconnect: self.reconnect.clone(),
             device_set: PhantomData,
             config_set: PhantomData,
         })
@@ -361,6 +393,7 @@ where
             device: self.device.clone(),
             config: Some(final_config),
             error_callback: self.error_callback.clone(),
+            reconnect: self.reconnect.clone(),
             device_set: PhantomData,
             config_set: PhantomData,
         }
@@ -389,6 +422,7 @@ where
             device: self.device.clone(),
             config: Some(new_config),
             error_callback: self.error_callback.clone(),
+            reconnect: self.reconnect.clone(),
             device_set: PhantomData,
             config_set: PhantomData,
         })
@@ -451,6 +485,7 @@ where
             device: self.device.clone(),
             config: Some(new_config),
             error_callback: self.error_callback.clone(),
+            reconnect: self.reconnect.clone(),
             device_set: PhantomData,
             config_set: PhantomData,
         })
@@ -548,13 +583,54 @@ where
         let device = self.device.as_ref().expect("DeviceIsSet").0.clone();
         let config = *self.config.as_ref().expect("ConfigIsSet");
         let error_callback = self.error_callback.clone();
-        crate::stream::MixerDeviceSink::open(&device, &config.into_cpal_config(), error_callback)
+        crate::stream::MixerDeviceSink::open(
+            &device,
+            &config.into_cpal_config(),
+            error_callback,
+            self.reconnect.clone(),
+        )
     }
 
-    // TODO
-    // pub fn open_queue() -> Result<QueueSink, DeviceSinkError> {
-    //     todo!()
-    // }
+    /// Opens the OS-Sink and provides a queue that plays `FixedSource`s in the
+    /// device format one after the other.
+    ///
+    /// The queue plays silence while empty. Sources must have the channel count
+    /// and sample rate of the configuration, see [`Source::into_fixed`](crate::Source::into_fixed).
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use rodio::speakers::SpeakersBuilder;
+    /// # use rodio::{Source, source::SineWave};
+    /// # use std::time::Duration;
+    /// let speakers = SpeakersBuilder::new()
+    ///     .default_device()?
+    ///     .default_config()?
+    ///     .open_queue()?;
+    /// let beep = SineWave::new(440.)
+    ///     .take_duration(Duration::from_secs(1))
+    ///     .into_fixed(speakers.config());
+    /// speakers.queue().append(beep)?;
+    /// std::thread::sleep(Duration::from_secs(1));
+    ///
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn open_queue(&self) -> Result<QueueSink, PlayError> {
+        let config = self.config.expect("ConfigIsSet");
+        let (queue, output) = fixed_source::queue(config.into(), true);
+        let builder = SpeakersBuilder::<DeviceIsSet, ConfigIsSet, E> {
+            device: self.device.clone(),
+            config: self.config,
+            error_callback: self.error_callback.clone(),
+            reconnect: self.reconnect.clone(),
+            device_set: PhantomData,
+            config_set: PhantomData,
+        };
+        let handle = builder.play(output)?;
+        Ok(QueueSink {
+            queue,
+            _handle: handle,
+        })
+    }
 
     /// Open the device with the current configuration and play a single
     /// `FixedSource` on it.
@@ -628,8 +704,34 @@ where
     }
 }
 
-// TODO cant introduce till we have introduced the other fixed source parts
-// pub struct QueueSink;
+/// A queue of `FixedSource`s playing on an OS-Sink, opened with
+/// [`SpeakersBuilder::open_queue`]. When this is dropped the queue stops
+/// playing.
+pub struct QueueSink {
+    queue: Arc<FixedQueue>,
+    _handle: SinkHandle,
+}
+
+impl QueueSink {
+    /// Returns the queue to append sources to.
+    pub fn queue(&self) -> &Arc<FixedQueue> {
+        &self.queue
+    }
+
+    /// Returns the format sources must have to be queued.
+    pub fn config(&self) -> FixedConfig {
+        self.queue.config()
+    }
+}
+
+impl Debug for QueueSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QueueSink")
+            .field("config", &self.config())
+            .field("queued", &self.queue.len())
+            .finish()
+    }
+}
 
 /// A sink handle. When this is dropped anything playing through this Sink will
 /// stop playing.
@@ -637,20 +739,28 @@ pub struct SinkHandle {
     _stream: cpal::Stream,
 }
 
+/// Error playing a `FixedSource` on an OS-Sink.
 #[derive(Debug, thiserror::Error)]
 pub enum PlayError {
+    /// The source does not have the channel count of the OS-Sink.
     #[error("DeviceSink channel count ({sink}) does not match the source channel count ({fixed_source})")]
     WrongChannelCount {
+        /// Channel count of the OS-Sink.
         sink: ChannelCount,
+        /// Channel count of the source.
         fixed_source: ChannelCount,
     },
+    /// The source does not have the sample rate of the OS-Sink.
     #[error(
         "DeviceSink sample rate ({sink}) does not match the source sample rate ({fixed_source})"
     )]
     WrongSampleRate {
+        /// Sample rate of the OS-Sink.
         sink: SampleRate,
+        /// Sample rate of the source.
         fixed_source: SampleRate,
     },
+    /// The OS-Sink could not be opened.
     #[error(transparent)]
     DeviceSink(#[from] crate::DeviceSinkError),
 }
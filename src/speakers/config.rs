@@ -1,6 +1,8 @@
 use std::num::NonZero;
 
-use crate::{math::nz, stream::DeviceSinkConfig, ChannelCount, SampleRate};
+use crate::{
+    fixed_source::FixedConfig, math::nz, stream::DeviceSinkConfig, ChannelCount, SampleRate,
+};
 
 /// Describes the output stream's configuration
 #[derive(Copy, Clone, Debug)]
@@ -71,6 +73,12 @@ impl From<cpal::SupportedStreamConfig> for OutputConfig {
     }
 }
 
+impl From<OutputConfig> for FixedConfig {
+    fn from(value: OutputConfig) -> Self {
+        FixedConfig::new(value.channel_count, value.sample_rate)
+    }
+}
+
 impl Default for OutputConfig {
     fn default() -> Self {
         Self {
@@ -12,7 +12,6 @@
 //!
 
 use core::fmt;
-use std::slice::Iter as SliceIter;
 use std::time::Duration;
 
 use crate::common::{ChannelCount, SampleRate};
@@ -23,7 +22,9 @@ use crate::{Sample, Source};
 /// A buffer of samples treated as a source.
 #[derive(Clone)]
 pub struct StaticSamplesBuffer {
-    data: SliceIter<'static, Sample>,
+    data: &'static [Sample],
+    pos: usize,
+    reversed: bool,
     channels: ChannelCount,
     sample_rate: SampleRate,
     duration: Duration,
@@ -63,12 +64,26 @@ impl StaticSamplesBuffer {
         );
 
         StaticSamplesBuffer {
-            data: data.iter(),
+            data,
+            pos: 0,
+            reversed: false,
             channels,
             sample_rate,
             duration,
         }
     }
+
+    /// Plays the buffer backwards, frame by frame, from the start.
+    ///
+    /// Unlike [`Source::reverse`] this does not copy the samples. Calling it again plays
+    /// the buffer forwards.
+    pub fn reversed(mut self) -> Self {
+        let channels = self.channels.get() as usize;
+        self.data = &self.data[..self.data.len() / channels * channels];
+        self.pos = 0;
+        self.reversed = !self.reversed;
+        self
+    }
 }
 
 impl Source for StaticSamplesBuffer {
@@ -105,12 +120,23 @@ impl Iterator for StaticSamplesBuffer {
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        self.data.next().cloned()
+        if self.pos >= self.data.len() {
+            return None;
+        }
+        let index = if self.reversed {
+            let channels = self.channels.get() as usize;
+            self.data.len() - channels - self.pos / channels * channels + self.pos % channels
+        } else {
+            self.pos
+        };
+        self.pos += 1;
+        Some(self.data[index])
     }
 
     #[inline]
     fn size_hint(&self) -> (usize, Option<usize>) {
-        self.data.size_hint()
+        let remaining = self.data.len() - self.pos;
+        (remaining, Some(remaining))
     }
 }
 
@@ -144,4 +170,19 @@ mod tests {
         assert_eq!(buf.next(), Some(6.0));
         assert_eq!(buf.next(), None);
     }
+
+    #[test]
+    fn reversed() {
+        let buf =
+            StaticSamplesBuffer::new(nz!(2), nz!(44100), &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0]);
+        let buf = buf.reversed();
+        assert_eq!(
+            buf.clone().collect::<Vec<_>>(),
+            [5.0, 6.0, 3.0, 4.0, 1.0, 2.0]
+        );
+        assert_eq!(
+            buf.reversed().collect::<Vec<_>>(),
+            [1.0, 2.0, 3.0, 4.0, 5.0, 6.0]
+        );
+    }
 }
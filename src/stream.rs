@@ -6,16 +6,31 @@
 //! There is also a convenience function `play` for using that output mixer to
 //! play a single sound.
 use crate::common::{assert_error_traits, ChannelCount, SampleRate};
-use crate::math::nz;
+use crate::decoder;
+use crate::math::{self, nz};
 use crate::mixer::{mixer, Mixer};
 use crate::player::Player;
-use crate::{decoder, Source};
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use cpal::{BufferSize, Sample, SampleFormat, StreamConfig, I24};
+use cpal::traits::{DeviceTrait, HostTrait};
+use cpal::{BufferSize, SampleFormat, StreamConfig};
 use std::fmt;
 use std::io::{Read, Seek};
 use std::marker::Sync;
 use std::num::NonZero;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "crossbeam-channel")]
+use crossbeam_channel::unbounded as channel;
+#[cfg(not(feature = "crossbeam-channel"))]
+use std::sync::mpsc::channel;
+
+use self::device_output::{DeviceOutput, ErrorCallback};
+
+mod device_output;
+mod reconnect;
+
+pub use self::reconnect::{DeviceEvent, ReconnectSettings, SwitchReason};
 
 const HZ_44100: SampleRate = nz!(44_100);
 
@@ -45,8 +60,9 @@ const HZ_44100: SampleRate = nz!(44_100);
 pub struct MixerDeviceSink {
     config: DeviceSinkConfig,
     mixer: Mixer,
+    output_clock: OutputClock,
+    output: Arc<DeviceOutput>,
     log_on_drop: bool,
-    _stream: cpal::Stream,
 }
 
 impl MixerDeviceSink {
@@ -56,10 +72,115 @@ impl MixerDeviceSink {
     }
 
     /// Access the sink's config.
+    ///
+    /// This is the config of the [mixer](Self::mixer). It can differ from the
+    /// config of the device, see [`device_config`](Self::device_config).
     pub fn config(&self) -> &DeviceSinkConfig {
         &self.config
     }
 
+    /// The device currently playing this sink's mixer.
+    ///
+    /// This changes when the stream moves to another device, see
+    /// [`DeviceSinkBuilder::with_reconnect`].
+    pub fn device(&self) -> cpal::Device {
+        self.output.device()
+    }
+
+    /// The config of the device currently playing this sink's mixer. When it
+    /// differs from [`config`](Self::config) the mixer output is converted.
+    pub fn device_config(&self) -> DeviceSinkConfig {
+        self.output.config()
+    }
+
+    /// Moves playback to another output device.
+    ///
+    /// Everything playing on the [mixer](Self::mixer) continues on the new
+    /// device, [`Player`]s and sources are not interrupted. The device is
+    /// opened with this sink's config if it supports that, otherwise with its
+    /// default config. In that case the mixer output is converted to the
+    /// device's channel count and sample rate.
+    ///
+    /// # Errors
+    /// When the new device can not be opened playback continues on the
+    /// current device and the error is returned.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use rodio::DeviceSinkBuilder;
+    /// # use rodio::cpal::traits::HostTrait;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let handle = DeviceSinkBuilder::open_default_sink()?;
+    /// // ... the user picks another device in the settings screen
+    /// let device = rodio::cpal::default_host()
+    ///     .output_devices()?
+    ///     .nth(1)
+    ///     .expect("a second output device");
+    /// handle.switch_device(device)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn switch_device(&self, device: cpal::Device) -> Result<(), DeviceSinkError> {
+        let config = reconnect::config_for_device(&device, &self.config)?;
+        self.output.switch_to(&device, &config)
+    }
+
+    /// Moves playback to another output device, opening it with `config`.
+    ///
+    /// Like [`switch_device`](Self::switch_device) the mixer output is
+    /// converted when `config` differs from this sink's config. Only the
+    /// channel count and sample rate are converted, the sample format and
+    /// buffer size are passed to the device as is.
+    pub fn switch_device_with_config(
+        &self,
+        device: cpal::Device,
+        config: DeviceSinkConfig,
+    ) -> Result<(), DeviceSinkError> {
+        Self::validate_config(&config);
+        self.output.switch_to(&device, &config)
+    }
+
+    /// Moves playback to another speaker output. See
+    /// [`switch_device`](Self::switch_device) for details.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use rodio::speakers::{available_outputs, SpeakersBuilder};
+    /// let speakers = SpeakersBuilder::new()
+    ///     .default_device()?
+    ///     .default_config()?
+    ///     .open_mixer()?;
+    /// let output = available_outputs()?.remove(1);
+    /// speakers.switch_output(output)?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    #[cfg(feature = "experimental")]
+    pub fn switch_output(&self, output: crate::speakers::Output) -> Result<(), DeviceSinkError> {
+        self.switch_device(output.into_inner())
+    }
+
+    /// Returns a clock reporting how much audio has been handed to the
+    /// device, when it is expected to be heard and the current latency.
+    ///
+    /// The clock can be cloned and read from any thread, use it to keep
+    /// video or game frames in sync with the audio.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use rodio::DeviceSinkBuilder;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let handle = DeviceSinkBuilder::open_default_sink()?;
+    /// let clock = handle.output_clock();
+    /// // ... later, in the render loop
+    /// let audio_position = clock.position();
+    /// println!("latency: {:?}", clock.latency());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn output_clock(&self) -> OutputClock {
+        self.output_clock.clone()
+    }
+
     /// When [`OS-Sink`] is dropped a message is logged to stderr or
     /// emitted through tracing if the tracing feature is enabled.
     pub fn log_on_drop(&mut self, enabled: bool) {
@@ -82,6 +203,183 @@ impl fmt::Debug for MixerDeviceSink {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.debug_struct("MixerDeviceSink")
             .field("config", &self.config)
+            .field("device_config", &self.device_config())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Reports the progress of the audio handed to an output device.
+///
+/// Obtained through [`MixerDeviceSink::output_clock`]. It is updated every
+/// time the device asks for more samples. Between those updates the played
+/// position is extrapolated from the last update using the wall clock.
+///
+/// All values are estimates based on the timestamps the OS reports. How
+/// accurate they are depends on the platform and audio backend.
+///
+/// Frames are counted at the sample rate of the device. Should playback move
+/// to a device with another sample rate the counts are rescaled so the
+/// position stays continuous.
+#[derive(Clone)]
+pub struct OutputClock {
+    timing: Arc<OutputTiming>,
+}
+
+// Written only by the audio callback. Instants are stored as nanoseconds
+// since `epoch` so they fit in atomics.
+struct OutputTiming {
+    epoch: Instant,
+    // Frames rendered before the most recent callback.
+    callback_start_frame: AtomicU64,
+    // Frames rendered including those of the most recent callback.
+    frames_rendered: AtomicU64,
+    // When the first frame of the most recent callback is predicted to
+    // be played.
+    playback_nanos: AtomicU64,
+    // Delay between the callback being called and its first frame being played.
+    output_latency_nanos: AtomicU64,
+    sample_rate: AtomicU32,
+}
+
+impl OutputClock {
+    fn new(sample_rate: SampleRate) -> Self {
+        Self {
+            timing: Arc::new(OutputTiming {
+                epoch: Instant::now(),
+                callback_start_frame: AtomicU64::new(0),
+                frames_rendered: AtomicU64::new(0),
+                playback_nanos: AtomicU64::new(0),
+                output_latency_nanos: AtomicU64::new(0),
+                sample_rate: AtomicU32::new(sample_rate.get()),
+            }),
+        }
+    }
+
+    // Called when playback moves to a device with a different sample rate.
+    fn set_sample_rate(&self, sample_rate: SampleRate) {
+        let timing = &self.timing;
+        let old = timing
+            .sample_rate
+            .swap(sample_rate.get(), Ordering::Relaxed) as u64;
+        let new = sample_rate.get() as u64;
+        if old == new {
+            return;
+        }
+        let rescale = |frames: &AtomicU64| {
+            let rescaled = frames.load(Ordering::Relaxed) as u128 * new as u128 / old as u128;
+            frames.store(rescaled as u64, Ordering::Relaxed);
+        };
+        rescale(&timing.callback_start_frame);
+        rescale(&timing.frames_rendered);
+    }
+
+    // Called from the audio callback with the number of frames it will write.
+    fn record_callback(&self, frames: u64, now: Instant, output_latency: Duration) {
+        let timing = &self.timing;
+        let callback_nanos = now.saturating_duration_since(timing.epoch).as_nanos() as u64;
+        let output_latency = output_latency.as_nanos() as u64;
+        let start_frame = timing.frames_rendered.load(Ordering::Relaxed);
+
+        timing
+            .callback_start_frame
+            .store(start_frame, Ordering::Relaxed);
+        timing
+            .playback_nanos
+            .store(callback_nanos + output_latency, Ordering::Relaxed);
+        timing
+            .output_latency_nanos
+            .store(output_latency, Ordering::Relaxed);
+        timing
+            .frames_rendered
+            .store(start_frame + frames, Ordering::Release);
+    }
+
+    /// Number of frames handed to the device so far. A frame is one sample
+    /// for every channel.
+    pub fn frames_rendered(&self) -> u64 {
+        self.timing.frames_rendered.load(Ordering::Acquire)
+    }
+
+    /// Estimated number of frames that have been played by the device at
+    /// this moment.
+    pub fn frames_played(&self) -> u64 {
+        self.frames_played_at(Instant::now(), self.frames_rendered())
+    }
+
+    /// Frames played at `now`, given a snapshot of the frames rendered.
+    fn frames_played_at(&self, now: Instant, rendered: u64) -> u64 {
+        let start_frame = self.timing.callback_start_frame.load(Ordering::Relaxed);
+        let start_playback = self.timing.epoch
+            + Duration::from_nanos(self.timing.playback_nanos.load(Ordering::Relaxed));
+
+        let Some(since_start) = now.checked_duration_since(start_playback) else {
+            // The first frame of the latest buffer has not been played yet.
+            let until_start = start_playback.duration_since(now);
+            return start_frame.saturating_sub(self.duration_to_frames(until_start));
+        };
+        (start_frame + self.duration_to_frames(since_start)).min(rendered)
+    }
+
+    /// Estimated position of the playback, the time of audio that has been
+    /// heard so far.
+    pub fn position(&self) -> Duration {
+        self.frames_to_duration(self.frames_played())
+    }
+
+    /// Estimated moment `frame` is, or was, played by the device.
+    ///
+    /// Returns `None` until the device has requested its first samples.
+    pub fn playback_instant(&self, frame: u64) -> Option<Instant> {
+        if self.frames_rendered() == 0 {
+            return None;
+        }
+        let start_frame = self.timing.callback_start_frame.load(Ordering::Relaxed);
+        let start_playback = self.timing.epoch
+            + Duration::from_nanos(self.timing.playback_nanos.load(Ordering::Relaxed));
+        if frame >= start_frame {
+            start_playback.checked_add(self.frames_to_duration(frame - start_frame))
+        } else {
+            start_playback.checked_sub(self.frames_to_duration(start_frame - frame))
+        }
+    }
+
+    /// Delay between the device requesting samples and it playing the first
+    /// of them, as reported by the OS.
+    pub fn output_latency(&self) -> Duration {
+        Duration::from_nanos(self.timing.output_latency_nanos.load(Ordering::Relaxed))
+    }
+
+    /// Estimated time between a sample being produced by rodio right now and
+    /// it being heard. This is the audio that is buffered but not yet played.
+    ///
+    /// Changes made through rodio (volume, pause, new sources) take at least
+    /// this long to become audible.
+    pub fn latency(&self) -> Duration {
+        // One snapshot, a callback in between must not make played exceed it.
+        let rendered = self.frames_rendered();
+        let buffered = rendered.saturating_sub(self.frames_played_at(Instant::now(), rendered));
+        self.frames_to_duration(buffered)
+    }
+
+    fn frames_to_duration(&self, frames: u64) -> Duration {
+        math::frames_to_duration(frames, self.sample_rate())
+    }
+
+    fn duration_to_frames(&self, duration: Duration) -> u64 {
+        math::duration_to_frames(duration, self.sample_rate())
+    }
+
+    fn sample_rate(&self) -> SampleRate {
+        let rate = self.timing.sample_rate.load(Ordering::Relaxed);
+        SampleRate::new(rate).expect("only ever set from a SampleRate")
+    }
+}
+
+impl fmt::Debug for OutputClock {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("OutputClock")
+            .field("frames_rendered", &self.frames_rendered())
+            .field("output_latency", &self.output_latency())
             .finish_non_exhaustive()
     }
 }
@@ -126,6 +424,17 @@ impl DeviceSinkConfig {
     pub fn sample_format(&self) -> SampleFormat {
         self.sample_format
     }
+
+    pub(crate) fn from_supported(config: &cpal::SupportedStreamConfig) -> Self {
+        Self {
+            channel_count: NonZero::new(config.channels())
+                .expect("no valid cpal config has zero channels"),
+            sample_rate: NonZero::new(config.sample_rate())
+                .expect("no valid cpal config has zero sample rate"),
+            sample_format: config.sample_format(),
+            ..Default::default()
+        }
+    }
 }
 
 impl core::fmt::Debug for DeviceSinkBuilder {
@@ -144,6 +453,7 @@ impl core::fmt::Debug for DeviceSinkBuilder {
         f.debug_struct("DeviceSinkBuilder")
             .field("device", &device)
             .field("config", &self.config)
+            .field("reconnect", &self.reconnect)
             .finish()
     }
 }
@@ -168,6 +478,7 @@ where
     device: Option<cpal::Device>,
     config: DeviceSinkConfig,
     error_callback: E,
+    reconnect: Option<ReconnectSettings>,
 }
 
 impl Default for DeviceSinkBuilder {
@@ -176,6 +487,7 @@ impl Default for DeviceSinkBuilder {
             device: None,
             config: DeviceSinkConfig::default(),
             error_callback: default_error_callback,
+            reconnect: None,
         }
     }
 }
@@ -309,14 +621,7 @@ where
         mut self,
         config: &cpal::SupportedStreamConfig,
     ) -> DeviceSinkBuilder<E> {
-        self.config = DeviceSinkConfig {
-            channel_count: NonZero::new(config.channels())
-                .expect("no valid cpal config has zero channels"),
-            sample_rate: NonZero::new(config.sample_rate())
-                .expect("no valid cpal config has zero sample rate"),
-            sample_format: config.sample_format(),
-            ..Default::default()
-        };
+        self.config = DeviceSinkConfig::from_supported(config);
         self
     }
 
@@ -342,14 +647,26 @@ where
             device: self.device,
             config: self.config,
             error_callback: callback,
+            reconnect: self.reconnect,
         }
     }
 
+    /// Keep playing when the output device is lost, for example because
+    /// headphones got unplugged, by moving the stream to another device.
+    /// Optionally also follows changes of the OS default output device.
+    ///
+    /// The error callback is still called for every stream error. See
+    /// [`ReconnectSettings`] for details and an example.
+    pub fn with_reconnect(mut self, settings: ReconnectSettings) -> DeviceSinkBuilder<E> {
+        self.reconnect = Some(settings);
+        self
+    }
+
     /// Open OS-Sink using parameters configured so far.
     pub fn open_stream(self) -> Result<MixerDeviceSink, DeviceSinkError> {
         let device = self.device.as_ref().expect("No output device specified");
 
-        MixerDeviceSink::open(device, &self.config, self.error_callback)
+        MixerDeviceSink::open(device, &self.config, self.error_callback, self.reconnect)
     }
 
     /// Try opening a new OS-Sink with the builder's current stream configuration.
@@ -363,14 +680,20 @@ where
         let device = self.device.as_ref().expect("No output device specified");
         let error_callback = &self.error_callback;
 
-        MixerDeviceSink::open(device, &self.config, error_callback.clone()).or_else(|err| {
+        MixerDeviceSink::open(
+            device,
+            &self.config,
+            error_callback.clone(),
+            self.reconnect.clone(),
+        )
+        .or_else(|err| {
             for supported_config in supported_output_configs(device)? {
-                if let Ok(handle) = DeviceSinkBuilder::default()
+                let mut builder = DeviceSinkBuilder::default()
                     .with_device(device.clone())
                     .with_supported_config(&supported_config)
-                    .with_error_callback(error_callback.clone())
-                    .open_stream()
-                {
+                    .with_error_callback(error_callback.clone());
+                builder.reconnect = self.reconnect.clone();
+                if let Ok(handle) = builder.open_stream() {
                     return Ok(handle);
                 }
             }
@@ -457,75 +780,50 @@ impl MixerDeviceSink {
     pub(crate) fn open<E>(
         device: &cpal::Device,
         config: &DeviceSinkConfig,
-        error_callback: E,
+        mut error_callback: E,
+        reconnect: Option<ReconnectSettings>,
     ) -> Result<MixerDeviceSink, DeviceSinkError>
     where
         E: FnMut(cpal::StreamError) + Send + 'static,
     {
         Self::validate_config(config);
         let (controller, source) = mixer(config.channel_count, config.sample_rate);
-        Self::init_stream(device, config, source, error_callback).and_then(|stream| {
-            stream.play().map_err(DeviceSinkError::PlayError)?;
-            Ok(Self {
-                _stream: stream,
-                mixer: controller,
-                config: *config,
-                log_on_drop: true,
-            })
-        })
-    }
-
-    fn init_stream<S, E>(
-        device: &cpal::Device,
-        config: &DeviceSinkConfig,
-        mut samples: S,
-        error_callback: E,
-    ) -> Result<cpal::Stream, DeviceSinkError>
-    where
-        S: Source + Send + 'static,
-        E: FnMut(cpal::StreamError) + Send + 'static,
-    {
-        let cpal_config = config.into();
-
-        macro_rules! build_output_streams {
-            ($($sample_format:tt, $generic:ty);+) => {
-                match config.sample_format {
-                    $(
-                        cpal::SampleFormat::$sample_format => device.build_output_stream::<$generic, _, _>(
-                            &cpal_config,
-                            move |data, _| {
-                                data.iter_mut().for_each(|d| {
-                                    *d = samples
-                                        .next()
-                                        .map(Sample::from_sample)
-                                        .unwrap_or(<$generic>::EQUILIBRIUM)
-                                })
-                            },
-                            error_callback,
-                            None,
-                        ),
-                    )+
-                    _ => return Err(DeviceSinkError::UnsupportedSampleFormat),
+        let output_clock = OutputClock::new(config.sample_rate);
+
+        let (lost_tx, lost_rx) = channel();
+        let error_callback: ErrorCallback = if reconnect.is_some() {
+            Arc::new(std::sync::Mutex::new(move |err: cpal::StreamError| {
+                let lost = matches!(
+                    err,
+                    cpal::StreamError::DeviceNotAvailable | cpal::StreamError::StreamInvalidated
+                );
+                error_callback(err);
+                if lost {
+                    let _ = lost_tx.send(());
                 }
-            };
-        }
+            }))
+        } else {
+            Arc::new(std::sync::Mutex::new(error_callback))
+        };
 
-        let result = build_output_streams!(
-            F32, f32;
-            F64, f64;
-            I8, i8;
-            I16, i16;
-            I24, I24;
-            I32, i32;
-            I64, i64;
-            U8, u8;
-            U16, u16;
-            U24, cpal::U24;
-            U32, u32;
-            U64, u64
-        );
+        let output = Arc::new(DeviceOutput::open(
+            device,
+            config,
+            source,
+            output_clock.clone(),
+            error_callback,
+        )?);
+        if let Some(settings) = reconnect {
+            reconnect::spawn_watcher(Arc::downgrade(&output), *config, settings, lost_rx);
+        }
 
-        result.map_err(DeviceSinkError::BuildError)
+        Ok(Self {
+            mixer: controller,
+            output_clock,
+            output,
+            config: *config,
+            log_on_drop: true,
+        })
     }
 }
 
@@ -551,3 +849,55 @@ pub fn supported_output_configs(
         formats
     }))
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::OutputClock;
+    use crate::math::nz;
+
+    #[test]
+    fn output_clock_extrapolates_between_callbacks() {
+        let clock = OutputClock::new(nz!(1000));
+        assert_eq!(clock.frames_rendered(), 0);
+        assert_eq!(clock.playback_instant(0), None);
+
+        let now = clock.timing.epoch;
+        clock.record_callback(100, now, Duration::from_millis(20));
+        clock.record_callback(
+            100,
+            now + Duration::from_millis(100),
+            Duration::from_millis(20),
+        );
+
+        assert_eq!(clock.frames_rendered(), 200);
+        assert_eq!(clock.output_latency(), Duration::from_millis(20));
+
+        // Frame 100 is played 20ms after the second callback.
+        let frame_100 = now + Duration::from_millis(120);
+        assert_eq!(clock.playback_instant(100), Some(frame_100));
+        assert_eq!(
+            clock.playback_instant(150),
+            Some(frame_100 + Duration::from_millis(50))
+        );
+        assert_eq!(clock.frames_played_at(frame_100, 200), 100);
+        assert_eq!(
+            clock.frames_played_at(frame_100 + Duration::from_millis(30), 200),
+            130
+        );
+        assert_eq!(
+            clock.frames_played_at(frame_100 - Duration::from_millis(10), 200),
+            90
+        );
+        // Never report more than has been rendered.
+        assert_eq!(
+            clock.frames_played_at(frame_100 + Duration::from_secs(1), 200),
+            200
+        );
+        assert_eq!(
+            clock.frames_played_at(frame_100 + Duration::from_secs(1), 150),
+            150
+        );
+    }
+}
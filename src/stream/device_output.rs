@@ -0,0 +1,302 @@
+//! Plays a mixer on an output device while allowing the device to be
+//! replaced without interrupting the mixer.
+
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Instant;
+
+use cpal::traits::{DeviceTrait, StreamTrait};
+use cpal::{FromSample, Sample, I24};
+
+use super::{DeviceSinkConfig, DeviceSinkError, OutputClock};
+use crate::common::{ChannelCount, SampleRate};
+use crate::mixer::{MixerSource, BLOCK_LEN};
+use crate::source::{Source, UniformSourceIterator};
+
+pub(crate) type ErrorCallback = Arc<Mutex<dyn FnMut(cpal::StreamError) + Send>>;
+
+/// The mixer output converted to the config of the device it plays on.
+enum MixerOutput {
+    Direct(MixerSource),
+    Converted(UniformSourceIterator<MixerSource>),
+}
+
+impl MixerOutput {
+    fn new(source: MixerSource, channels: ChannelCount, sample_rate: SampleRate) -> Self {
+        if source.channels() == channels && source.sample_rate() == sample_rate {
+            MixerOutput::Direct(source)
+        } else {
+            MixerOutput::Converted(UniformSourceIterator::new(source, channels, sample_rate))
+        }
+    }
+
+    fn into_mixer_source(self) -> MixerSource {
+        match self {
+            MixerOutput::Direct(source) => source,
+            MixerOutput::Converted(source) => source.into_inner(),
+        }
+    }
+
+    #[inline]
+    fn fill_buffer(&mut self, buffer: &mut [crate::Sample]) -> usize {
+        match self {
+            MixerOutput::Direct(source) => source.fill_buffer(buffer),
+            MixerOutput::Converted(source) => source.fill_buffer(buffer),
+        }
+    }
+
+    /// Writes the next samples to `data`, in blocks so the mixer can process
+    /// them together. Plays silence where the mixer has nothing to play.
+    fn write_to<T>(&mut self, data: &mut [T])
+    where
+        T: Sample + FromSample<crate::Sample>,
+    {
+        let mut block = [0.0; BLOCK_LEN];
+        for chunk in data.chunks_mut(BLOCK_LEN) {
+            let block = &mut block[..chunk.len()];
+            let mut filled = 0;
+            while filled < block.len() {
+                match self.fill_buffer(&mut block[filled..]) {
+                    0 => break,
+                    written => filled += written,
+                }
+            }
+            for (out, sample) in chunk.iter_mut().zip(&block[..filled]) {
+                *out = T::from_sample(*sample);
+            }
+            chunk[filled..].fill(T::EQUILIBRIUM);
+        }
+    }
+}
+
+// Shared between the stream callbacks. Only the callback of the stream whose
+// generation matches may pull samples, any other stream plays silence.
+struct Slot {
+    output: Option<MixerOutput>,
+    generation: u64,
+}
+
+/// Locks the slot outside the audio callback, where the callback must not be
+/// able to make us panic.
+fn lock(slot: &Mutex<Slot>) -> MutexGuard<'_, Slot> {
+    slot.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+struct Current {
+    // Kept so the stream keeps playing, dropping it stops the stream.
+    _stream: cpal::Stream,
+    device: cpal::Device,
+    config: DeviceSinkConfig,
+}
+
+/// Owns the stream playing the mixer and can move it to another device.
+pub(crate) struct DeviceOutput {
+    slot: Arc<Mutex<Slot>>,
+    current: Mutex<Current>,
+    clock: OutputClock,
+    error_callback: ErrorCallback,
+}
+
+impl DeviceOutput {
+    pub(crate) fn open(
+        device: &cpal::Device,
+        config: &DeviceSinkConfig,
+        source: MixerSource,
+        clock: OutputClock,
+        error_callback: ErrorCallback,
+    ) -> Result<Self, DeviceSinkError> {
+        let output = MixerOutput::new(source, config.channel_count, config.sample_rate);
+        let slot = Arc::new(Mutex::new(Slot {
+            output: Some(output),
+            generation: 0,
+        }));
+        let stream = build_stream(device, config, &slot, 0, &clock, error_callback.clone())?;
+        stream.play().map_err(DeviceSinkError::PlayError)?;
+
+        Ok(Self {
+            slot,
+            current: Mutex::new(Current {
+                _stream: stream,
+                device: device.clone(),
+                config: *config,
+            }),
+            clock,
+            error_callback,
+        })
+    }
+
+    /// The device the mixer is currently played on.
+    pub(crate) fn device(&self) -> cpal::Device {
+        self.current.lock().unwrap().device.clone()
+    }
+
+    /// The config of the device the mixer is currently played on.
+    pub(crate) fn config(&self) -> DeviceSinkConfig {
+        self.current.lock().unwrap().config
+    }
+
+    /// Starts playing the mixer on `device` and stops the current stream.
+    ///
+    /// If the new stream can not be started the mixer keeps playing on the
+    /// current device.
+    pub(crate) fn switch_to(
+        &self,
+        device: &cpal::Device,
+        config: &DeviceSinkConfig,
+    ) -> Result<(), DeviceSinkError> {
+        let generation = lock(&self.slot).generation + 1;
+        // Starts out silent as the slot still belongs to the current stream.
+        let stream = build_stream(
+            device,
+            config,
+            &self.slot,
+            generation,
+            &self.clock,
+            self.error_callback.clone(),
+        )?;
+        stream.play().map_err(DeviceSinkError::PlayError)?;
+
+        {
+            let mut slot = lock(&self.slot);
+            let source = slot
+                .output
+                .take()
+                .expect("slot is only empty while switching")
+                .into_mixer_source();
+            slot.output = Some(MixerOutput::new(
+                source,
+                config.channel_count,
+                config.sample_rate,
+            ));
+            slot.generation = generation;
+            self.clock.set_sample_rate(config.sample_rate);
+        }
+
+        // Dropping the old stream must happen without holding the slot lock,
+        // some hosts wait for a running callback to return.
+        let previous = std::mem::replace(
+            &mut *self.current.lock().unwrap(),
+            Current {
+                _stream: stream,
+                device: device.clone(),
+                config: *config,
+            },
+        );
+        drop(previous);
+        Ok(())
+    }
+}
+
+fn build_stream(
+    device: &cpal::Device,
+    config: &DeviceSinkConfig,
+    slot: &Arc<Mutex<Slot>>,
+    generation: u64,
+    clock: &OutputClock,
+    error_callback: ErrorCallback,
+) -> Result<cpal::Stream, DeviceSinkError> {
+    let cpal_config = config.into();
+    let channels = config.channel_count.get() as usize;
+
+    macro_rules! build_output_streams {
+        ($($sample_format:tt, $generic:ty);+) => {
+            match config.sample_format {
+                $(
+                    cpal::SampleFormat::$sample_format => {
+                        let error_slot = slot.clone();
+                        let slot = slot.clone();
+                        let clock = clock.clone();
+                        let error_callback = error_callback.clone();
+                        device.build_output_stream::<$generic, _, _>(
+                            &cpal_config,
+                            move |data: &mut [$generic], info: &cpal::OutputCallbackInfo| {
+                                // Never wait on the audio thread. The lock is
+                                // only held elsewhere while switching devices.
+                                let Ok(mut slot) = slot.try_lock() else {
+                                    data.fill(<$generic>::EQUILIBRIUM);
+                                    return;
+                                };
+                                let Slot { output, generation: active } = &mut *slot;
+                                let output = match output.as_mut() {
+                                    Some(output) if *active == generation => output,
+                                    _ => {
+                                        data.fill(<$generic>::EQUILIBRIUM);
+                                        return;
+                                    }
+                                };
+
+                                let timestamp = info.timestamp();
+                                clock.record_callback(
+                                    (data.len() / channels) as u64,
+                                    Instant::now(),
+                                    timestamp
+                                        .playback
+                                        .duration_since(&timestamp.callback)
+                                        .unwrap_or_default(),
+                                );
+                                output.write_to(data);
+                            },
+                            move |err| {
+                                // A stream that was switched away from may
+                                // still report errors, they no longer matter.
+                                if generation < lock(&error_slot).generation {
+                                    return;
+                                }
+                                (error_callback.lock().unwrap())(err)
+                            },
+                            None,
+                        )
+                    }
+                )+
+                _ => return Err(DeviceSinkError::UnsupportedSampleFormat),
+            }
+        };
+    }
+
+    let result = build_output_streams!(
+        F32, f32;
+        F64, f64;
+        I8, i8;
+        I16, i16;
+        I24, I24;
+        I32, i32;
+        I64, i64;
+        U8, u8;
+        U16, u16;
+        U24, cpal::U24;
+        U32, u32;
+        U64, u64
+    );
+
+    result.map_err(DeviceSinkError::BuildError)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MixerOutput;
+    use crate::buffer::SamplesBuffer;
+    use crate::math::nz;
+    use crate::mixer::mixer;
+
+    #[test]
+    fn converted_output_keeps_mixer() {
+        let (controller, source) = mixer(nz!(1), nz!(48_000));
+        controller.add(SamplesBuffer::new(
+            nz!(1),
+            nz!(48_000),
+            vec![1.0, 2.0, 3.0, 4.0],
+        ));
+
+        let mut output = MixerOutput::new(source, nz!(2), nz!(48_000));
+        assert!(matches!(output, MixerOutput::Converted(_)));
+        let mut data = [0.0f32; 2];
+        output.write_to(&mut data);
+        assert_eq!(data, [1.0, 1.0]);
+
+        // Back to the mixer's own config, the mixer carries on where it was.
+        let mut output = MixerOutput::new(output.into_mixer_source(), nz!(1), nz!(48_000));
+        assert!(matches!(output, MixerOutput::Direct(_)));
+        let mut data = [0i16; 4];
+        output.write_to(&mut data);
+        assert_eq!(data, [i16::MAX, i16::MAX, i16::MAX, 0]);
+    }
+}
@@ -0,0 +1,348 @@
+//! Keeps a [`MixerDeviceSink`](super::MixerDeviceSink) playing when its
+//! output device goes away or the OS default output device changes.
+
+use std::fmt;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use cpal::traits::{DeviceTrait, HostTrait};
+
+#[cfg(feature = "crossbeam-channel")]
+use crossbeam_channel::{Receiver, RecvTimeoutError};
+#[cfg(not(feature = "crossbeam-channel"))]
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+
+use super::device_output::DeviceOutput;
+use super::{DeviceSinkConfig, DeviceSinkError};
+
+type EventCallback = Arc<dyn Fn(DeviceEvent) + Send + Sync>;
+
+/// Configures how a sink recovers when its output device stops working.
+///
+/// Pass these to [`DeviceSinkBuilder::with_reconnect`](super::DeviceSinkBuilder::with_reconnect).
+/// When the device is lost the stream is rebuilt on another device. The
+/// [`Mixer`](crate::mixer::Mixer) stays the same, so every source and
+/// [`Player`](crate::Player) connected to it keeps playing.
+///
+/// The devices are tried in this order: the OS default output device (when
+/// [following the default](Self::with_follow_default_device)), then the
+/// [fallback devices](Self::with_fallback_devices) in the order given. As long
+/// as none of them can be opened this keeps retrying every
+/// [`poll_interval`](Self::with_poll_interval).
+///
+/// # Example
+/// ```no_run
+/// use rodio::stream::{DeviceEvent, ReconnectSettings};
+/// use rodio::DeviceSinkBuilder;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let settings = ReconnectSettings::default().with_event_callback(|event| {
+///     if let DeviceEvent::Switched { reason, .. } = event {
+///         println!("moved to another output device: {reason:?}");
+///     }
+/// });
+/// let handle = DeviceSinkBuilder::from_default_device()?
+///     .with_reconnect(settings)
+///     .open_stream()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct ReconnectSettings {
+    pub(crate) follow_default_device: bool,
+    pub(crate) fallback_devices: Vec<cpal::Device>,
+    pub(crate) poll_interval: Duration,
+    pub(crate) event_callback: Option<EventCallback>,
+}
+
+impl Default for ReconnectSettings {
+    fn default() -> Self {
+        Self {
+            follow_default_device: true,
+            fallback_devices: Vec::new(),
+            poll_interval: Duration::from_millis(500),
+            event_callback: None,
+        }
+    }
+}
+
+impl ReconnectSettings {
+    /// Whether to also move to the OS default output device whenever it
+    /// changes, for example when headphones are plugged in. When the device
+    /// is lost the default device is tried first.
+    ///
+    /// Enabled by default.
+    pub fn with_follow_default_device(mut self, follow: bool) -> Self {
+        self.follow_default_device = follow;
+        self
+    }
+
+    /// Devices to try, in order, when the current device is lost.
+    pub fn with_fallback_devices(
+        mut self,
+        devices: impl IntoIterator<Item = cpal::Device>,
+    ) -> Self {
+        self.fallback_devices = devices.into_iter().collect();
+        self
+    }
+
+    /// How often to check whether the default device changed and to retry
+    /// opening a device while none is available.
+    ///
+    /// Default is 500 milliseconds.
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Called from a background thread whenever playback moves to another
+    /// device or when no device could be opened.
+    pub fn with_event_callback<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(DeviceEvent) + Send + Sync + 'static,
+    {
+        self.event_callback = Some(Arc::new(callback));
+        self
+    }
+}
+
+impl fmt::Debug for ReconnectSettings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReconnectSettings")
+            .field("follow_default_device", &self.follow_default_device)
+            .field("fallback_devices", &self.fallback_devices.len())
+            .field("poll_interval", &self.poll_interval)
+            .field("event_callback", &self.event_callback.is_some())
+            .finish()
+    }
+}
+
+/// Reported through [`ReconnectSettings::with_event_callback`].
+#[non_exhaustive]
+pub enum DeviceEvent {
+    /// Playback moved to another device.
+    Switched {
+        /// The device now playing.
+        device: cpal::Device,
+        /// The config the new device is opened with. When it differs from the
+        /// mixer's config the mixer output is converted.
+        config: DeviceSinkConfig,
+        /// Why playback moved.
+        reason: SwitchReason,
+    },
+    /// The device was lost and no other device could be opened. Opening a
+    /// device is retried until one succeeds, this is reported once per loss.
+    NoDeviceAvailable,
+}
+
+impl fmt::Debug for DeviceEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeviceEvent::Switched {
+                device,
+                config,
+                reason,
+            } => f
+                .debug_struct("Switched")
+                .field("device", &device_name(device))
+                .field("config", config)
+                .field("reason", reason)
+                .finish(),
+            DeviceEvent::NoDeviceAvailable => f.write_str("NoDeviceAvailable"),
+        }
+    }
+}
+
+fn device_name(device: &cpal::Device) -> String {
+    device
+        .description()
+        .map_or("UnNamed".to_string(), |d| d.name().to_string())
+}
+
+/// Why playback moved to another device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SwitchReason {
+    /// The previous device stopped working, for example it was unplugged.
+    DeviceLost,
+    /// The OS default output device changed.
+    DefaultDeviceChanged,
+}
+
+/// Chooses the config to open `device` with. The mixer's own config is
+/// preferred so no conversion is needed, otherwise the device default is used.
+pub(crate) fn config_for_device(
+    device: &cpal::Device,
+    preferred: &DeviceSinkConfig,
+) -> Result<DeviceSinkConfig, DeviceSinkError> {
+    let supported = device
+        .supported_output_configs()
+        .map_err(DeviceSinkError::SupportedConfigsError)?
+        .any(|range| {
+            range.channels() == preferred.channel_count.get()
+                && range.sample_format() == preferred.sample_format
+                && (range.min_sample_rate()..=range.max_sample_rate())
+                    .contains(&preferred.sample_rate.get())
+        });
+    if supported {
+        return Ok(*preferred);
+    }
+
+    let default = device
+        .default_output_config()
+        .map_err(DeviceSinkError::DefaultSinkConfigError)?;
+    Ok(DeviceSinkConfig::from_supported(&default))
+}
+
+fn is_same_device(a: &cpal::Device, b: &cpal::Device) -> bool {
+    matches!((a.id(), b.id()), (Ok(a), Ok(b)) if a == b)
+}
+
+/// Spawns the thread that watches for lost devices and default device
+/// changes. It stops once the output is dropped.
+pub(crate) fn spawn_watcher(
+    output: Weak<DeviceOutput>,
+    preferred: DeviceSinkConfig,
+    settings: ReconnectSettings,
+    device_lost: Receiver<()>,
+) {
+    let spawned = std::thread::Builder::new()
+        .name("rodio-reconnect".to_owned())
+        .spawn(move || watch(output, preferred, settings, device_lost));
+    if let Err(err) = spawned {
+        #[cfg(feature = "tracing")]
+        tracing::error!("could not start device reconnection thread: {err}");
+        #[cfg(not(feature = "tracing"))]
+        eprintln!("could not start device reconnection thread: {err}");
+    }
+}
+
+fn watch(
+    output: Weak<DeviceOutput>,
+    preferred: DeviceSinkConfig,
+    settings: ReconnectSettings,
+    device_lost: Receiver<()>,
+) {
+    let mut lost = false;
+    // Whether `NoDeviceAvailable` was reported since the device was lost.
+    let mut reported_lost = false;
+    // Only a change of the default device makes us follow it. That way a
+    // device picked through `MixerDeviceSink::switch_device` is respected.
+    let mut last_default = default_device_id();
+    loop {
+        match device_lost.recv_timeout(settings.poll_interval) {
+            Ok(()) => lost = true,
+            Err(RecvTimeoutError::Timeout) => (),
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+        // Errors from the stream we are replacing can queue up.
+        while device_lost.try_recv().is_ok() {}
+
+        let Some(output) = output.upgrade() else {
+            return;
+        };
+
+        if lost {
+            lost = !reconnect(&output, &preferred, &settings);
+            if !lost {
+                reported_lost = false;
+            } else if !reported_lost {
+                notify(&settings, DeviceEvent::NoDeviceAvailable);
+                reported_lost = true;
+            }
+            last_default = default_device_id();
+        } else if settings.follow_default_device {
+            let default = default_device_id();
+            if default.is_some() && default != last_default {
+                follow_default(&output, &preferred, &settings);
+                last_default = default;
+            }
+        }
+    }
+}
+
+fn default_device_id() -> Option<cpal::DeviceId> {
+    cpal::default_host()
+        .default_output_device()
+        .and_then(|device| device.id().ok())
+}
+
+/// Returns whether playback moved to a working device.
+fn reconnect(
+    output: &DeviceOutput,
+    preferred: &DeviceSinkConfig,
+    settings: &ReconnectSettings,
+) -> bool {
+    let default = settings
+        .follow_default_device
+        .then(|| cpal::default_host().default_output_device())
+        .flatten();
+
+    for device in default.iter().chain(settings.fallback_devices.iter()) {
+        // Failures are expected here, the device might be gone as well.
+        if try_switch(
+            output,
+            device,
+            preferred,
+            settings,
+            SwitchReason::DeviceLost,
+        )
+        .is_ok()
+        {
+            return true;
+        }
+    }
+    false
+}
+
+fn follow_default(
+    output: &DeviceOutput,
+    preferred: &DeviceSinkConfig,
+    settings: &ReconnectSettings,
+) {
+    let Some(default) = cpal::default_host().default_output_device() else {
+        return;
+    };
+    if is_same_device(&default, &output.device()) {
+        return;
+    }
+    let switched = try_switch(
+        output,
+        &default,
+        preferred,
+        settings,
+        SwitchReason::DefaultDeviceChanged,
+    );
+    if let Err(err) = switched {
+        #[cfg(feature = "tracing")]
+        tracing::warn!("could not switch to new default output device: {err}");
+        #[cfg(not(feature = "tracing"))]
+        eprintln!("could not switch to new default output device: {err}");
+    }
+}
+
+fn try_switch(
+    output: &DeviceOutput,
+    device: &cpal::Device,
+    preferred: &DeviceSinkConfig,
+    settings: &ReconnectSettings,
+    reason: SwitchReason,
+) -> Result<(), DeviceSinkError> {
+    let config = config_for_device(device, preferred)?;
+    output.switch_to(device, &config)?;
+    notify(
+        settings,
+        DeviceEvent::Switched {
+            device: device.clone(),
+            config,
+            reason,
+        },
+    );
+    Ok(())
+}
+
+fn notify(settings: &ReconnectSettings, event: DeviceEvent) {
+    if let Some(callback) = &settings.event_callback {
+        callback(event);
+    }
+}
@@ -1,22 +1,31 @@
 use crate::common::assert_error_traits;
-use crate::Sample;
 use crate::Source;
-use dasp_sample::Sample as DaspSample;
-use hound::{SampleFormat, WavSpec};
-use std::io::{self, Write};
+use std::io;
 use std::path;
 use std::sync::Arc;
 
+mod metadata;
+mod sink;
+
+pub use self::metadata::{BroadcastExtension, WavMetadata};
+pub use self::sink::{WavOutputSettings, WavSampleFormat, WavWriterSink};
+
+/// Errors that can occur when writing a wav file.
 #[derive(Debug, thiserror::Error, Clone)]
 pub enum ToWavError {
+    /// The output file could not be created.
     #[error("Opening file for writing")]
     OpenFile(#[source] Arc<std::io::Error>),
+    /// The wav header could not be written.
     #[error("Could not create wav writer")]
     Creating(#[source] Arc<hound::Error>),
+    /// Samples could not be written.
     #[error("Failed to write samples writer")]
     Writing(#[source] Arc<hound::Error>),
+    /// The header could not be updated with the final length.
     #[error("Failed to update the wav header")]
     Finishing(#[source] Arc<hound::Error>),
+    /// Buffered bytes or the updated header could not be flushed to the writer.
     #[error("Failed to flush all bytes to writer")]
     Flushing(#[source] Arc<std::io::Error>),
 }
@@ -26,12 +35,13 @@ assert_error_traits!(ToWavError);
 /// float. This function is intended primarily for testing and diagnostics. It can be used to see
 /// the output without opening OS-Sink to a real audio device.
 ///
-/// If the file already exists it will be overwritten.
+/// If the file already exists it will be overwritten. Use [`WavWriterSink`] to choose another
+/// sample format or to write incrementally.
 ///
 /// # Note
 /// This is a convenience wrapper around `wav_to_writer`
 pub fn wav_to_file(
-    source: impl Source, // TODO make this take a spanless source
+    source: impl Source,
     wav_file: impl AsRef<path::Path>,
 ) -> Result<(), ToWavError> {
     let mut file = std::fs::File::create(wav_file)
@@ -44,6 +54,9 @@ pub fn wav_to_file(
 /// is intended primarily for testing and diagnostics. It can be used to see the output without
 /// opening an OS-Sink to a real audio device.
 ///
+/// The output keeps the channel count and sample rate the source starts with. Should the source
+/// change those later on, it is converted back.
+///
 /// # Example
 /// ```rust
 /// # use rodio::static_buffer::StaticSamplesBuffer;
@@ -60,72 +73,20 @@ pub fn wav_to_file(
 /// # Ok::<(), Box<dyn std::error::Error>>(())
 /// ```
 pub fn wav_to_writer(
-    source: impl Source, // TODO make this take a spanless source
+    source: impl Source,
     writer: &mut (impl io::Write + io::Seek),
 ) -> Result<(), ToWavError> {
-    let format = WavSpec {
-        channels: source.channels().get(),
-        sample_rate: source.sample_rate().get(),
-        bits_per_sample: 32,
-        sample_format: SampleFormat::Float,
-    };
-    let mut writer = io::BufWriter::new(writer);
-    {
-        let mut writer = hound::WavWriter::new(&mut writer, format)
-            .map_err(Arc::new)
-            .map_err(ToWavError::Creating)?;
-
-        let whole_frames = WholeFrames::new(source);
-        for sample in whole_frames {
-            writer
-                .write_sample(sample.to_sample::<f32>())
-                .map_err(Arc::new)
-                .map_err(ToWavError::Writing)?;
-        }
-
-        writer
-            .finalize()
-            .map_err(Arc::new)
-            .map_err(ToWavError::Finishing)?;
-    }
-    writer
+    let mut sink = WavWriterSink::new(
+        writer,
+        source.channels(),
+        source.sample_rate(),
+        WavOutputSettings::default(),
+    )?;
+    sink.append(source)?;
+    sink.finalize()?
         .flush()
         .map_err(Arc::new)
-        .map_err(ToWavError::Flushing)?;
-    Ok(())
-}
-
-struct WholeFrames<I: Iterator<Item = Sample>> {
-    buffer: Vec<Sample>,
-    pos: usize,
-    source: I,
-}
-
-impl<S: Source> WholeFrames<S> {
-    fn new(source: S) -> Self {
-        Self {
-            buffer: vec![0.0; source.channels().get().into()],
-            pos: source.channels().get().into(),
-            source,
-        }
-    }
-}
-
-impl<I: Iterator<Item = Sample>> Iterator for WholeFrames<I> {
-    type Item = Sample;
-
-    fn next(&mut self) -> Option<Sample> {
-        if self.pos >= self.buffer.len() {
-            for sample in &mut self.buffer {
-                *sample = self.source.next()?;
-            }
-            self.pos = 0;
-        }
-
-        let to_yield = self.buffer[self.pos];
-        self.pos += 1;
-        Some(to_yield)
-    }
+        .map_err(ToWavError::Flushing)
 }
 
 #[cfg(test)]
@@ -0,0 +1,163 @@
+use std::io::{self, Write};
+
+/// Metadata stored in a wav file written by
+/// [`WavWriterSink`](super::WavWriterSink).
+///
+/// Text tags go into a `LIST`/`INFO` chunk which most audio software
+/// understands. The optional [`BroadcastExtension`] is stored as a Broadcast
+/// Wave Format (BWF) `bext` chunk.
+///
+/// # Example
+/// ```
+/// use rodio::{BroadcastExtension, WavMetadata};
+///
+/// let metadata = WavMetadata::default()
+///     .with_title("Field recording")
+///     .with_artist("rodio")
+///     .with_broadcast_extension(BroadcastExtension {
+///         originator: "rodio".to_string(),
+///         origination_date: "2024-05-01".to_string(),
+///         origination_time: "12:00:00".to_string(),
+///         ..BroadcastExtension::default()
+///     });
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WavMetadata {
+    info: Vec<([u8; 4], String)>,
+    broadcast_extension: Option<BroadcastExtension>,
+}
+
+impl WavMetadata {
+    /// Sets a `LIST`/`INFO` tag, for example `*b"IGNR"` for the genre.
+    /// Setting a tag again replaces its value.
+    pub fn with_info(mut self, id: [u8; 4], value: impl Into<String>) -> Self {
+        let value = value.into();
+        match self.info.iter_mut().find(|(tag, _)| *tag == id) {
+            Some((_, existing)) => *existing = value,
+            None => self.info.push((id, value)),
+        }
+        self
+    }
+
+    /// Sets the title (`INAM`).
+    pub fn with_title(self, title: impl Into<String>) -> Self {
+        self.with_info(*b"INAM", title)
+    }
+
+    /// Sets the artist (`IART`).
+    pub fn with_artist(self, artist: impl Into<String>) -> Self {
+        self.with_info(*b"IART", artist)
+    }
+
+    /// Sets a comment (`ICMT`).
+    pub fn with_comment(self, comment: impl Into<String>) -> Self {
+        self.with_info(*b"ICMT", comment)
+    }
+
+    /// Sets the creation date (`ICRD`), preferably as `YYYY-MM-DD`.
+    pub fn with_creation_date(self, date: impl Into<String>) -> Self {
+        self.with_info(*b"ICRD", date)
+    }
+
+    /// Sets the software that created the file (`ISFT`).
+    pub fn with_software(self, software: impl Into<String>) -> Self {
+        self.with_info(*b"ISFT", software)
+    }
+
+    /// Adds a Broadcast Wave Format `bext` chunk.
+    pub fn with_broadcast_extension(mut self, bext: BroadcastExtension) -> Self {
+        self.broadcast_extension = Some(bext);
+        self
+    }
+
+    /// The `LIST`/`INFO` tags in the order they will be written.
+    pub fn info(&self) -> impl Iterator<Item = ([u8; 4], &str)> {
+        self.info.iter().map(|(id, value)| (*id, value.as_str()))
+    }
+
+    /// The Broadcast Wave Format extension, if any.
+    pub fn broadcast_extension(&self) -> Option<&BroadcastExtension> {
+        self.broadcast_extension.as_ref()
+    }
+
+    /// Writes the metadata chunks, each padded to an even length.
+    pub(super) fn write_chunks(&self, writer: &mut impl Write) -> io::Result<()> {
+        if let Some(bext) = &self.broadcast_extension {
+            bext.write_chunk(writer)?;
+        }
+        if !self.info.is_empty() {
+            let mut list = Vec::new();
+            list.extend_from_slice(b"INFO");
+            for (id, value) in &self.info {
+                // Stored zero terminated.
+                let len = value.len() + 1;
+                list.extend_from_slice(id);
+                list.extend_from_slice(&(len as u32).to_le_bytes());
+                list.extend_from_slice(value.as_bytes());
+                list.push(0);
+                if len % 2 == 1 {
+                    list.push(0);
+                }
+            }
+            writer.write_all(b"LIST")?;
+            writer.write_all(&(list.len() as u32).to_le_bytes())?;
+            writer.write_all(&list)?;
+        }
+        Ok(())
+    }
+}
+
+/// The Broadcast Wave Format (EBU Tech 3285) `bext` chunk.
+///
+/// Text fields longer than the space the format reserves for them are
+/// truncated.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BroadcastExtension {
+    /// Free text description, at most 256 bytes.
+    pub description: String,
+    /// Name of the originator, at most 32 bytes.
+    pub originator: String,
+    /// Unique reference assigned by the originator, at most 32 bytes.
+    pub originator_reference: String,
+    /// Date of creation formatted as `yyyy-mm-dd`.
+    pub origination_date: String,
+    /// Time of creation formatted as `hh:mm:ss`.
+    pub origination_time: String,
+    /// Position of the first sample counted in samples since midnight.
+    pub time_reference: u64,
+    /// The coding history, lines of the form `A=PCM,F=48000,W=24,M=stereo`.
+    pub coding_history: String,
+}
+
+impl BroadcastExtension {
+    fn write_chunk(&self, writer: &mut impl Write) -> io::Result<()> {
+        const FIXED_LEN: usize = 602;
+
+        let mut chunk = Vec::with_capacity(FIXED_LEN + self.coding_history.len() + 1);
+        put_fixed(&mut chunk, &self.description, 256);
+        put_fixed(&mut chunk, &self.originator, 32);
+        put_fixed(&mut chunk, &self.originator_reference, 32);
+        put_fixed(&mut chunk, &self.origination_date, 10);
+        put_fixed(&mut chunk, &self.origination_time, 8);
+        chunk.extend_from_slice(&(self.time_reference as u32).to_le_bytes());
+        chunk.extend_from_slice(&((self.time_reference >> 32) as u32).to_le_bytes());
+        // Version 1: no loudness values, those bytes stay reserved.
+        chunk.extend_from_slice(&1u16.to_le_bytes());
+        // UMID and reserved bytes.
+        chunk.resize(FIXED_LEN, 0);
+        chunk.extend_from_slice(self.coding_history.as_bytes());
+        if chunk.len() % 2 == 1 {
+            chunk.push(0);
+        }
+
+        writer.write_all(b"bext")?;
+        writer.write_all(&(chunk.len() as u32).to_le_bytes())?;
+        writer.write_all(&chunk)
+    }
+}
+
+fn put_fixed(chunk: &mut Vec<u8>, text: &str, len: usize) {
+    let bytes = &text.as_bytes()[..text.len().min(len)];
+    chunk.extend_from_slice(bytes);
+    chunk.resize(chunk.len() + len - bytes.len(), 0);
+}
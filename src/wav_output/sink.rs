@@ -0,0 +1,665 @@
+use std::collections::VecDeque;
+use std::io::{self, Seek, SeekFrom, Write};
+use std::path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use dasp_sample::Sample as DaspSample;
+
+use super::{ToWavError, WavMetadata};
+#[cfg(feature = "dither")]
+use crate::source::{Dither, DitherAlgorithm};
+use crate::source::{SeekError, UniformSourceIterator};
+use crate::{ChannelCount, Sample, SampleRate, Source};
+
+/// Number of samples converted at a time by [`WavWriterSink::append`].
+const APPEND_CHUNK_LEN: usize = 4096;
+
+/// How samples are stored in a wav file written by [`WavWriterSink`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WavSampleFormat {
+    /// 32-bit IEEE float, stores samples without loss.
+    #[default]
+    Float32,
+    /// 16-bit signed integer, the format of audio CDs.
+    Int16,
+    /// 24-bit signed integer.
+    Int24,
+    /// 32-bit signed integer.
+    Int32,
+}
+
+impl WavSampleFormat {
+    /// The number of bits used for one sample.
+    pub fn bits_per_sample(self) -> u16 {
+        match self {
+            WavSampleFormat::Int16 => 16,
+            WavSampleFormat::Int24 => 24,
+            WavSampleFormat::Float32 | WavSampleFormat::Int32 => 32,
+        }
+    }
+
+    fn bytes_per_sample(self) -> u16 {
+        self.bits_per_sample() / 8
+    }
+
+    fn is_float(self) -> bool {
+        self == WavSampleFormat::Float32
+    }
+
+    #[inline]
+    fn encode(self, sample: Sample, out: &mut Vec<u8>) {
+        match self {
+            WavSampleFormat::Float32 => {
+                out.extend_from_slice(&sample.to_sample::<f32>().to_le_bytes())
+            }
+            WavSampleFormat::Int16 => {
+                out.extend_from_slice(&(quantize(sample, 16) as i16).to_le_bytes())
+            }
+            WavSampleFormat::Int24 => {
+                out.extend_from_slice(&quantize(sample, 24).to_le_bytes()[..3])
+            }
+            WavSampleFormat::Int32 => out.extend_from_slice(&quantize(sample, 32).to_le_bytes()),
+        }
+    }
+}
+
+/// Rounds to the nearest integer sample of `bits` bits, clipping at full scale.
+#[inline]
+fn quantize(sample: Sample, bits: u32) -> i32 {
+    let scale = (1_u64 << (bits - 1)) as f64;
+    (sample.to_sample::<f64>() * scale)
+        .round()
+        .clamp(-scale, scale - 1.0) as i32
+}
+
+/// Configures a [`WavWriterSink`].
+///
+/// By default samples are stored as 32-bit float and no metadata is written.
+/// When writing integer samples they are dithered to the target bit depth
+/// using [TPDF](DitherAlgorithm::TPDF) dither, see [`Self::with_dither`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(not(feature = "dither"), derive(Default))]
+pub struct WavOutputSettings {
+    sample_format: WavSampleFormat,
+    #[cfg(feature = "dither")]
+    dither: Option<DitherAlgorithm>,
+    metadata: WavMetadata,
+}
+
+#[cfg(feature = "dither")]
+impl Default for WavOutputSettings {
+    fn default() -> Self {
+        Self {
+            sample_format: WavSampleFormat::default(),
+            dither: Some(DitherAlgorithm::default()),
+            metadata: WavMetadata::default(),
+        }
+    }
+}
+
+impl WavOutputSettings {
+    /// The format samples are stored in.
+    pub fn with_sample_format(mut self, sample_format: WavSampleFormat) -> Self {
+        self.sample_format = sample_format;
+        self
+    }
+
+    /// The dither applied before quantizing to an integer sample format, or
+    /// `None` to only round. Has no effect when writing float samples.
+    ///
+    /// Default is [`DitherAlgorithm::TPDF`].
+    #[cfg(feature = "dither")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "dither")))]
+    pub fn with_dither(mut self, algorithm: Option<DitherAlgorithm>) -> Self {
+        self.dither = algorithm;
+        self
+    }
+
+    /// Metadata to store in the file.
+    pub fn with_metadata(mut self, metadata: WavMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+}
+
+/// Holds samples passed to [`WavWriterSink::write`] until they are written.
+struct Pending {
+    samples: VecDeque<Sample>,
+    channels: ChannelCount,
+    sample_rate: SampleRate,
+}
+
+impl Iterator for Pending {
+    type Item = Sample;
+
+    #[inline]
+    fn next(&mut self) -> Option<Sample> {
+        self.samples.pop_front()
+    }
+}
+
+impl Source for Pending {
+    fn current_span_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> ChannelCount {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> SampleRate {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+
+    fn try_seek(&mut self, _: Duration) -> Result<(), SeekError> {
+        Err(SeekError::NotSupported {
+            underlying_source: std::any::type_name::<Self>(),
+        })
+    }
+}
+
+enum Quantizer {
+    Plain(Pending),
+    #[cfg(feature = "dither")]
+    Dithered(Dither<Pending>),
+}
+
+impl Quantizer {
+    fn pending(&mut self) -> &mut VecDeque<Sample> {
+        match self {
+            Quantizer::Plain(pending) => &mut pending.samples,
+            #[cfg(feature = "dither")]
+            Quantizer::Dithered(dither) => &mut dither.inner_mut().samples,
+        }
+    }
+
+    #[inline]
+    fn next(&mut self) -> Option<Sample> {
+        match self {
+            Quantizer::Plain(pending) => pending.next(),
+            #[cfg(feature = "dither")]
+            Quantizer::Dithered(dither) => dither.next(),
+        }
+    }
+}
+
+/// Writes audio to a wav file incrementally.
+///
+/// Unlike [`wav_to_writer`](crate::wav_to_writer) this does not need the whole
+/// recording up front. Samples can be written as they become available, for
+/// example from a live tap on a mixer or a microphone. Call
+/// [`flush`](Self::flush) now and then to keep the file on disk readable
+/// while recording, and [`finalize`](Self::finalize) once done. A sink that is
+/// dropped is finalized as well, but errors are then ignored.
+///
+/// Samples are stored in the [`WavSampleFormat`] chosen in the
+/// [`WavOutputSettings`], integer formats are dithered by default.
+///
+/// # Example
+/// ```no_run
+/// use rodio::math::nz;
+/// use rodio::source::SineWave;
+/// use rodio::{OfflineSink, Player, WavMetadata, WavOutputSettings, WavSampleFormat, WavWriterSink};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut offline = OfflineSink::new(nz!(2), nz!(48_000));
+/// let player = Player::connect_new(offline.mixer());
+/// player.append(SineWave::new(440.0));
+///
+/// let settings = WavOutputSettings::default()
+///     .with_sample_format(WavSampleFormat::Int24)
+///     .with_metadata(WavMetadata::default().with_title("A tone"));
+/// let mut wav = WavWriterSink::create("tone.wav", nz!(2), nz!(48_000), settings)?;
+///
+/// let mut buffer = vec![0.0; 2 * 480];
+/// for _ in 0..100 {
+///     offline.render(&mut buffer);
+///     wav.write(&buffer)?;
+/// }
+/// wav.finalize()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct WavWriterSink<W: Write + Seek> {
+    writer: Option<io::BufWriter<W>>,
+    sample_format: WavSampleFormat,
+    channels: ChannelCount,
+    sample_rate: SampleRate,
+    quantizer: Quantizer,
+    data_len_offset: u64,
+    data_bytes: u64,
+    encoded: Vec<u8>,
+}
+
+impl WavWriterSink<std::fs::File> {
+    /// Creates a wav file at `path` and writes its header.
+    ///
+    /// If the file already exists it will be overwritten.
+    pub fn create(
+        path: impl AsRef<path::Path>,
+        channels: ChannelCount,
+        sample_rate: SampleRate,
+        settings: WavOutputSettings,
+    ) -> Result<Self, ToWavError> {
+        let file = std::fs::File::create(path)
+            .map_err(Arc::new)
+            .map_err(ToWavError::OpenFile)?;
+        Self::new(file, channels, sample_rate, settings)
+    }
+}
+
+impl<W: Write + Seek> WavWriterSink<W> {
+    /// Writes the wav header to `writer`. Samples are to be written with
+    /// `channels` interleaved channels at `sample_rate`.
+    pub fn new(
+        writer: W,
+        channels: ChannelCount,
+        sample_rate: SampleRate,
+        settings: WavOutputSettings,
+    ) -> Result<Self, ToWavError> {
+        let mut writer = io::BufWriter::new(writer);
+        let header = header(&settings, channels, sample_rate)
+            .map_err(io_error)
+            .map_err(ToWavError::Creating)?;
+        writer
+            .write_all(&header)
+            .map_err(io_error)
+            .map_err(ToWavError::Creating)?;
+
+        let pending = Pending {
+            samples: VecDeque::new(),
+            channels,
+            sample_rate,
+        };
+        #[cfg(feature = "dither")]
+        let quantizer = match settings.dither {
+            Some(algorithm) if !settings.sample_format.is_float() => {
+                let bits = u32::from(settings.sample_format.bits_per_sample());
+                Quantizer::Dithered(Dither::new(
+                    pending,
+                    crate::BitDepth::new(bits).expect("bit depth is not zero"),
+                    algorithm,
+                ))
+            }
+            _ => Quantizer::Plain(pending),
+        };
+        #[cfg(not(feature = "dither"))]
+        let quantizer = Quantizer::Plain(pending);
+
+        Ok(Self {
+            writer: Some(writer),
+            sample_format: settings.sample_format,
+            channels,
+            sample_rate,
+            quantizer,
+            // The header ends with the length of the data chunk.
+            data_len_offset: header.len() as u64 - 4,
+            data_bytes: 0,
+            encoded: Vec::new(),
+        })
+    }
+
+    /// The number of interleaved channels.
+    pub fn channels(&self) -> ChannelCount {
+        self.channels
+    }
+
+    /// The sample rate.
+    pub fn sample_rate(&self) -> SampleRate {
+        self.sample_rate
+    }
+
+    /// The format samples are stored in.
+    pub fn sample_format(&self) -> WavSampleFormat {
+        self.sample_format
+    }
+
+    /// The number of whole frames written so far.
+    pub fn frames_written(&self) -> u64 {
+        self.data_bytes
+            / (self.sample_format.bytes_per_sample() as u64 * self.channels.get() as u64)
+    }
+
+    /// Writes interleaved samples.
+    ///
+    /// The samples do not need to make up whole frames, what is left of an
+    /// incomplete frame is kept until the next write. An incomplete frame at
+    /// the end of the recording is dropped.
+    pub fn write(&mut self, samples: &[Sample]) -> Result<(), ToWavError> {
+        let pending = self.quantizer.pending();
+        pending.extend(samples);
+        let channels = self.channels.get() as usize;
+        let whole_frames = pending.len() / channels;
+
+        self.encoded.clear();
+        for _ in 0..whole_frames * channels {
+            let sample = self
+                .quantizer
+                .next()
+                .expect("only pending samples are requested");
+            self.sample_format.encode(sample, &mut self.encoded);
+        }
+
+        let writer = self
+            .writer
+            .as_mut()
+            .expect("writer is only taken when finishing");
+        writer
+            .write_all(&self.encoded)
+            .map_err(io_error)
+            .map_err(ToWavError::Writing)?;
+        self.data_bytes += self.encoded.len() as u64;
+        Ok(())
+    }
+
+    /// Writes all of `source`. It is converted to the channel count and
+    /// sample rate of this sink, including any span in which the source
+    /// changes its own parameters.
+    pub fn append(&mut self, source: impl Source) -> Result<(), ToWavError> {
+        let mut source = UniformSourceIterator::new(source, self.channels, self.sample_rate);
+        let mut chunk = Vec::with_capacity(APPEND_CHUNK_LEN);
+        loop {
+            chunk.clear();
+            chunk.extend(source.by_ref().take(APPEND_CHUNK_LEN));
+            if chunk.is_empty() {
+                return Ok(());
+            }
+            self.write(&chunk)?;
+        }
+    }
+
+    /// Updates the header and flushes the writer. Afterwards the file is a
+    /// valid wav file containing everything written so far, recording can
+    /// continue.
+    pub fn flush(&mut self) -> Result<(), ToWavError> {
+        self.update_header(false)
+            .and_then(|()| self.writer_mut().flush())
+            .map_err(Arc::new)
+            .map_err(ToWavError::Flushing)
+    }
+
+    /// Completes the file and returns the underlying writer.
+    pub fn finalize(mut self) -> Result<W, ToWavError> {
+        self.finish()
+    }
+
+    fn writer_mut(&mut self) -> &mut io::BufWriter<W> {
+        self.writer
+            .as_mut()
+            .expect("writer is only taken when finishing")
+    }
+
+    /// Writes the current lengths to the header. `padded` tells whether the
+    /// pad byte after an odd number of data bytes has been written.
+    fn update_header(&mut self, padded: bool) -> io::Result<()> {
+        // The riff chunk holds everything after its own 8 byte header.
+        let pad = if padded { self.data_bytes % 2 } else { 0 };
+        let riff_len = self.data_len_offset + 4 - 8 + self.data_bytes + pad;
+        let data_len = self.data_bytes;
+        let data_len_offset = self.data_len_offset;
+
+        let writer = self.writer_mut();
+        writer.seek(SeekFrom::Start(4))?;
+        writer.write_all(&clamp_len(riff_len).to_le_bytes())?;
+        writer.seek(SeekFrom::Start(data_len_offset))?;
+        writer.write_all(&clamp_len(data_len).to_le_bytes())?;
+        writer.seek(SeekFrom::End(0))?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<W, ToWavError> {
+        // Chunks are padded to an even length.
+        if self.data_bytes % 2 == 1 {
+            self.writer_mut()
+                .write_all(&[0])
+                .map_err(io_error)
+                .map_err(ToWavError::Writing)?;
+        }
+        self.update_header(true)
+            .map_err(io_error)
+            .map_err(ToWavError::Finishing)?;
+        let writer = self.writer.take().expect("sink is only finished once");
+        writer
+            .into_inner()
+            .map_err(|err| Arc::new(err.into_error()))
+            .map_err(ToWavError::Flushing)
+    }
+}
+
+impl<W: Write + Seek> Drop for WavWriterSink<W> {
+    fn drop(&mut self) {
+        if self.writer.is_some() {
+            let _ = self.finish();
+        }
+    }
+}
+
+impl<W: Write + Seek> std::fmt::Debug for WavWriterSink<W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WavWriterSink")
+            .field("sample_format", &self.sample_format)
+            .field("channels", &self.channels)
+            .field("sample_rate", &self.sample_rate)
+            .field("frames_written", &self.frames_written())
+            .finish_non_exhaustive()
+    }
+}
+
+fn io_error(err: io::Error) -> Arc<hound::Error> {
+    Arc::new(hound::Error::IoError(err))
+}
+
+/// Wav files can not describe more than 4 GiB of data.
+fn clamp_len(len: u64) -> u32 {
+    len.min(u32::MAX as u64) as u32
+}
+
+/// Builds everything up to and including the length of the data chunk.
+fn header(
+    settings: &WavOutputSettings,
+    channels: ChannelCount,
+    sample_rate: SampleRate,
+) -> io::Result<Vec<u8>> {
+    const KSDATAFORMAT_SUBTYPE_PCM: [u8; 16] = [
+        0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x80, 0x00, 0x00, 0xaa, 0x00, 0x38, 0x9b,
+        0x71,
+    ];
+    const KSDATAFORMAT_SUBTYPE_IEEE_FLOAT: [u8; 16] = [
+        0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x80, 0x00, 0x00, 0xaa, 0x00, 0x38, 0x9b,
+        0x71,
+    ];
+
+    let format = settings.sample_format;
+    let channels = channels.get();
+    let bits = format.bits_per_sample();
+    let block_align = channels * format.bytes_per_sample();
+    // The older format is more widely supported, but can only describe up to
+    // two channels with at most 16 bits.
+    let extensible = channels > 2 || bits > 16;
+
+    let mut header = Vec::new();
+    header.write_all(b"RIFF")?;
+    // Filled in by `update_header`.
+    header.write_all(&0u32.to_le_bytes())?;
+    header.write_all(b"WAVE")?;
+
+    header.write_all(b"fmt ")?;
+    header.write_all(&(if extensible { 40u32 } else { 16 }).to_le_bytes())?;
+    let format_tag: u16 = match (extensible, format.is_float()) {
+        (true, _) => 0xfffe,
+        (false, true) => 3,
+        (false, false) => 1,
+    };
+    header.write_all(&format_tag.to_le_bytes())?;
+    header.write_all(&channels.to_le_bytes())?;
+    header.write_all(&sample_rate.get().to_le_bytes())?;
+    header.write_all(&(sample_rate.get() * block_align as u32).to_le_bytes())?;
+    header.write_all(&block_align.to_le_bytes())?;
+    header.write_all(&bits.to_le_bytes())?;
+    if extensible {
+        // Size of the extension, valid bits and the channel mask.
+        header.write_all(&22u16.to_le_bytes())?;
+        header.write_all(&bits.to_le_bytes())?;
+        let channel_mask = (1u32 << channels.min(18)) - 1;
+        header.write_all(&channel_mask.to_le_bytes())?;
+        header.write_all(if format.is_float() {
+            &KSDATAFORMAT_SUBTYPE_IEEE_FLOAT
+        } else {
+            &KSDATAFORMAT_SUBTYPE_PCM
+        })?;
+    }
+
+    settings.metadata.write_chunks(&mut header)?;
+
+    header.write_all(b"data")?;
+    // Filled in by `update_header`.
+    header.write_all(&0u32.to_le_bytes())?;
+    Ok(header)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use dasp_sample::Sample as DaspSample;
+
+    use super::{WavOutputSettings, WavSampleFormat, WavWriterSink};
+    use crate::math::nz;
+    use crate::{BroadcastExtension, Sample, WavMetadata};
+
+    fn read_back(bytes: Vec<u8>) -> hound::WavReader<Cursor<Vec<u8>>> {
+        hound::WavReader::new(Cursor::new(bytes)).expect("wav file can be read back")
+    }
+
+    #[test]
+    fn integer_formats_round_trip() {
+        let samples: [Sample; 6] = [0.0, 0.5, -0.5, 1.0, -1.0, 0.25];
+        for (format, bits) in [
+            (WavSampleFormat::Int16, 16),
+            (WavSampleFormat::Int24, 24),
+            (WavSampleFormat::Int32, 32),
+        ] {
+            let settings = WavOutputSettings::default().with_sample_format(format);
+            #[cfg(feature = "dither")]
+            let settings = settings.with_dither(None);
+            let mut sink =
+                WavWriterSink::new(Cursor::new(Vec::new()), nz!(2), nz!(44_100), settings).unwrap();
+            sink.write(&samples).unwrap();
+            let bytes = sink.finalize().unwrap().into_inner();
+
+            let mut reader = read_back(bytes);
+            assert_eq!(reader.spec().bits_per_sample, bits);
+            assert_eq!(reader.spec().sample_format, hound::SampleFormat::Int);
+            let scale = (1_i64 << (bits - 1)) as f64;
+            let read: Vec<f64> = reader
+                .samples::<i32>()
+                .map(|s| s.unwrap() as f64 / scale)
+                .collect();
+            assert_eq!(read.len(), samples.len());
+            for (read, written) in read.iter().zip(samples) {
+                assert!((read - written.to_sample::<f64>()).abs() <= 1.0 / scale);
+            }
+        }
+    }
+
+    #[test]
+    fn chunked_writes_keep_whole_frames() {
+        let mut sink = WavWriterSink::new(
+            Cursor::new(Vec::new()),
+            nz!(2),
+            nz!(48_000),
+            WavOutputSettings::default(),
+        )
+        .unwrap();
+        sink.write(&[0.1, 0.2, 0.3]).unwrap();
+        assert_eq!(sink.frames_written(), 1);
+        sink.write(&[0.4, 0.5]).unwrap();
+        assert_eq!(sink.frames_written(), 2);
+
+        // Readable after a flush, recording continues afterwards.
+        sink.flush().unwrap();
+        sink.write(&[0.6, 0.7]).unwrap();
+        let bytes = sink.finalize().unwrap().into_inner();
+
+        let mut reader = read_back(bytes);
+        let read: Vec<f32> = reader.samples::<f32>().map(Result::unwrap).collect();
+        assert_eq!(read, [0.1, 0.2, 0.3, 0.4, 0.5, 0.6]);
+    }
+
+    #[cfg(feature = "dither")]
+    #[test]
+    fn integer_formats_are_dithered() {
+        let settings = WavOutputSettings::default().with_sample_format(WavSampleFormat::Int16);
+        let mut sink =
+            WavWriterSink::new(Cursor::new(Vec::new()), nz!(1), nz!(44_100), settings).unwrap();
+        sink.write(&[0.0; 1000]).unwrap();
+        let bytes = sink.finalize().unwrap().into_inner();
+
+        let read: Vec<i16> = read_back(bytes)
+            .samples::<i16>()
+            .map(Result::unwrap)
+            .collect();
+        assert!(read.iter().any(|s| *s != 0));
+        assert!(read.iter().all(|s| s.abs() <= 2));
+    }
+
+    #[test]
+    fn metadata_chunks_are_written() {
+        let metadata = WavMetadata::default()
+            .with_title("odd")
+            .with_software("rodio")
+            .with_broadcast_extension(BroadcastExtension {
+                originator: "rodio".to_string(),
+                time_reference: 48_000,
+                ..BroadcastExtension::default()
+            });
+        let settings = WavOutputSettings::default()
+            .with_sample_format(WavSampleFormat::Int24)
+            .with_metadata(metadata);
+        #[cfg(feature = "dither")]
+        let settings = settings.with_dither(None);
+        let mut sink =
+            WavWriterSink::new(Cursor::new(Vec::new()), nz!(1), nz!(48_000), settings).unwrap();
+        // An odd number of data bytes needs a pad byte.
+        sink.write(&[0.5]).unwrap();
+        let bytes = sink.finalize().unwrap().into_inner();
+
+        assert_eq!(bytes.len() % 2, 0);
+        let riff_len = u32::from_le_bytes(bytes[4..8].try_into().unwrap());
+        assert_eq!(riff_len as usize, bytes.len() - 8);
+        let find = |id: &[u8]| bytes.windows(4).position(|w| w == id).unwrap();
+        assert!(find(b"bext") < find(b"data"));
+        assert_eq!(&bytes[find(b"INAM") + 8..find(b"INAM") + 12], b"odd\0");
+        assert_eq!(&bytes[find(b"bext") + 8 + 256..][..5], b"rodio");
+
+        let mut reader = read_back(bytes);
+        let read: Vec<i32> = reader.samples::<i32>().map(Result::unwrap).collect();
+        assert_eq!(read, [1 << 22]);
+    }
+
+    #[test]
+    fn flush_describes_the_file_as_written() {
+        let settings = WavOutputSettings::default().with_sample_format(WavSampleFormat::Int24);
+        #[cfg(feature = "dither")]
+        let settings = settings.with_dither(None);
+        let mut sink =
+            WavWriterSink::new(Cursor::new(Vec::new()), nz!(1), nz!(48_000), settings).unwrap();
+        // Three data bytes, the pad byte is only written when finishing.
+        sink.write(&[0.5]).unwrap();
+        sink.flush().unwrap();
+
+        let bytes = sink.writer_mut().get_ref().get_ref().clone();
+        let riff_len = u32::from_le_bytes(bytes[4..8].try_into().unwrap());
+        assert_eq!(riff_len as usize, bytes.len() - 8);
+        let read: Vec<i32> = read_back(bytes)
+            .samples::<i32>()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(read, [1 << 22]);
+    }
+}
@@ -0,0 +1,71 @@
+#![cfg(feature = "symphonia-wav")]
+
+use std::fs::File;
+use std::io::BufReader;
+use std::time::Duration;
+
+use rodio::decoder::Chapter;
+use rodio::{Decoder, Source};
+
+fn decoder() -> Decoder<BufReader<File>> {
+    Decoder::try_from(File::open("assets/music.wav").unwrap()).unwrap()
+}
+
+fn chapters() -> Vec<Chapter> {
+    vec![
+        Chapter::new(Duration::ZERO).with_title("One"),
+        Chapter::new(Duration::from_millis(500)).with_title("Two"),
+        Chapter::new(Duration::from_secs(1)).with_title("Three"),
+    ]
+}
+
+#[test]
+fn wav_has_no_chapters() {
+    assert!(decoder().chapters().is_empty());
+}
+
+#[test]
+fn chapters_play_back_to_back() {
+    let expected: Vec<_> = decoder().collect();
+    let total_duration = decoder().total_duration().unwrap();
+
+    let parts = decoder().split_chapters(chapters());
+    assert_eq!(parts.len(), 3);
+    assert_eq!(parts[0].total_duration(), Some(Duration::from_millis(500)));
+    assert_eq!(
+        parts[2].total_duration(),
+        Some(total_duration - Duration::from_secs(1))
+    );
+
+    let mut played = Vec::new();
+    for part in parts {
+        played.extend(part);
+    }
+    assert_eq!(played.len(), expected.len());
+    assert!(played == expected);
+}
+
+#[test]
+fn skipped_chapter_seeks_to_next() {
+    let decoder = decoder();
+    let channels = decoder.channels().get() as usize;
+    let rate = decoder.sample_rate().get() as usize;
+    let expected: Vec<_> = self::decoder().skip(rate * channels).take(100).collect();
+
+    let mut parts = decoder.split_chapters(chapters()).into_iter();
+    // Start the first chapter, then drop it and the second unplayed.
+    parts.next().unwrap().take(10).for_each(drop);
+    parts.next();
+    let third = parts.next().unwrap();
+    assert_eq!(third.chapter().title(), Some("Three"));
+    let played: Vec<_> = third.take(100).collect();
+    assert_eq!(played, expected);
+}
+
+#[test]
+fn whole_file_without_chapters() {
+    let expected = decoder().count();
+    let parts = decoder().split_chapters(Vec::new());
+    assert_eq!(parts.len(), 1);
+    assert_eq!(parts.into_iter().next().unwrap().count(), expected);
+}
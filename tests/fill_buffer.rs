@@ -0,0 +1,98 @@
+use std::time::Duration;
+
+use rodio::buffer::SamplesBuffer;
+use rodio::math::nz;
+use rodio::source::UniformSourceIterator;
+use rodio::{Sample, Source};
+
+fn ramp(channels: u16, len: usize) -> SamplesBuffer {
+    let samples = (0..len)
+        .map(|i| i as Sample / len as Sample)
+        .collect::<Vec<_>>();
+    SamplesBuffer::new(
+        rodio::ChannelCount::new(channels).unwrap(),
+        nz!(1000),
+        samples,
+    )
+}
+
+fn collect_blocks(mut source: impl Source, block_len: usize) -> Vec<Sample> {
+    let mut buffer = vec![0.0; block_len];
+    let mut collected = Vec::new();
+    loop {
+        let written = source.fill_buffer(&mut buffer);
+        if written == 0 {
+            return collected;
+        }
+        collected.extend_from_slice(&buffer[..written]);
+    }
+}
+
+fn effects(source: SamplesBuffer) -> impl Source {
+    let mut accesses = 0;
+    source
+        .amplify(0.5)
+        .low_pass(100)
+        .pausable(false)
+        .periodic_access(Duration::from_millis(7), move |pausable| {
+            accesses += 1;
+            pausable.set_paused(accesses % 3 == 0);
+        })
+        .track_position()
+}
+
+#[test]
+fn effects_match_next() {
+    let expected: Vec<_> = effects(ramp(2, 300)).collect();
+    for block_len in [1, 5, 64] {
+        assert_eq!(collect_blocks(effects(ramp(2, 300)), block_len), expected);
+    }
+}
+
+#[test]
+fn channel_conversion_matches_next() {
+    let convert = || UniformSourceIterator::new(ramp(1, 101), nz!(2), nz!(1000));
+    let expected: Vec<_> = convert().collect();
+    assert_eq!(collect_blocks(convert(), 16), expected);
+}
+
+#[test]
+fn resampling_matches_next() {
+    let convert = || UniformSourceIterator::new(ramp(2, 100), nz!(2), nz!(1500));
+    let expected: Vec<_> = convert().collect();
+    assert_eq!(collect_blocks(convert(), 16), expected);
+}
+
+#[test]
+fn track_position_counts_blocks() {
+    let mut source = ramp(2, 200).track_position();
+    let mut buffer = [0.0; 100];
+    assert_eq!(source.fill_buffer(&mut buffer), 100);
+    assert_eq!(source.get_pos(), Duration::from_millis(50));
+}
+
+#[test]
+fn player_through_mixer_matches_next() {
+    let play = || {
+        let (mixer, output) = rodio::mixer::mixer(nz!(2), nz!(1000));
+        let player = rodio::Player::connect_new(&mixer);
+        player.set_volume(0.5);
+        player.append(ramp(1, 101));
+        player.append(ramp(2, 300));
+        (player, output)
+    };
+
+    let (_player, output) = play();
+    let expected: Vec<_> = output.take(1000).collect();
+
+    let (_player, mut output) = play();
+    let mut buffer = [0.0; 64];
+    let mut mixed = Vec::new();
+    while mixed.len() < expected.len() {
+        let len = buffer.len().min(expected.len() - mixed.len());
+        let written = output.fill_buffer(&mut buffer[..len]);
+        assert!(written > 0);
+        mixed.extend_from_slice(&buffer[..written]);
+    }
+    assert_eq!(mixed, expected);
+}
@@ -0,0 +1,114 @@
+#![cfg(feature = "opus")]
+
+use std::fs::File;
+use std::time::Duration;
+
+use rodio::decoder::DecoderBuilder;
+use rodio::{Decoder, Sample, Source};
+
+// Half a second of stereo audio: a 440 Hz sine at amplitude 0.5 on the left
+// and an 880 Hz sine at amplitude 0.25 on the right. Both files hold the same
+// packets, the WebM one has an output gain of -6 dB in its `OpusHead`.
+const FRAMES: usize = 24_000;
+// Silence the encoder added to fill the last packet.
+const PADDING: usize = 648;
+const WEBM_GAIN: Sample = 0.5;
+
+fn decoder(path: &str, gapless: bool) -> Decoder<File> {
+    let file = File::open(path).unwrap();
+    let len = file.metadata().unwrap().len();
+    DecoderBuilder::new()
+        .with_data(file)
+        .with_byte_len(len)
+        .with_seekable(true)
+        .with_gapless(gapless)
+        .build()
+        .unwrap()
+}
+
+fn rms(samples: &[Sample]) -> Sample {
+    (samples.iter().map(|s| s * s).sum::<Sample>() / samples.len() as Sample).sqrt()
+}
+
+fn upward_zero_crossings(samples: &[Sample]) -> usize {
+    samples
+        .windows(2)
+        .filter(|pair| pair[0] < 0.0 && pair[1] >= 0.0)
+        .count()
+}
+
+fn channel(samples: &[Sample], channel: usize) -> Vec<Sample> {
+    samples.iter().skip(channel).step_by(2).copied().collect()
+}
+
+/// Checks the sine on each channel by its loudness and frequency.
+fn assert_sines(samples: &[Sample], gain: Sample) {
+    for (index, amplitude, frequency) in [(0, 0.5, 440), (1, 0.25, 880)] {
+        let samples = channel(samples, index);
+        let rms = rms(&samples);
+        let expected = amplitude * gain / Sample::sqrt(2.0);
+        assert!(
+            (rms - expected).abs() < 0.05 * expected,
+            "channel {index}: rms {rms}, expected {expected}"
+        );
+        let periods = frequency * samples.len() / 48_000;
+        let crossings = upward_zero_crossings(&samples);
+        assert!(
+            crossings.abs_diff(periods) <= 2,
+            "channel {index}: {crossings} zero crossings"
+        );
+    }
+}
+
+#[test]
+fn ogg_trims_pre_skip() {
+    for (gapless, frames) in [(true, FRAMES), (false, FRAMES + PADDING)] {
+        let decoder = decoder("assets/sine.opus", gapless);
+        assert_eq!(decoder.sample_rate().get(), 48_000);
+        assert_eq!(decoder.channels().get(), 2);
+        assert_eq!(decoder.count(), frames * 2, "gapless: {gapless}");
+    }
+}
+
+#[test]
+fn decodes_ogg() {
+    let samples: Vec<Sample> = decoder("assets/sine.opus", true).collect();
+    assert_sines(&samples, 1.0);
+}
+
+#[test]
+fn decodes_webm() {
+    let webm = decoder("assets/sine.webm", true);
+    assert_eq!(webm.sample_rate().get(), 48_000);
+    assert_eq!(webm.channels().get(), 2);
+    let samples: Vec<Sample> = webm.collect();
+    // Matroska does not say where the padding at the end starts.
+    assert!(samples.len() >= FRAMES * 2, "{} samples", samples.len());
+    let samples = &samples[..FRAMES * 2];
+    assert_sines(samples, WEBM_GAIN);
+
+    // The pre-skip is trimmed just like from the Ogg file.
+    let ogg = decoder("assets/sine.opus", true);
+    for (webm, ogg) in samples.iter().zip(ogg) {
+        assert!(
+            (webm - ogg * WEBM_GAIN).abs() < 1e-3,
+            "{webm} != {ogg} * gain"
+        );
+    }
+}
+
+#[test]
+fn seeks() {
+    let mut decoder = decoder("assets/sine.opus", true);
+    decoder.try_seek(Duration::from_millis(250)).unwrap();
+    let left: Vec<Sample> = decoder.collect();
+    // Seeking lands on a packet boundary.
+    let expected = (FRAMES - 12_000) * 2;
+    assert!(
+        left.len().abs_diff(expected) <= 960 * 2,
+        "{} samples left",
+        left.len()
+    );
+    // libopus needs up to 80 ms to converge after it is reset.
+    assert_sines(&left[3840 * 2..], 1.0);
+}
@@ -0,0 +1,139 @@
+#![cfg(feature = "symphonia-wav")]
+
+use std::io::{self, Read};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::Duration;
+
+use rodio::decoder::{DecoderBuilder, StreamDecoder};
+use rodio::{Decoder, Sample, Source};
+
+/// A network-like reader that only delivers what the test sends it.
+struct Network {
+    chunks: Receiver<Vec<u8>>,
+    pending: io::Cursor<Vec<u8>>,
+}
+
+impl Read for Network {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let n = self.pending.read(buf)?;
+            if n > 0 {
+                return Ok(n);
+            }
+            match self.chunks.recv() {
+                Ok(chunk) => self.pending = io::Cursor::new(chunk),
+                Err(_) => return Ok(0),
+            }
+        }
+    }
+}
+
+fn network() -> (Sender<Vec<u8>>, Network) {
+    let (sender, chunks) = mpsc::channel();
+    let reader = Network {
+        chunks,
+        pending: io::Cursor::new(Vec::new()),
+    };
+    (sender, reader)
+}
+
+fn wav() -> Vec<u8> {
+    std::fs::read("assets/music.wav").unwrap()
+}
+
+fn decoded() -> Vec<Sample> {
+    Decoder::try_from(std::fs::File::open("assets/music.wav").unwrap())
+        .unwrap()
+        .collect()
+}
+
+/// Plays `decoder` to the end and returns the samples that were not filler silence.
+fn play(mut decoder: StreamDecoder) -> Vec<Sample> {
+    let status = decoder.status();
+    let mut played = Vec::new();
+    loop {
+        let buffering = status.is_buffering();
+        let Some(sample) = decoder.next() else {
+            break;
+        };
+        if buffering {
+            assert_eq!(sample, 0.0);
+        } else {
+            played.push(sample);
+        }
+        if buffering && !status.has_ended() {
+            std::thread::sleep(Duration::from_micros(10));
+        }
+    }
+    played
+}
+
+#[test]
+fn decodes_reader_without_seek() {
+    let (sender, reader) = network();
+    sender.send(wav()).unwrap();
+    drop(sender);
+
+    let decoder = StreamDecoder::new(reader).unwrap();
+    assert_eq!(decoder.total_duration(), None);
+    assert!(play(decoder) == decoded());
+}
+
+#[test]
+fn plays_silence_while_stalled() {
+    let data = wav();
+    let quarter = data.len() / 4;
+    let (sender, reader) = network();
+    sender.send(data[..quarter].to_vec()).unwrap();
+
+    let mut decoder = DecoderBuilder::new()
+        .with_data(reader)
+        .with_stream_buffer(Duration::from_millis(100))
+        .build_stream()
+        .unwrap();
+    let status = decoder.status();
+
+    // Play until the first quarter runs out. The last, incomplete block stays
+    // with the decoding thread, and a few blocks may wait for rebuffering.
+    let available = (quarter - 44) / 2 - 4 * 2048;
+    let mut played = Vec::new();
+    while played.len() < available || !status.is_buffering() {
+        let buffering = status.is_buffering();
+        let sample = decoder.next().unwrap();
+        if buffering {
+            assert_eq!(sample, 0.0);
+            std::thread::sleep(Duration::from_micros(10));
+        } else {
+            played.push(sample);
+        }
+    }
+    assert!(status.underruns() >= 1);
+    assert!(!status.has_ended());
+
+    // The stalled decoder keeps playing silence.
+    for _ in 0..10_000 {
+        assert_eq!(decoder.next(), Some(0.0));
+    }
+
+    sender.send(data[quarter..].to_vec()).unwrap();
+    drop(sender);
+    played.extend(play(decoder));
+    assert!(played == decoded());
+}
+
+#[cfg(feature = "symphonia-mp3")]
+#[test]
+fn skips_damaged_data() {
+    let mut data = std::fs::read("assets/music.mp3").unwrap();
+    let middle = data.len() / 2;
+    data[middle..middle + 4096].fill(0xFF);
+    let expected = Decoder::try_from(std::fs::File::open("assets/music.mp3").unwrap())
+        .unwrap()
+        .count();
+
+    let (sender, reader) = network();
+    sender.send(data).unwrap();
+    drop(sender);
+    let played = play(StreamDecoder::new(reader).unwrap()).len();
+    assert!(played > expected * 9 / 10, "{played} of {expected}");
+}
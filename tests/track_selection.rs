@@ -0,0 +1,69 @@
+#![cfg(all(feature = "symphonia-aac", feature = "symphonia-isomp4"))]
+
+use std::fs::File;
+
+use rodio::decoder::DecoderError;
+use rodio::{Decoder, Source};
+
+fn open() -> File {
+    File::open("assets/music.m4a").unwrap()
+}
+
+fn byte_len() -> u64 {
+    open().metadata().unwrap().len()
+}
+
+#[test]
+fn lists_tracks() {
+    let decoder = Decoder::try_from(open()).unwrap();
+    let tracks = decoder.tracks();
+    assert_eq!(tracks.len(), 1);
+
+    let track = &tracks[0];
+    assert_eq!(decoder.track_id(), Some(track.id()));
+    assert_eq!(track.codec(), Some("aac"));
+    assert!(track.is_supported());
+    // The MP4 demuxer leaves the channel count to the AAC decoder.
+    assert!(track
+        .channels()
+        .is_none_or(|channels| channels == decoder.channels()));
+    assert_eq!(track.sample_rate(), Some(decoder.sample_rate()));
+}
+
+#[test]
+fn selects_track_by_id() {
+    let id = Decoder::try_from(open()).unwrap().tracks()[0].id();
+    let mut decoder = Decoder::builder()
+        .with_data(open())
+        .with_byte_len(byte_len())
+        .with_track(id)
+        .build()
+        .unwrap();
+    assert_eq!(decoder.track_id(), Some(id));
+    assert!(decoder.any(|sample| sample != 0.0));
+
+    let missing = Decoder::builder()
+        .with_data(open())
+        .with_byte_len(byte_len())
+        .with_track(id + 100)
+        .build();
+    assert!(matches!(missing, Err(DecoderError::NoSuchTrack)));
+}
+
+#[test]
+fn selects_track_with_selector() {
+    let decoder = Decoder::builder()
+        .with_data(open())
+        .with_byte_len(byte_len())
+        .with_track_selector(|track| track.codec() == Some("aac"))
+        .build()
+        .unwrap();
+    assert!(decoder.track_id().is_some());
+
+    let none = Decoder::builder()
+        .with_data(open())
+        .with_byte_len(byte_len())
+        .with_track_selector(|track| track.language() == Some("klingon"))
+        .build();
+    assert!(matches!(none, Err(DecoderError::NoSuchTrack)));
+}